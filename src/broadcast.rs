@@ -0,0 +1,124 @@
+use std::io;
+
+use agcli::{CommandError, CommandOutput, NextAction};
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::error::AppError;
+use crate::publish::read_json_input;
+use crate::relays::{WriteSession, relays_arg, reports_json};
+
+/// The command's usual result fields plus the full signed event.
+fn signed_result(event: &Event, mut result: serde_json::Value) -> serde_json::Value {
+    result["event"] = serde_json::to_value(event).unwrap_or_default();
+    result["signed_only"] = json!(true);
+    result
+}
+
+/// Output for `--sign-only`: nothing is sent; the event goes to `wokhei broadcast` later.
+pub fn signed_output(event: &Event, result: serde_json::Value) -> CommandOutput {
+    let actions = vec![NextAction::new(
+        "wokhei broadcast <signed-json-file-or-stdin> [--relay=<url>[,<url>...]]",
+        "Send this signed event unchanged (accepts this whole envelope)",
+    )];
+    CommandOutput::new(signed_result(event, result)).next_actions(actions)
+}
+
+/// Accept a bare signed event, `{"event": ...}`, or a whole `--sign-only` envelope.
+fn extract_signed_event(raw: &serde_json::Value) -> Result<Event, AppError> {
+    let candidate = raw
+        .pointer("/result/event")
+        .or_else(|| raw.get("event"))
+        .unwrap_or(raw);
+
+    let event: Event =
+        serde_json::from_value(candidate.clone()).map_err(|e| AppError::InvalidEvent {
+            reason: e.to_string(),
+        })?;
+    event.verify().map_err(|e| AppError::InvalidEvent {
+        reason: e.to_string(),
+    })?;
+    Ok(event)
+}
+
+pub async fn broadcast(relays: Vec<String>, input: String) -> Result<CommandOutput, CommandError> {
+    let json_str = read_json_input(&input, io::stdin())?;
+    let raw: serde_json::Value = serde_json::from_str(&json_str).map_err(|_| {
+        CommandError::from(AppError::InvalidJson {
+            reason: "Failed to parse JSON input".to_string(),
+        })
+    })?;
+    let event = extract_signed_event(&raw).map_err(CommandError::from)?;
+
+    let session = WriteSession::connect(&relays).await?;
+    let sent = session.send(&event).await;
+    session.disconnect().await;
+    let reports = sent?;
+
+    let relay = relays_arg(&relays);
+    let event_id = event.id.to_hex();
+    let result = json!({
+        "event_id": event_id,
+        "kind": event.kind.as_u16(),
+        "pubkey": event.pubkey.to_hex(),
+        "relays": reports_json(&reports),
+    });
+
+    let actions = vec![NextAction::new(
+        format!("wokhei inspect --relay={relay} {event_id}"),
+        "Inspect the broadcast event",
+    )];
+
+    Ok(CommandOutput::new(result).next_actions(actions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_event() -> Event {
+        EventBuilder::new(Kind::Custom(9998), "")
+            .tag(Tag::custom(TagKind::custom("names"), ["book", "books"]))
+            .sign_with_keys(&Keys::generate())
+            .unwrap()
+    }
+
+    #[test]
+    fn signed_result_embeds_full_event() {
+        let event = signed_event();
+        let j = signed_result(&event, json!({ "event_id": event.id.to_hex() }));
+        assert_eq!(j["signed_only"], true);
+        assert_eq!(j["event"]["id"], event.id.to_hex());
+        assert_eq!(j["event"]["sig"], event.sig.to_string());
+        assert_eq!(j["event"]["tags"][0][0], "names");
+    }
+
+    #[test]
+    fn extract_accepts_bare_event() {
+        let event = signed_event();
+        let raw = serde_json::to_value(&event).unwrap();
+        assert_eq!(extract_signed_event(&raw).unwrap().id, event.id);
+    }
+
+    #[test]
+    fn extract_accepts_sign_only_envelope() {
+        let event = signed_event();
+        let envelope = json!({ "ok": true, "result": signed_result(&event, json!({})) });
+        assert_eq!(extract_signed_event(&envelope).unwrap().id, event.id);
+    }
+
+    #[test]
+    fn extract_rejects_tampered_event() {
+        let mut raw = serde_json::to_value(signed_event()).unwrap();
+        raw["content"] = json!("tampered");
+        let err = extract_signed_event(&raw).unwrap_err();
+        assert_eq!(err.code(), "INVALID_EVENT");
+    }
+
+    #[test]
+    fn extract_rejects_unsigned_template() {
+        let raw = json!({ "kind": 9998, "content": "", "tags": [] });
+        let err = extract_signed_event(&raw).unwrap_err();
+        assert_eq!(err.code(), "INVALID_EVENT");
+    }
+}
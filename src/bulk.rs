@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::Path;
+
+use agcli::CommandError;
+
+use crate::error::AppError;
+
+/// One item from an `add-item --from` file.
+///
+/// `resource`, `content` and `d_tag` columns are special; every other column is a field.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemRow {
+    pub resource: String,
+    pub content: Option<String>,
+    pub d_tag: Option<String>,
+    pub fields: Vec<String>,
+}
+
+impl ItemRow {
+    /// Build a row from `(column, value)` pairs; empty values are skipped.
+    fn from_columns<'a>(
+        columns: impl IntoIterator<Item = (&'a str, String)>,
+    ) -> Result<Self, &'static str> {
+        let mut row = Self::default();
+        for (column, value) in columns {
+            if value.is_empty() {
+                continue;
+            }
+            match column {
+                "resource" => row.resource = value,
+                "content" => row.content = Some(value),
+                "d_tag" => row.d_tag = Some(value),
+                field => row.fields.push(format!("{field}={value}")),
+            }
+        }
+        if row.resource.is_empty() {
+            return Err("missing resource");
+        }
+        Ok(row)
+    }
+}
+
+/// Parsed rows in file order; a bad row keeps its own error.
+pub type ItemRows = Vec<Result<ItemRow, AppError>>;
+
+fn row_error(line: u64, reason: impl std::fmt::Display) -> AppError {
+    AppError::InvalidRow {
+        reason: format!("line {line}: {reason}"),
+    }
+}
+
+/// CSV with a header row naming the columns.
+fn parse_csv_rows(contents: &str) -> Result<ItemRows, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(contents.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| row_error(1, e))?
+        .iter()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    if !headers.iter().any(|h| h == "resource") {
+        return Err(row_error(1, "CSV header has no resource column"));
+    }
+
+    Ok(reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|e| {
+                row_error(e.position().map_or(0, csv::Position::line), e.to_string())
+            })?;
+            let line = record.position().map_or(0, csv::Position::line);
+            ItemRow::from_columns(
+                headers
+                    .iter()
+                    .map(String::as_str)
+                    .zip(record.iter().map(String::from)),
+            )
+            .map_err(|e| row_error(line, e))
+        })
+        .collect())
+}
+
+/// JSON Lines: one object per line; non-string values are written as JSON.
+fn parse_jsonl_rows(contents: &str) -> ItemRows {
+    contents
+        .lines()
+        .zip(1u64..)
+        .filter(|(line, _)| !line.trim().is_empty())
+        .map(|(line, n)| {
+            let value: serde_json::Value =
+                serde_json::from_str(line).map_err(|e| row_error(n, e))?;
+            let obj = value
+                .as_object()
+                .ok_or_else(|| row_error(n, "expected a JSON object"))?;
+            ItemRow::from_columns(obj.iter().map(|(k, v)| {
+                let value = v.as_str().map_or_else(|| v.to_string(), String::from);
+                (k.as_str(), if v.is_null() { String::new() } else { value })
+            }))
+            .map_err(|e| row_error(n, e))
+        })
+        .collect()
+}
+
+/// Read `--from=<file>`; the format comes from the extension (`.csv`, `.jsonl`, `.ndjson`).
+///
+/// The outer error is for an unreadable file; each row carries its own error.
+pub fn read_item_rows(path: &str) -> Result<ItemRows, CommandError> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+    let parse: fn(&str) -> Result<ItemRows, AppError> = match extension.as_deref() {
+        Some("csv") => parse_csv_rows,
+        Some("jsonl" | "ndjson") => |contents| Ok(parse_jsonl_rows(contents)),
+        _ => {
+            return Err(CommandError::new(
+                format!("--from={path} is not a .csv or .jsonl file"),
+                "INVALID_ARGS",
+                "Use a .csv file with a header row, or a .jsonl file with one object per line",
+            ));
+        }
+    };
+
+    let contents = fs::read_to_string(path).map_err(|_| {
+        CommandError::from(AppError::Io {
+            reason: format!("Failed to read {path}"),
+        })
+    })?;
+    let rows = parse(&contents).map_err(CommandError::from)?;
+    if rows.is_empty() {
+        return Err(CommandError::from(AppError::InvalidRow {
+            reason: format!("{path} has no item rows"),
+        }));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_maps_resource_content_d_tag_and_fields() {
+        let rows = parse_csv_rows("resource,title,d_tag,year\nhttps://a, Dune ,dune,\n").unwrap();
+        assert_eq!(
+            rows[0].as_ref().unwrap(),
+            &ItemRow {
+                resource: "https://a".into(),
+                content: None,
+                d_tag: Some("dune".into()),
+                fields: vec!["title=Dune".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn csv_without_resource_column_is_rejected() {
+        let err = parse_csv_rows("title\nDune\n").unwrap_err();
+        assert_eq!(err.code(), "INVALID_ROW");
+    }
+
+    #[test]
+    fn csv_row_errors_carry_line_numbers() {
+        let rows = parse_csv_rows("resource,title\nhttps://a,A\n,B\nhttps://c,C,extra\n").unwrap();
+        assert!(rows[0].is_ok());
+        assert_eq!(
+            rows[1].as_ref().unwrap_err().to_string(),
+            "Invalid item row: line 3: missing resource"
+        );
+        assert!(rows[2].as_ref().unwrap_err().to_string().contains("line 4"));
+    }
+
+    #[test]
+    fn jsonl_stringifies_non_string_values() {
+        let rows =
+            parse_jsonl_rows("{\"resource\":\"https://a\",\"year\":1965,\"note\":null}\n\n[1]\n");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].as_ref().unwrap().fields, vec!["year=1965"]);
+        assert!(rows[1].as_ref().unwrap_err().to_string().contains("line 3"));
+    }
+
+    #[test]
+    fn read_item_rows_rejects_unknown_extension() {
+        let err = read_item_rows("items.txt").unwrap_err();
+        assert_eq!(err.code, "INVALID_ARGS");
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::error::AppError;
+
+fn cache_path_from(base: &Path) -> PathBuf {
+    crate::keys::keys_dir_from(base).join("cache.jsonl")
+}
+
+/// Events no relay has returned for this long are dropped when the cache loads.
+const CACHE_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+/// At most this many events are kept; the ones confirmed longest ago go first.
+const MAX_CACHE_EVENTS: usize = 50_000;
+/// A confirmation newer than this isn't rewritten, so repeat queries don't rewrite the file.
+const CONFIRM_RESOLUTION_SECS: u64 = 24 * 60 * 60;
+
+/// How query commands use the local event cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Ask the relays and refresh the cache with what they return.
+    #[default]
+    Refresh,
+    /// Answer from the cache when it has matches, otherwise ask the relays.
+    CacheFirst,
+    /// Answer from the cache only; never connect.
+    Offline,
+}
+
+impl CacheMode {
+    /// The flag that selects this mode, for `next_actions` commands.
+    pub fn flag(self) -> Option<&'static str> {
+        match self {
+            Self::Refresh => None,
+            Self::CacheFirst => Some("--cache-first"),
+            Self::Offline => Some("--offline"),
+        }
+    }
+}
+
+/// Header and item events seen by query commands, stored in `~/.wokhei/cache.jsonl` as one
+/// `{"event": ..., "relays": [...], "seen": <unix secs>}` line per event, where `seen` is
+/// when a relay last returned it.
+#[derive(Debug, Default)]
+pub struct EventCache {
+    entries: HashMap<EventId, (Event, Vec<String>, u64)>,
+    dirty: bool,
+    /// Home directory the cache was loaded from and saves to; `None` keeps it in memory.
+    base: Option<PathBuf>,
+}
+
+impl EventCache {
+    /// A missing cache file is an empty cache; unreadable lines are dropped, and so are
+    /// events past `CACHE_TTL_SECS` or `MAX_CACHE_EVENTS`.
+    pub fn load_from(base: &Path) -> Self {
+        let now = Timestamp::now().as_secs();
+        let entries = fs::read_to_string(cache_path_from(base))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let entry: serde_json::Value = serde_json::from_str(line).ok()?;
+                let event = Event::from_json(entry["event"].to_string()).ok()?;
+                let relays = entry["relays"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|r| r.as_str().map(String::from))
+                    .collect();
+                let seen = entry["seen"].as_u64().unwrap_or(now);
+                Some((event.id, (event, relays, seen)))
+            })
+            .collect();
+        let mut cache = Self {
+            entries,
+            dirty: false,
+            base: Some(base.to_path_buf()),
+        };
+        cache.prune(now, MAX_CACHE_EVENTS);
+        cache
+    }
+
+    pub fn load() -> Self {
+        Self::load_from(&crate::keys::home_base())
+    }
+
+    /// Drop events not confirmed within `CACHE_TTL_SECS` of `now`, then the least recently
+    /// confirmed beyond `max_events`.
+    fn prune(&mut self, now: u64, max_events: usize) {
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, (_, _, seen)| now.saturating_sub(*seen) <= CACHE_TTL_SECS);
+        if self.entries.len() > max_events {
+            let mut by_seen: Vec<(u64, EventId)> = self
+                .entries
+                .iter()
+                .map(|(id, (_, _, seen))| (*seen, *id))
+                .collect();
+            by_seen.sort_unstable();
+            let excess = self.entries.len() - max_events;
+            for (_, id) in &by_seen[..excess] {
+                self.entries.remove(id);
+            }
+        }
+        self.dirty |= self.entries.len() != before;
+    }
+
+    /// Write the cache back to where it was loaded from, if anything changed.
+    pub fn save(&self) -> Result<(), AppError> {
+        let Some(base) = self.base.as_deref().filter(|_| self.dirty) else {
+            return Ok(());
+        };
+        let path = cache_path_from(base);
+        let io = |e: std::io::Error| AppError::Io {
+            reason: format!("Failed to write {}: {e}", path.display()),
+        };
+        fs::create_dir_all(crate::keys::keys_dir_from(base)).map_err(io)?;
+        let contents =
+            self.entries
+                .values()
+                .fold(String::new(), |mut out, (event, relays, seen)| {
+                    let line = json!({ "event": event, "relays": relays, "seen": seen });
+                    let _ = writeln!(out, "{line}");
+                    out
+                });
+        fs::write(&path, contents).map_err(io)
+    }
+
+    /// Remember that `relay` returned `event` just now.
+    pub fn record(&mut self, relay: &str, event: &Event) {
+        let now = Timestamp::now().as_secs();
+        let (_, relays, seen) = self
+            .entries
+            .entry(event.id)
+            .or_insert_with(|| (event.clone(), Vec::new(), now));
+        if !relays.iter().any(|r| r == relay) {
+            relays.push(relay.to_string());
+            self.dirty = true;
+        }
+        if now.saturating_sub(*seen) >= CONFIRM_RESOLUTION_SECS {
+            *seen = now;
+            self.dirty = true;
+        }
+    }
+
+    /// Forget that `relay` has the event `id`; events no relay has are dropped.
+    pub fn forget(&mut self, relay: &str, id: &EventId) {
+        let Some((_, relays, _)) = self.entries.get_mut(id) else {
+            return;
+        };
+        relays.retain(|r| r != relay);
+        if relays.is_empty() {
+            self.entries.remove(id);
+        }
+        self.dirty = true;
+    }
+
+    /// Cached events matching `filter` that were seen on any of `relays`, each with the
+    /// subset of `relays` it was seen on. A filter `limit` keeps only the newest matches.
+    pub fn matching(&self, relays: &[String], filter: &Filter) -> Vec<(&Event, Vec<String>)> {
+        let mut matches: Vec<(&Event, Vec<String>)> = self
+            .entries
+            .values()
+            .filter(|(event, _, _)| filter.match_event(event, MatchEventOptions::new()))
+            .map(|(event, seen_on, _)| {
+                let on: Vec<String> = relays
+                    .iter()
+                    .filter(|r| seen_on.contains(r))
+                    .cloned()
+                    .collect();
+                (event, on)
+            })
+            .filter(|(_, on)| !on.is_empty())
+            .collect();
+        matches.sort_by(|(a, _), (b, _)| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+        if let Some(limit) = filter.limit {
+            matches.truncate(limit);
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn header(tag: &str, secs: u64) -> Event {
+        EventBuilder::new(Kind::Custom(9998), "")
+            .tags([Tag::hashtag(tag)])
+            .custom_created_at(Timestamp::from_secs(secs))
+            .sign_with_keys(&Keys::generate())
+            .unwrap()
+    }
+
+    fn relays(urls: &[&str]) -> Vec<String> {
+        urls.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn matching_applies_filter_relays_and_limit() {
+        let mut cache = EventCache::default();
+        let (old, new, other) = (header("jazz", 1), header("jazz", 2), header("rock", 3));
+        cache.record("ws://a", &old);
+        cache.record("ws://b", &old);
+        cache.record("ws://a", &new);
+        cache.record("ws://a", &other);
+
+        let jazz = Filter::new().kind(Kind::Custom(9998)).hashtag("jazz");
+        let found = cache.matching(&relays(&["ws://b", "ws://a"]), &jazz);
+        let ids: Vec<EventId> = found.iter().map(|(e, _)| e.id).collect();
+        assert_eq!(ids, vec![new.id, old.id]);
+        assert_eq!(found[1].1, relays(&["ws://b", "ws://a"]));
+
+        assert_eq!(cache.matching(&relays(&["ws://b"]), &jazz).len(), 1);
+        assert_eq!(
+            cache.matching(&relays(&["ws://a"]), &jazz.limit(1))[0].0.id,
+            new.id
+        );
+    }
+
+    #[test]
+    fn forget_drops_events_no_relay_has() {
+        let mut cache = EventCache::default();
+        let event = header("jazz", 1);
+        cache.record("ws://a", &event);
+        cache.record("ws://b", &event);
+        cache.forget("ws://a", &event.id);
+        assert_eq!(cache.entries[&event.id].1, relays(&["ws://b"]));
+        cache.forget("ws://b", &event.id);
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn cache_round_trips_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = EventCache::load_from(dir.path());
+        assert!(cache.entries.is_empty());
+
+        let event = header("jazz", 5);
+        cache.record("ws://a", &event);
+        cache.record("ws://b", &event);
+        cache.save().unwrap();
+
+        let loaded = EventCache::load_from(dir.path());
+        let (loaded_event, seen_on, _) = &loaded.entries[&event.id];
+        assert_eq!(
+            (loaded_event, seen_on),
+            (&event, &relays(&["ws://a", "ws://b"]))
+        );
+        assert!(!loaded.dirty);
+    }
+
+    #[test]
+    fn prune_drops_expired_then_least_recently_confirmed_events() {
+        let mut cache = EventCache::default();
+        let now = CACHE_TTL_SECS * 2;
+        let mut seen_at = |secs: u64| {
+            let event = header("jazz", secs);
+            cache
+                .entries
+                .insert(event.id, (event.clone(), relays(&["ws://a"]), secs));
+            event.id
+        };
+        let expired = seen_at(now - CACHE_TTL_SECS - 1);
+        let oldest = seen_at(now - CACHE_TTL_SECS);
+        let (older, newest) = (seen_at(now - 10), seen_at(now));
+        cache.prune(now, 2);
+        let kept: HashSet<EventId> = cache.entries.keys().copied().collect();
+        assert_eq!(kept, HashSet::from([older, newest]));
+        assert!(!kept.contains(&expired) && !kept.contains(&oldest));
+        assert!(cache.dirty);
+
+        cache.dirty = false;
+        cache.prune(now, 2);
+        assert!(!cache.dirty);
+    }
+
+    #[test]
+    fn stale_confirmations_are_refreshed_by_record() {
+        let mut cache = EventCache::default();
+        let event = header("jazz", 1);
+        cache
+            .entries
+            .insert(event.id, (event.clone(), relays(&["ws://a"]), 0));
+        cache.record("ws://a", &event);
+        assert!(cache.entries[&event.id].2 > 0);
+        assert!(cache.dirty);
+
+        cache.dirty = false;
+        cache.record("ws://a", &event);
+        assert!(!cache.dirty);
+    }
+
+    #[test]
+    fn save_reports_an_unwritable_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(crate::keys::keys_dir_from(dir.path()), "not a directory").unwrap();
+        let mut cache = EventCache::load_from(dir.path());
+        cache.record("ws://a", &header("jazz", 1));
+        assert_eq!(cache.save().unwrap_err().code(), "IO_ERROR");
+
+        let mut in_memory = EventCache::default();
+        in_memory.record("ws://a", &header("jazz", 1));
+        assert!(in_memory.save().is_ok());
+    }
+}
@@ -3,19 +3,18 @@ use serde_json::json;
 
 use agcli::{CommandError, CommandOutput, NextAction};
 
+use crate::broadcast::signed_output;
 use crate::error::AppError;
-use crate::keys::load_keys;
+use crate::relays::{WriteSession, relays_arg, reports_json};
+use crate::signer::{Signer, SignerSpec};
 
 pub async fn delete(
-    relay: String,
+    relays: Vec<String>,
+    signer: &SignerSpec,
     event_id_strs: Vec<String>,
+    sign_only: bool,
 ) -> Result<CommandOutput, CommandError> {
-    let keys = load_keys().map_err(|e| {
-        CommandError::from(e).next_actions(vec![NextAction::new(
-            "wokhei init --generate",
-            "Generate a keypair first",
-        )])
-    })?;
+    let signer = Signer::load(signer)?;
 
     let event_ids: Vec<EventId> = event_id_strs
         .iter()
@@ -25,42 +24,40 @@ pub async fn delete(
         })
         .collect::<Result<_, CommandError>>()?;
 
-    let client = Client::builder().signer(keys).build();
-    if client.add_relay(&relay).await.is_err() {
-        return Err(CommandError::from(AppError::RelayUnreachable {
-            url: relay.clone(),
-        }));
-    }
-    client.connect().await;
-
     let mut request = EventDeletionRequest::new();
     for id in event_ids {
         request = request.id(id);
     }
     let builder = EventBuilder::delete(request);
+    let event = signer.sign(builder).await;
+    signer.shutdown().await;
+    let event = event.map_err(CommandError::from)?;
+
+    if sign_only {
+        let result = json!({
+            "deletion_event_id": event.id.to_hex(),
+            "deleted_ids": event_id_strs,
+        });
+        return Ok(signed_output(&event, result));
+    }
 
-    let result = match client.send_event_builder(builder).await {
-        Ok(output) => {
-            let deletion_id = output.val.to_hex();
-
-            let result = json!({
-                "deletion_event_id": deletion_id,
-                "deleted_ids": event_id_strs,
-                "note": "NIP-09: deletion is a REQUEST — relays may or may not honor it"
-            });
-
-            let actions = vec![NextAction::new(
-                format!("wokhei list-headers --relay={relay}"),
-                "List headers to verify deletion",
-            )];
-
-            Ok(CommandOutput::new(result).next_actions(actions))
-        }
-        Err(e) => Err(CommandError::from(AppError::RelayRejected {
-            reason: e.to_string(),
-        })),
-    };
-
-    client.disconnect().await;
-    result
+    let session = WriteSession::connect(&relays).await?;
+    let sent = session.send(&event).await;
+    session.disconnect().await;
+    let reports = sent?;
+
+    let relay = relays_arg(&relays);
+    let result = json!({
+        "deletion_event_id": event.id.to_hex(),
+        "deleted_ids": event_id_strs,
+        "relays": reports_json(&reports),
+        "note": "NIP-09: deletion is a REQUEST — relays may or may not honor it"
+    });
+
+    let actions = vec![NextAction::new(
+        format!("wokhei list-headers --relay={relay}"),
+        "List headers to verify deletion",
+    )];
+
+    Ok(CommandOutput::new(result).next_actions(actions))
 }
@@ -47,6 +47,65 @@ pub enum AppError {
 
     #[error("Event not found: {event_id}")]
     EventNotFound { event_id: String },
+
+    #[error("Failed to sign event: {reason}")]
+    SigningFailed { reason: String },
+
+    #[error("Passphrase required to {action}")]
+    PassphraseRequired { action: String },
+
+    #[error("Failed to decrypt ncryptsec: {reason}")]
+    DecryptionFailed { reason: String },
+
+    #[error("Invalid profile name: {name}")]
+    InvalidProfile { name: String },
+
+    #[error("Invalid signer: {uri}")]
+    InvalidSigner { uri: String },
+
+    #[error("Remote signer unavailable: {reason}")]
+    SignerUnavailable { reason: String },
+
+    #[error("Invalid signed event: {reason}")]
+    InvalidEvent { reason: String },
+
+    #[error("Invalid item row: {reason}")]
+    InvalidRow { reason: String },
+
+    #[error("DCoSL rule violation: {details}")]
+    RuleViolation { rule: &'static str, details: String },
+
+    #[error("Missing fields required by the header: {}", fields.join(", "))]
+    MissingRequiredFields { fields: Vec<String> },
+
+    #[error("{coordinate} was published by another key")]
+    NotAuthor { coordinate: String },
+
+    #[error("{invalid} of {total} list items break the header schema: {details}")]
+    ListInvalid {
+        invalid: usize,
+        total: usize,
+        details: String,
+    },
+
+    #[error("{url} returned {returned} events created at {created_at} but may hold more")]
+    RelayLimitCapped {
+        url: String,
+        created_at: u64,
+        returned: usize,
+    },
+
+    #[error("{coordinate} already holds a different list")]
+    CoordinateTaken { coordinate: String },
+}
+
+/// A `nostrconnect://` URI fails when it wasn't made for the profile's session key.
+fn signer_fix(uri: &str) -> String {
+    if uri.starts_with("nostrconnect://") {
+        "Pair with the URI from `wokhei nostrconnect-uri` for the same --profile".to_string()
+    } else {
+        "Use --signer=bunker://<remote-signer-pubkey>?relay=<url>[&secret=<token>] or a nostrconnect:// URI from `wokhei nostrconnect-uri`".to_string()
+    }
 }
 
 impl AppError {
@@ -67,11 +126,28 @@ impl AppError {
             Self::Io { .. } => "IO_ERROR",
             Self::InvalidJson { .. } => "INVALID_JSON",
             Self::EventNotFound { .. } => "EVENT_NOT_FOUND",
+            Self::SigningFailed { .. } => "SIGNING_FAILED",
+            Self::PassphraseRequired { .. } => "PASSPHRASE_REQUIRED",
+            Self::DecryptionFailed { .. } => "DECRYPTION_FAILED",
+            Self::InvalidProfile { .. } => "INVALID_PROFILE",
+            Self::InvalidSigner { .. } => "INVALID_SIGNER",
+            Self::SignerUnavailable { .. } => "SIGNER_UNAVAILABLE",
+            Self::InvalidEvent { .. } => "INVALID_EVENT",
+            Self::InvalidRow { .. } => "INVALID_ROW",
+            Self::RuleViolation { rule, .. } => rule,
+            Self::MissingRequiredFields { .. } => "MISSING_REQUIRED_FIELDS",
+            Self::ListInvalid { .. } => "LIST_INVALID",
+            Self::NotAuthor { .. } => "NOT_AUTHOR",
+            Self::RelayLimitCapped { .. } => "RELAY_LIMIT_CAPPED",
+            Self::CoordinateTaken { .. } => "COORDINATE_TAKEN",
         }
     }
 
     pub fn retryable(&self) -> bool {
-        matches!(self, Self::RelayUnreachable { .. })
+        matches!(
+            self,
+            Self::RelayUnreachable { .. } | Self::SignerUnavailable { .. }
+        )
     }
 
     pub fn fix(&self) -> String {
@@ -119,6 +195,57 @@ impl AppError {
                 "Verify the event ID, or use `wokhei list-headers` to find valid events"
                     .to_string()
             }
+            Self::SigningFailed { .. } => {
+                "Check your identity with `wokhei whoami`".to_string()
+            }
+            Self::PassphraseRequired { .. } => {
+                "Set WOKHEI_PASSPHRASE, or WOKHEI_PASSPHRASE_FILE=<path> to a file containing it"
+                    .to_string()
+            }
+            Self::DecryptionFailed { .. } => {
+                "Check the passphrase in WOKHEI_PASSPHRASE / WOKHEI_PASSPHRASE_FILE".to_string()
+            }
+            Self::InvalidProfile { .. } => {
+                "Use lowercase letters, digits, '-' or '_' (max 64 chars), e.g. --profile=curator"
+                    .to_string()
+            }
+            Self::InvalidSigner { uri } => signer_fix(uri),
+            Self::SignerUnavailable { .. } => {
+                "Check the bunker is online and has approved this client, then retry".to_string()
+            }
+            Self::InvalidEvent { .. } => {
+                "Provide a complete signed event (id, pubkey, sig), e.g. the output of --sign-only"
+                    .to_string()
+            }
+            Self::InvalidRow { .. } => {
+                "Give every row a resource column; other columns become item fields".to_string()
+            }
+            Self::RuleViolation { .. } => {
+                "Fix the listed tags or fields (see DCoSL Validation Rules in SKILL.md); publish also accepts --no-validate"
+                    .to_string()
+            }
+            Self::MissingRequiredFields { fields } => {
+                let example: Vec<String> = fields.iter().map(|f| format!("{f}=<value>")).collect();
+                format!(
+                    "Add them with --fields={} (or as columns of the --from file)",
+                    example.join(",")
+                )
+            }
+            Self::NotAuthor { .. } => {
+                "Only the author can replace an addressable event; use the author's --profile or --signer"
+                    .to_string()
+            }
+            Self::ListInvalid { .. } => {
+                "Fix the listed items (add-item with the same --d-tag replaces an addressable item) or delete them with `wokhei delete <event-id>`"
+                    .to_string()
+            }
+            Self::RelayLimitCapped { .. } => {
+                "Query a relay that supports NIP-77 negentropy or NIP-45 COUNT, or raise this relay's max filter limit (strfry: relay.maxFilterLimit)"
+                    .to_string()
+            }
+            Self::CoordinateTaken { .. } => {
+                "Pass --d-tag=<id> to migrate to a coordinate that is not in use".to_string()
+            }
         }
     }
 }
@@ -208,6 +335,37 @@ mod tests {
             AppError::EventNotFound {
                 event_id: "e".into(),
             },
+            AppError::SigningFailed { reason: "r".into() },
+            AppError::PassphraseRequired { action: "a".into() },
+            AppError::DecryptionFailed { reason: "r".into() },
+            AppError::InvalidProfile { name: "n".into() },
+            AppError::InvalidSigner { uri: "u".into() },
+            AppError::SignerUnavailable { reason: "r".into() },
+            AppError::InvalidEvent { reason: "r".into() },
+            AppError::InvalidRow { reason: "r".into() },
+            AppError::RuleViolation {
+                rule: "MISSING_NAMES",
+                details: "d".into(),
+            },
+            AppError::MissingRequiredFields {
+                fields: vec!["f".into()],
+            },
+            AppError::NotAuthor {
+                coordinate: "c".into(),
+            },
+            AppError::ListInvalid {
+                invalid: 1,
+                total: 1,
+                details: "d".into(),
+            },
+            AppError::RelayLimitCapped {
+                url: "u".into(),
+                created_at: 1,
+                returned: 1,
+            },
+            AppError::CoordinateTaken {
+                coordinate: "c".into(),
+            },
         ];
         for v in variants {
             assert!(!v.fix().is_empty(), "fix() empty for {}", v.code());
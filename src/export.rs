@@ -0,0 +1,393 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+
+use agcli::CommandError;
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::error::AppError;
+use crate::query::{build_header_filter, event_to_json};
+
+/// File layout for `export --output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One raw signed event per line, headers before their items.
+    Jsonl,
+    /// The same `{relay, exported_at, headers: [...]}` tree `export` prints, written incrementally.
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Result<Self, CommandError> {
+        match value {
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            "json" => Ok(Self::Json),
+            other => Err(CommandError::new(
+                format!("unknown export format: {other}"),
+                "INVALID_ARGS",
+                "Use --format=jsonl (one signed event per line) or --format=json (backup tree)",
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Which lists `export` includes. Empty = every header on the relay.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportScope {
+    pub author: Option<String>,
+    pub tag: Option<String>,
+    pub name: Option<String>,
+    pub header: Option<String>,
+    pub header_coordinate: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+}
+
+impl ExportScope {
+    /// Relay filter for the headers; `--name` and the time range are applied afterwards.
+    pub fn header_filter(&self) -> Result<Filter, CommandError> {
+        let mut filter = build_header_filter(self.author.as_ref(), self.tag.as_ref())?;
+        if let Some(id_str) = &self.header {
+            let id = EventId::parse(id_str)
+                .map_err(|_| CommandError::from(AppError::InvalidEventId { id: id_str.clone() }))?;
+            filter = filter.id(id);
+        }
+        if let Some(coord) = &self.header_coordinate {
+            let (kind, pubkey, d_tag) = dcosl_core::item::parse_coordinate_str(coord)
+                .map_err(|e| CommandError::from(AppError::from(e)))?;
+            if kind != 39998 {
+                return Err(CommandError::from(AppError::InvalidCoordinate {
+                    input: coord.clone(),
+                }));
+            }
+            filter = filter
+                .kind(Kind::Custom(39998))
+                .author(pubkey)
+                .identifier(d_tag);
+        }
+        Ok(filter)
+    }
+
+    /// Restrict an item filter to `--since`/`--until`.
+    pub fn item_filter(&self, mut filter: Filter) -> Filter {
+        if let Some(since) = self.since {
+            filter = filter.since(Timestamp::from_secs(since));
+        }
+        if let Some(until) = self.until {
+            filter = filter.until(Timestamp::from_secs(until));
+        }
+        filter
+    }
+
+    /// Case-insensitive `--name` substring match on the header's singular name.
+    pub fn matches_name(&self, header: &Event) -> bool {
+        let Some(needle) = &self.name else {
+            return true;
+        };
+        let needle = needle.to_lowercase();
+        header.tags.iter().map(Tag::as_slice).any(|t| {
+            t.first().map(String::as_str) == Some("names")
+                && t.get(1).is_some_and(|n| n.to_lowercase().contains(&needle))
+        })
+    }
+
+    pub fn in_range(&self, created_at: Timestamp) -> bool {
+        let secs = created_at.as_secs();
+        self.since.is_none_or(|s| secs >= s) && self.until.is_none_or(|u| secs <= u)
+    }
+
+    /// A header is kept if it is in range, or still has items in range.
+    pub fn keeps(&self, header: &Event, items_in_range: usize) -> bool {
+        self.in_range(header.created_at) || items_in_range > 0
+    }
+
+    /// The scope flags that were set, for the result envelope.
+    pub fn to_json(&self) -> Option<serde_json::Value> {
+        (*self != Self::default()).then(|| {
+            let mut scope = json!({});
+            for (key, value) in [
+                ("author", self.author.as_ref().map(|v| json!(v))),
+                ("tag", self.tag.as_ref().map(|v| json!(v))),
+                ("name", self.name.as_ref().map(|v| json!(v))),
+                ("header", self.header.as_ref().map(|v| json!(v))),
+                (
+                    "header_coordinate",
+                    self.header_coordinate.as_ref().map(|v| json!(v)),
+                ),
+                ("since", self.since.map(|v| json!(v))),
+                ("until", self.until.map(|v| json!(v))),
+            ] {
+                if let Some(value) = value {
+                    scope[key] = value;
+                }
+            }
+            scope
+        })
+    }
+}
+
+/// `export --output=<path> --format=...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportFile {
+    pub path: String,
+    pub format: ExportFormat,
+}
+
+fn io_error(path: &str, e: &std::io::Error) -> AppError {
+    AppError::Io {
+        reason: format!("Failed to write {path}: {e}"),
+    }
+}
+
+/// Writes each header and its items as soon as they are fetched, so memory stays at one list.
+pub struct ExportWriter {
+    out: BufWriter<File>,
+    path: String,
+    format: ExportFormat,
+    headers: usize,
+    items: usize,
+}
+
+impl ExportWriter {
+    pub fn create(file: &ExportFile, relay: &str, exported_at: u64) -> Result<Self, AppError> {
+        let out = File::create(&file.path).map_err(|e| io_error(&file.path, &e))?;
+        let mut writer = Self::new(out, file);
+        if writer.format == ExportFormat::Json {
+            let relay = json!(relay);
+            writer.write(&format!(
+                "{{\"relay\":{relay},\"exported_at\":{exported_at},\"headers\":["
+            ))?;
+        }
+        Ok(writer)
+    }
+
+    /// Continue an existing JSON Lines export (`--sync`).
+    pub fn append(file: &ExportFile) -> Result<Self, AppError> {
+        let out = OpenOptions::new()
+            .append(true)
+            .open(&file.path)
+            .map_err(|e| io_error(&file.path, &e))?;
+        Ok(Self::new(out, file))
+    }
+
+    fn new(out: File, file: &ExportFile) -> Self {
+        Self {
+            out: BufWriter::new(out),
+            path: file.path.clone(),
+            format: file.format,
+            headers: 0,
+            items: 0,
+        }
+    }
+
+    fn write(&mut self, s: &str) -> Result<(), AppError> {
+        self.out
+            .write_all(s.as_bytes())
+            .map_err(|e| io_error(&self.path, &e))
+    }
+
+    fn write_event_line(&mut self, event: &Event) -> Result<(), AppError> {
+        let line = serde_json::to_string(event).map_err(|e| AppError::InvalidJson {
+            reason: e.to_string(),
+        })?;
+        self.write(&line)?;
+        self.write("\n")
+    }
+
+    /// JSON Lines only: a header (if it changed) and just the new items of its list.
+    pub fn write_changes(
+        &mut self,
+        header: Option<&Event>,
+        items: &[&Event],
+    ) -> Result<(), AppError> {
+        if let Some(header) = header {
+            self.write_event_line(header)?;
+            self.headers += 1;
+        }
+        for item in items {
+            self.write_event_line(item)?;
+        }
+        self.items += items.len();
+        Ok(())
+    }
+
+    pub fn write_list(&mut self, header: &Event, items: &[Event]) -> Result<(), AppError> {
+        match self.format {
+            ExportFormat::Jsonl => {
+                self.write_event_line(header)?;
+                for item in items {
+                    self.write_event_line(item)?;
+                }
+            }
+            ExportFormat::Json => {
+                if self.headers > 0 {
+                    self.write(",")?;
+                }
+                let entry = json!({
+                    "header": event_to_json(header),
+                    "items_count": items.len(),
+                    "items": items.iter().map(event_to_json).collect::<Vec<_>>(),
+                });
+                self.write(&entry.to_string())?;
+            }
+        }
+        self.headers += 1;
+        self.items += items.len();
+        Ok(())
+    }
+
+    /// Close the document and flush; returns the `(headers, items)` written.
+    pub fn finish(mut self) -> Result<(usize, usize), AppError> {
+        if self.format == ExportFormat::Json {
+            let counts = json!({ "headers": self.headers, "items": self.items });
+            self.write(&format!("],\"counts\":{counts}}}\n"))?;
+        }
+        self.out.flush().map_err(|e| io_error(&self.path, &e))?;
+        Ok((self.headers, self.items))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed(kind: u16, keys: &Keys) -> Event {
+        EventBuilder::new(Kind::Custom(kind), "")
+            .tag(Tag::identifier("x"))
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    fn export_to(format: ExportFormat) -> (String, Event, Event) {
+        let dir = tempfile::tempdir().unwrap();
+        let file = ExportFile {
+            path: dir.path().join("out").display().to_string(),
+            format,
+        };
+        let keys = Keys::generate();
+        let (header, item) = (signed(39998, &keys), signed(39999, &keys));
+
+        let mut writer = ExportWriter::create(&file, "ws://r", 42).unwrap();
+        writer
+            .write_list(&header, std::slice::from_ref(&item))
+            .unwrap();
+        writer.write_list(&header, &[]).unwrap();
+        assert_eq!(writer.finish().unwrap(), (2, 1));
+        (std::fs::read_to_string(&file.path).unwrap(), header, item)
+    }
+
+    fn header_named(name: &str, created_at: u64) -> Event {
+        EventBuilder::new(Kind::Custom(9998), "")
+            .tag(Tag::custom(TagKind::custom("names"), [name, "plural"]))
+            .custom_created_at(Timestamp::from_secs(created_at))
+            .sign_with_keys(&Keys::generate())
+            .unwrap()
+    }
+
+    #[test]
+    fn empty_scope_keeps_everything() {
+        let scope = ExportScope::default();
+        assert!(scope.matches_name(&header_named("a", 1)));
+        assert!(scope.keeps(&header_named("a", 1), 0));
+        assert!(scope.to_json().is_none());
+    }
+
+    #[test]
+    fn scope_name_is_case_insensitive_substring() {
+        let scope = ExportScope {
+            name: Some("PLAY".into()),
+            ..ExportScope::default()
+        };
+        assert!(scope.matches_name(&header_named("playlist", 1)));
+        assert!(!scope.matches_name(&header_named("books", 1)));
+    }
+
+    #[test]
+    fn scope_keeps_old_header_only_with_items_in_range() {
+        let scope = ExportScope {
+            since: Some(100),
+            until: Some(200),
+            ..ExportScope::default()
+        };
+        assert!(scope.keeps(&header_named("a", 150), 0));
+        assert!(!scope.keeps(&header_named("a", 50), 0));
+        assert!(scope.keeps(&header_named("a", 50), 3));
+        assert_eq!(scope.to_json(), Some(json!({ "since": 100, "until": 200 })));
+    }
+
+    #[test]
+    fn scope_rejects_bad_header_refs() {
+        let bad_id = ExportScope {
+            header: Some("nope".into()),
+            ..ExportScope::default()
+        };
+        assert_eq!(bad_id.header_filter().unwrap_err().code, "INVALID_EVENT_ID");
+
+        let item_coord = ExportScope {
+            header_coordinate: Some(format!("39999:{}:x", Keys::generate().public_key())),
+            ..ExportScope::default()
+        };
+        assert_eq!(
+            item_coord.header_filter().unwrap_err().code,
+            "INVALID_COORDINATE"
+        );
+    }
+
+    #[test]
+    fn append_continues_jsonl_without_rewriting() {
+        let (contents, header, item) = export_to(ExportFormat::Jsonl);
+        let dir = tempfile::tempdir().unwrap();
+        let file = ExportFile {
+            path: dir.path().join("out").display().to_string(),
+            format: ExportFormat::Jsonl,
+        };
+        std::fs::write(&file.path, &contents).unwrap();
+
+        let mut writer = ExportWriter::append(&file).unwrap();
+        writer.write_changes(None, &[&item]).unwrap();
+        writer.write_changes(Some(&header), &[]).unwrap();
+        assert_eq!(writer.finish().unwrap(), (1, 1));
+        assert_eq!(
+            std::fs::read_to_string(&file.path).unwrap().lines().count(),
+            5
+        );
+    }
+
+    #[test]
+    fn format_accepts_ndjson_alias() {
+        assert_eq!(ExportFormat::parse("ndjson").unwrap(), ExportFormat::Jsonl);
+        assert_eq!(ExportFormat::parse("csv").unwrap_err().code, "INVALID_ARGS");
+    }
+
+    #[test]
+    fn jsonl_writes_one_signed_event_per_line() {
+        let (contents, header, item) = export_to(ExportFormat::Jsonl);
+        let events: Vec<Event> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], header);
+        assert_eq!(events[1], item);
+        assert!(events.iter().all(|e| e.verify().is_ok()));
+    }
+
+    #[test]
+    fn json_writes_the_export_tree() {
+        let (contents, header, _) = export_to(ExportFormat::Json);
+        let tree: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(tree["relay"], "ws://r");
+        assert_eq!(tree["exported_at"], 42);
+        assert_eq!(tree["counts"], json!({ "headers": 2, "items": 1 }));
+        assert_eq!(tree["headers"][0]["header"]["event_id"], header.id.to_hex());
+        assert_eq!(tree["headers"][0]["items_count"], 1);
+        assert_eq!(tree["headers"][1]["items"], json!([]));
+    }
+}
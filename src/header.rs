@@ -2,15 +2,20 @@ use agcli::{CommandError, CommandOutput, NextAction};
 use nostr_sdk::prelude::*;
 use serde_json::json;
 
+use crate::broadcast::signed_output;
 use crate::error::AppError;
-use crate::keys::load_keys;
+use crate::query::{fetch_current_addressable, replacement_created_at};
+use crate::relays::{WriteSession, relays_arg, reports_json};
+use crate::signer::{Signer, SignerSpec};
+use crate::validate::{check_event, reject, tag_diff, tags_json, violations_json};
 
 // Re-export from dcosl-core for use in other wokhei modules
 pub use dcosl_core::header::HeaderParams as DcoslHeaderParams;
 pub use dcosl_core::header::build_header_tags as dcosl_build_header_tags;
 
 pub struct HeaderParams {
-    pub relay: String,
+    pub relays: Vec<String>,
+    pub signer: SignerSpec,
     pub name: String,
     pub plural_name: String,
     pub titles: Vec<String>,
@@ -21,6 +26,8 @@ pub struct HeaderParams {
     pub alt: Option<String>,
     pub addressable: bool,
     pub d_tag: Option<String>,
+    pub sign_only: bool,
+    pub dry_run: bool,
 }
 
 impl HeaderParams {
@@ -44,92 +51,374 @@ fn build_header_tags(params: &HeaderParams) -> Vec<Tag> {
     dcosl_build_header_tags(&params.to_dcosl_params())
 }
 
+fn header_kind(addressable: bool) -> Kind {
+    if addressable {
+        Kind::Custom(39998)
+    } else {
+        Kind::Custom(9998)
+    }
+}
+
+/// `--dry-run`: the unsigned header exactly as it would be published, plus `DCoSL` rule
+/// violations. Needs no relay; the auto d-tag is previewed only if a local key is available.
+fn dry_run_header(mut params: HeaderParams, pubkey: Option<PublicKey>) -> serde_json::Value {
+    let kind = header_kind(params.addressable);
+    let d_pending = params.addressable && params.d_tag.is_none() && pubkey.is_none();
+    if params.addressable
+        && params.d_tag.is_none()
+        && let Some(pk) = pubkey
+    {
+        params.d_tag = Some(crate::dtag::header_dtag(&params.name, &pk.to_hex()));
+    }
+
+    let tags = build_header_tags(&params);
+    let mut violations = check_event(kind.as_u16(), &tags);
+    if d_pending {
+        violations.retain(|v| v.rule != "MISSING_D_TAG");
+    }
+
+    let mut result = json!({
+        "dry_run": true,
+        "kind": kind.as_u16(),
+        "pubkey": pubkey.map(|pk| pk.to_hex()),
+        "d_tag": params.d_tag,
+        "content": "",
+        "tags": tags_json(&tags),
+        "valid": violations.is_empty(),
+        "violations": violations_json(&violations),
+    });
+    if let (Some(pk), Some(d)) = (pubkey, &params.d_tag) {
+        result["coordinate"] = json!(format!("{}:{}:{d}", kind.as_u16(), pk.to_hex()));
+    }
+    if d_pending {
+        result["notes"] = json!([
+            "d tag is derived from the signing pubkey; configure keys or pass --d-tag to preview it"
+        ]);
+    }
+    result
+}
+
 pub async fn create_header(mut params: HeaderParams) -> Result<CommandOutput, CommandError> {
-    let keys = load_keys().map_err(|e| {
-        CommandError::from(e).next_actions(vec![NextAction::new(
-            "wokhei init --generate",
-            "Generate a keypair first",
-        )])
-    })?;
+    if params.dry_run {
+        let pubkey = params.signer.local_public_key();
+        let actions = vec![NextAction::new(
+            "wokhei create-header <same flags without --dry-run>",
+            "Sign and publish this header",
+        )];
+        return Ok(CommandOutput::new(dry_run_header(params, pubkey)).next_actions(actions));
+    }
+
+    let signer = Signer::load(&params.signer)?;
 
+    let pubkey_hex = signer
+        .public_key()
+        .await
+        .map_err(CommandError::from)?
+        .to_hex();
     if params.addressable && params.d_tag.is_none() {
-        let pubkey_hex = keys.public_key().to_hex();
         params.d_tag = Some(crate::dtag::header_dtag(&params.name, &pubkey_hex));
     }
 
-    let kind = if params.addressable {
-        Kind::Custom(39998)
-    } else {
-        Kind::Custom(9998)
-    };
+    let kind = header_kind(params.addressable);
 
     let event_tags = build_header_tags(&params);
     let tags_count = event_tags.len();
     let builder = EventBuilder::new(kind, "").tags(event_tags);
+    let event = signer.sign(builder).await;
+    signer.shutdown().await;
+    let event = event.map_err(CommandError::from)?;
+
+    if params.sign_only {
+        let mut result = json!({
+            "event_id": event.id.to_hex(),
+            "kind": kind.as_u16(),
+            "pubkey": pubkey_hex,
+        });
+        if let Some(ref d) = params.d_tag {
+            result["d_tag"] = json!(d);
+            result["coordinate"] = json!(format!("{}:{pubkey_hex}:{d}", kind.as_u16()));
+        }
+        return Ok(signed_output(&event, result));
+    }
 
-    let client = Client::builder().signer(keys.clone()).build();
-    if client.add_relay(&params.relay).await.is_err() {
-        return Err(CommandError::from(AppError::RelayUnreachable {
-            url: params.relay.clone(),
-        }));
-    }
-    client.connect().await;
-
-    let result = match client.send_event_builder(builder).await {
-        Ok(output) => {
-            let event_id = output.val.to_hex();
-            let pubkey_hex = keys.public_key().to_hex();
-            let relay = &params.relay;
-            let mut result = json!({
-                "event_id": event_id,
-                "kind": kind.as_u16(),
-                "pubkey": pubkey_hex,
-                "created_at": jiff::Timestamp::now().as_second(),
-                "tags_count": tags_count,
-            });
-
-            if let Some(ref d) = params.d_tag {
-                result["d_tag"] = json!(d);
-                let coord = format!("{}:{}:{}", kind.as_u16(), pubkey_hex, d);
-                result["coordinate"] = json!(coord);
-            }
+    let session = WriteSession::connect(&params.relays).await?;
+    let sent = session.send(&event).await;
+    session.disconnect().await;
+    let reports = sent?;
+
+    let event_id = event.id.to_hex();
+    let relay = relays_arg(&params.relays);
+    let mut result = json!({
+        "event_id": event_id,
+        "kind": kind.as_u16(),
+        "pubkey": pubkey_hex,
+        "created_at": jiff::Timestamp::now().as_second(),
+        "tags_count": tags_count,
+        "relays": reports_json(&reports),
+    });
+
+    if let Some(ref d) = params.d_tag {
+        result["d_tag"] = json!(d);
+        let coord = format!("{}:{}:{}", kind.as_u16(), pubkey_hex, d);
+        result["coordinate"] = json!(coord);
+    }
 
-            let mut actions = vec![
-                NextAction::new(
-                    format!("wokhei add-item --relay={relay} --header={event_id} --resource=<url>"),
-                    "Add an item to this list",
-                ),
-                NextAction::new(
-                    format!("wokhei inspect --relay={relay} {event_id}"),
-                    "Inspect the created header",
-                ),
-                NextAction::new(
-                    format!("wokhei list-headers --relay={relay}"),
-                    "List all headers on this relay",
+    let mut actions = vec![
+        NextAction::new(
+            format!("wokhei add-item --relay={relay} --header={event_id} --resource=<url>"),
+            "Add an item to this list",
+        ),
+        NextAction::new(
+            format!("wokhei inspect --relay={relay} {event_id}"),
+            "Inspect the created header",
+        ),
+        NextAction::new(
+            format!("wokhei list-headers --relay={relay}"),
+            "List all headers on this relay",
+        ),
+    ];
+
+    if let Some(ref d) = params.d_tag {
+        let coord = format!("{}:{}:{}", kind.as_u16(), pubkey_hex, d);
+        actions.insert(
+            1,
+            NextAction::new(
+                format!(
+                    "wokhei add-item --relay={relay} --header-coordinate=\"{coord}\" --resource=<url>"
                 ),
-            ];
-
-            if let Some(ref d) = params.d_tag {
-                let coord = format!("{}:{}:{}", kind.as_u16(), pubkey_hex, d);
-                actions.insert(
-                    1,
-                    NextAction::new(
-                        format!(
-                            "wokhei add-item --relay={relay} --header-coordinate=\"{coord}\" --resource=<url>"
-                        ),
-                        "Add item using coordinate reference",
-                    ),
-                );
-            }
+                "Add item using coordinate reference",
+            ),
+        );
+    }
+
+    Ok(CommandOutput::new(result).next_actions(actions))
+}
+
+// ---------------------------------------------------------------------------
+// update-header
+// ---------------------------------------------------------------------------
+
+/// Field-level edits for `update-header`. Unset edits leave their tags alone; an empty
+/// `description` or `alt` removes that tag.
+#[derive(Debug, Default, Clone)]
+pub struct HeaderChanges {
+    pub name: Option<String>,
+    pub plural_name: Option<String>,
+    pub titles: Vec<String>,
+    pub description: Option<String>,
+    pub alt: Option<String>,
+    pub add_required: Vec<String>,
+    pub remove_required: Vec<String>,
+    pub add_recommended: Vec<String>,
+    pub remove_recommended: Vec<String>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+}
+
+pub type TagParts = Vec<Vec<String>>;
+
+/// Replace the first `name` tag with `[name, values...]` in place and drop any others;
+/// `None` drops them all.
+pub fn set_tag(tags: &mut TagParts, name: &str, values: Option<Vec<String>>) {
+    let position = tags.iter().position(|t| t[0] == name);
+    tags.retain(|t| t[0] != name);
+    if let Some(values) = values {
+        let tag = std::iter::once(name.to_string()).chain(values).collect();
+        tags.insert(position.unwrap_or(tags.len()).min(tags.len()), tag);
+    }
+}
+
+/// `values` with `remove` dropped and `add` appended, without duplicates.
+fn edit_values(values: Vec<String>, add: &[String], remove: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for value in values.into_iter().chain(add.iter().cloned()) {
+        if !remove.contains(&value) && !out.contains(&value) {
+            out.push(value);
+        }
+    }
+    out
+}
 
-            Ok(CommandOutput::new(result).next_actions(actions))
+/// One `[name, value]` tag per value, replacing the existing ones for the edited values.
+fn edit_single_value_tags(tags: &mut TagParts, name: &str, add: &[String], remove: &[String]) {
+    tags.retain(|t| !(t[0] == name && t.get(1).is_some_and(|v| remove.contains(v))));
+    for value in add {
+        if !tags.iter().any(|t| t[0] == name && t.get(1) == Some(value)) {
+            tags.push(vec![name.to_string(), value.clone()]);
         }
-        Err(e) => Err(CommandError::from(AppError::RelayRejected {
-            reason: e.to_string(),
-        })),
+    }
+}
+
+impl HeaderChanges {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.plural_name.is_none()
+            && self.titles.is_empty()
+            && self.description.is_none()
+            && self.alt.is_none()
+            && self.add_required.is_empty()
+            && self.remove_required.is_empty()
+            && self.add_recommended.is_empty()
+            && self.remove_recommended.is_empty()
+            && self.add_tags.is_empty()
+            && self.remove_tags.is_empty()
+    }
+
+    /// `tags` with the edits applied. Untouched tags (`d`, `client`, unknown ones) keep
+    /// their values and order.
+    pub fn apply(&self, tags: &[Tag]) -> Vec<Tag> {
+        let mut parts: TagParts = tags
+            .iter()
+            .map(|t| t.as_slice().to_vec())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let current = |parts: &TagParts, name: &str| -> Vec<String> {
+            parts
+                .iter()
+                .filter(|t| t[0] == name)
+                .flat_map(|t| t[1..].iter().cloned())
+                .collect()
+        };
+
+        if self.name.is_some() || self.plural_name.is_some() {
+            let names = current(&parts, "names");
+            let singular = self.name.clone().or_else(|| names.first().cloned());
+            let plural = self.plural_name.clone().or_else(|| names.get(1).cloned());
+            let names = [singular, plural]
+                .into_iter()
+                .map(Option::unwrap_or_default);
+            set_tag(&mut parts, "names", Some(names.collect()));
+        }
+        if !self.titles.is_empty() {
+            set_tag(&mut parts, "titles", Some(self.titles.clone()));
+        }
+        for (name, value) in [("description", &self.description), ("alt", &self.alt)] {
+            if let Some(value) = value {
+                let values = (!value.is_empty()).then(|| vec![value.clone()]);
+                set_tag(&mut parts, name, values);
+            }
+        }
+        if !self.add_required.is_empty() || !self.remove_required.is_empty() {
+            let required = edit_values(
+                current(&parts, "required"),
+                &self.add_required,
+                &self.remove_required,
+            );
+            set_tag(
+                &mut parts,
+                "required",
+                (!required.is_empty()).then_some(required),
+            );
+        }
+        edit_single_value_tags(
+            &mut parts,
+            "recommended",
+            &self.add_recommended,
+            &self.remove_recommended,
+        );
+        edit_single_value_tags(&mut parts, "t", &self.add_tags, &self.remove_tags);
+
+        parts
+            .into_iter()
+            .filter_map(|t| Tag::parse(t).ok())
+            .collect()
+    }
+}
+
+pub struct UpdateHeaderParams {
+    pub relays: Vec<String>,
+    pub signer: SignerSpec,
+    /// `39998:<pubkey>:<d-tag>` or the event ID of any version of the header.
+    pub target: String,
+    pub changes: HeaderChanges,
+    pub sign_only: bool,
+    pub dry_run: bool,
+}
+
+/// Republish an addressable header with `changes` applied, keeping its d-tag and content.
+/// `--dry-run` needs no keys; nothing is signed when the edits change no tag.
+pub async fn update_header(params: UpdateHeaderParams) -> Result<CommandOutput, CommandError> {
+    if params.changes.is_empty() {
+        return Err(CommandError::new(
+            "update-header needs at least one change",
+            "MISSING_ARG",
+            "Pass e.g. --description=<text>, --add-recommended=<field> or --add-tags=<tag>",
+        ));
+    }
+    let signer = if params.dry_run {
+        None
+    } else {
+        Some(Signer::load(&params.signer)?)
     };
+    let relay = relays_arg(&params.relays);
+    let session = WriteSession::connect(&params.relays).await?;
+
+    let result = async {
+        let current = fetch_current_addressable(
+            session.client(),
+            &relay,
+            &params.target,
+            Kind::Custom(39998),
+        )
+        .await?;
+        let tags = params.changes.apply(current.tags.as_slice());
+        reject(&check_event(39998, &tags)).map_err(CommandError::from)?;
+
+        let d_tag = crate::query::header_d_tag(&current).unwrap_or_default();
+        let coordinate = format!("39998:{}:{d_tag}", current.pubkey.to_hex());
+        let changed = tags != current.tags.as_slice();
+        let mut result = json!({
+            "previous_event_id": current.id.to_hex(),
+            "kind": 39998,
+            "d_tag": d_tag,
+            "coordinate": coordinate,
+            "diff": tag_diff(current.tags.as_slice(), &tags),
+            "changed": changed,
+        });
+        let Some(signer) = signer.as_ref().filter(|_| changed) else {
+            result["tags"] = tags_json(&tags);
+            if params.dry_run {
+                result["dry_run"] = json!(true);
+            }
+            let actions = vec![NextAction::new(
+                format!("wokhei inspect --relay={relay} {}", current.id.to_hex()),
+                "Inspect the current header",
+            )];
+            return Ok(CommandOutput::new(result).next_actions(actions));
+        };
+
+        if signer.public_key().await.map_err(CommandError::from)? != current.pubkey {
+            return Err(CommandError::from(AppError::NotAuthor { coordinate }));
+        }
+        let builder = EventBuilder::new(Kind::Custom(39998), current.content.clone())
+            .tags(tags)
+            .custom_created_at(replacement_created_at(&current));
+        let event = signer.sign(builder).await.map_err(CommandError::from)?;
+        result["event_id"] = json!(event.id.to_hex());
+        if params.sign_only {
+            return Ok(signed_output(&event, result));
+        }
+
+        let reports = session.send(&event).await?;
+        result["relays"] = json!(reports_json(&reports));
+        let actions = vec![
+            NextAction::new(
+                format!("wokhei inspect --relay={relay} {}", event.id.to_hex()),
+                "Inspect the updated header",
+            ),
+            NextAction::new(
+                format!("wokhei list-items --relay={relay} --header-coordinate=\"{coordinate}\""),
+                "Items keep pointing at the same coordinate",
+            ),
+        ];
+        Ok(CommandOutput::new(result).next_actions(actions))
+    }
+    .await;
 
-    client.disconnect().await;
+    session.disconnect().await;
+    if let Some(signer) = signer {
+        signer.shutdown().await;
+    }
     result
 }
 
@@ -139,7 +428,10 @@ mod tests {
 
     fn minimal_params() -> HeaderParams {
         HeaderParams {
-            relay: "ws://localhost:7777".into(),
+            relays: vec!["ws://localhost:7777".into()],
+            signer: SignerSpec::Local {
+                profile: "default".into(),
+            },
             name: "mylist".into(),
             plural_name: "mylists".into(),
             titles: vec![],
@@ -150,6 +442,8 @@ mod tests {
             alt: None,
             addressable: false,
             d_tag: None,
+            sign_only: false,
+            dry_run: false,
         }
     }
 
@@ -276,4 +570,111 @@ mod tests {
         let tags = build_header_tags(&minimal_params());
         assert!(find_tag(&tags, "d").is_none());
     }
+
+    // -----------------------------------------------------------------------
+    // dry_run_header
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn dry_run_previews_auto_d_tag_and_coordinate() {
+        let pk = Keys::generate().public_key();
+        let mut p = minimal_params();
+        p.addressable = true;
+        let j = dry_run_header(p, Some(pk));
+        let d = crate::dtag::header_dtag("mylist", &pk.to_hex());
+        assert_eq!(j["kind"], 39998);
+        assert_eq!(j["d_tag"], d);
+        assert_eq!(j["coordinate"], format!("39998:{}:{d}", pk.to_hex()));
+        assert_eq!(j["valid"], true);
+        assert_eq!(j["tags"][0], json!(["names", "mylist", "mylists"]));
+    }
+
+    #[test]
+    fn dry_run_without_keys_leaves_d_tag_pending() {
+        let mut p = minimal_params();
+        p.addressable = true;
+        let j = dry_run_header(p, None);
+        assert!(j["d_tag"].is_null());
+        assert_eq!(j["valid"], true);
+        assert!(j["notes"][0].as_str().unwrap().contains("--d-tag"));
+    }
+
+    #[test]
+    fn dry_run_reports_violations() {
+        let mut p = minimal_params();
+        p.plural_name = String::new();
+        p.recommended = vec!["a b".into()];
+        let j = dry_run_header(p, None);
+        assert_eq!(j["valid"], false);
+        assert_eq!(j["violations"][0]["rule"], "NAMES_INCOMPLETE");
+        assert_eq!(j["violations"][1]["rule"], "INVALID_FIELD_NAME");
+    }
+
+    // -----------------------------------------------------------------------
+    // update-header
+    // -----------------------------------------------------------------------
+
+    fn addressable_tags() -> Vec<Tag> {
+        let mut p = minimal_params();
+        p.addressable = true;
+        p.d_tag = Some("mylists--1234".into());
+        p.description = Some("old".into());
+        p.required = vec!["url".into(), "title".into()];
+        p.recommended = vec!["artist".into()];
+        p.tags_list = vec!["jazz".into()];
+        build_header_tags(&p)
+    }
+
+    #[test]
+    fn header_changes_edit_fields_and_keep_other_tags() {
+        let old = addressable_tags();
+        let changes = HeaderChanges {
+            description: Some("new".into()),
+            remove_required: vec!["url".into()],
+            add_recommended: vec!["year".into(), "artist".into()],
+            add_tags: vec!["bebop".into()],
+            ..HeaderChanges::default()
+        };
+        let new = changes.apply(&old);
+
+        assert_eq!(
+            tag_values(find_tag(&new, "description").unwrap()),
+            vec!["description", "new"]
+        );
+        assert_eq!(
+            tag_values(find_tag(&new, "required").unwrap()),
+            vec!["required", "title"]
+        );
+        assert_eq!(find_tags(&new, "recommended").len(), 2);
+        assert_eq!(find_tags(&new, "t").len(), 2);
+        assert_eq!(find_tag(&new, "d"), find_tag(&old, "d"));
+        assert_eq!(find_tag(&new, "client"), find_tag(&old, "client"));
+        let position =
+            |tags: &[Tag], name| tags.iter().position(|t| t.as_slice()[0] == name).unwrap();
+        assert_eq!(position(&new, "description"), position(&old, "description"));
+        assert!(check_event(39998, &new).is_empty());
+    }
+
+    #[test]
+    fn header_changes_remove_tags_and_rename() {
+        let old = addressable_tags();
+        let changes = HeaderChanges {
+            plural_name: Some("collections".into()),
+            description: Some(String::new()),
+            remove_required: vec!["url".into(), "title".into()],
+            remove_recommended: vec!["artist".into()],
+            remove_tags: vec!["jazz".into()],
+            ..HeaderChanges::default()
+        };
+        let new = changes.apply(&old);
+        assert_eq!(
+            tag_values(find_tag(&new, "names").unwrap()),
+            vec!["names", "mylist", "collections"]
+        );
+        for gone in ["description", "required", "recommended", "t"] {
+            assert!(find_tag(&new, gone).is_none(), "{gone} should be removed");
+        }
+        assert!(HeaderChanges::default().is_empty());
+        assert_eq!(HeaderChanges::default().apply(&old), old);
+    }
 }
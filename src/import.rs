@@ -0,0 +1,287 @@
+use std::collections::HashSet;
+use std::io;
+
+use agcli::{CommandError, CommandOutput, NextAction};
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::error::AppError;
+use crate::publish::read_json_input;
+use crate::query::QUERY_TIMEOUT;
+use crate::relays::{WriteSession, relays_arg};
+
+/// Ids per existence lookup, so one filter stays well under relay limits.
+pub const LOOKUP_CHUNK: usize = 500;
+
+fn failure(event_id: &serde_json::Value, err: &CommandError) -> serde_json::Value {
+    json!({
+        "event_id": event_id,
+        "error": { "code": err.code, "message": err.message },
+    })
+}
+
+/// Rebuild the signed event from an `export` entry (`event_id` plus the raw event fields).
+fn exported_event(entry: &serde_json::Value) -> Result<Event, AppError> {
+    let raw = json!({
+        "id": entry.get("event_id").or_else(|| entry.get("id")),
+        "pubkey": entry["pubkey"],
+        "created_at": entry["created_at"],
+        "kind": entry["kind"],
+        "tags": entry["tags"],
+        "content": entry["content"],
+        "sig": entry["sig"],
+    });
+    let event: Event = serde_json::from_value(raw).map_err(|e| AppError::InvalidEvent {
+        reason: e.to_string(),
+    })?;
+    event.verify().map_err(|e| AppError::InvalidEvent {
+        reason: e.to_string(),
+    })?;
+    Ok(event)
+}
+
+/// Events in an `export` backup (whole envelope or bare result), each header before its
+/// items and deduplicated by id. Entries that fail verification come back as failures.
+fn backup_events(
+    raw: &serde_json::Value,
+) -> Result<(Vec<Event>, Vec<serde_json::Value>), AppError> {
+    let headers = raw
+        .pointer("/result/headers")
+        .or_else(|| raw.get("headers"))
+        .and_then(serde_json::Value::as_array)
+        .ok_or_else(|| AppError::InvalidJson {
+            reason: "not a wokhei export backup: no headers array".to_string(),
+        })?;
+
+    let entries = headers.iter().flat_map(|h| {
+        std::iter::once(&h["header"]).chain(h["items"].as_array().into_iter().flatten())
+    });
+
+    let mut events = Vec::new();
+    let mut failures = Vec::new();
+    let mut seen = HashSet::new();
+    for entry in entries {
+        match exported_event(entry) {
+            Ok(event) => {
+                if seen.insert(event.id) {
+                    events.push(event);
+                }
+            }
+            Err(e) => failures.push(failure(&entry["event_id"], &CommandError::from(e))),
+        }
+    }
+    Ok((events, failures))
+}
+
+/// Which of `ids` one relay has; `None` if it didn't answer.
+async fn ids_on(client: &Client, relay: &str, ids: &[EventId]) -> Option<HashSet<EventId>> {
+    let mut found = HashSet::new();
+    for chunk in ids.chunks(LOOKUP_CHUNK) {
+        let filter = Filter::new().ids(chunk.iter().copied());
+        let events = client
+            .fetch_events_from([relay], filter, QUERY_TIMEOUT)
+            .await
+            .ok()?;
+        found.extend(events.into_iter().map(|e| e.id));
+    }
+    Some(found)
+}
+
+/// Raw signed events, one per line, as written by `export --format=jsonl`.
+fn jsonl_events(contents: &str) -> Result<(Vec<Event>, Vec<serde_json::Value>), AppError> {
+    let mut events = Vec::new();
+    let mut failures = Vec::new();
+    let mut seen = HashSet::new();
+    for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+        let entry: serde_json::Value =
+            serde_json::from_str(line).map_err(|_| AppError::InvalidJson {
+                reason: "not a wokhei export backup (JSON tree or JSON Lines of events)"
+                    .to_string(),
+            })?;
+        match exported_event(&entry) {
+            Ok(event) => {
+                if seen.insert(event.id) {
+                    events.push(event);
+                }
+            }
+            Err(e) => failures.push(failure(&entry["id"], &CommandError::from(e))),
+        }
+    }
+    Ok((events, failures))
+}
+
+/// An `export` backup: the JSON tree (envelope or `--format=json` file) or JSON Lines.
+fn parse_backup(contents: &str) -> Result<(Vec<Event>, Vec<serde_json::Value>), AppError> {
+    match serde_json::from_str::<serde_json::Value>(contents) {
+        Ok(raw) if raw.get("id").is_none() => backup_events(&raw),
+        _ => jsonl_events(contents),
+    }
+}
+
+/// Ids already stored on every relay that answered the lookup.
+pub async fn existing_ids(client: &Client, relays: &[String], ids: &[EventId]) -> HashSet<EventId> {
+    let mut everywhere: Option<HashSet<EventId>> = None;
+    for relay in relays {
+        if let Some(found) = ids_on(client, relay, ids).await {
+            everywhere = Some(match everywhere {
+                None => found,
+                Some(prev) => prev.intersection(&found).copied().collect(),
+            });
+        }
+    }
+    everywhere.unwrap_or_default()
+}
+
+/// Republish a parsed backup verbatim, skipping events every target relay already has.
+async fn import_backup(
+    relays: &[String],
+    contents: &str,
+) -> Result<serde_json::Value, CommandError> {
+    let (events, mut failures) = parse_backup(contents).map_err(CommandError::from)?;
+    let total = events.len() + failures.len();
+
+    let session = WriteSession::connect(relays).await?;
+    let ids: Vec<EventId> = events.iter().map(|e| e.id).collect();
+    let present = existing_ids(session.client(), relays, &ids).await;
+
+    let mut imported = 0usize;
+    for event in events.iter().filter(|e| !present.contains(&e.id)) {
+        match session.send(event).await {
+            Ok(_) => imported += 1,
+            Err(e) => failures.push(failure(&json!(event.id.to_hex()), &e)),
+        }
+    }
+    session.disconnect().await;
+
+    Ok(json!({
+        "relays": relays,
+        "counts": {
+            "events": total,
+            "imported": imported,
+            "skipped": present.len(),
+            "failed": failures.len(),
+        },
+        "failures": failures,
+    }))
+}
+
+pub async fn import(relays: Vec<String>, input: String) -> Result<CommandOutput, CommandError> {
+    let contents = read_json_input(&input, io::stdin())?;
+    let result = import_backup(&relays, &contents).await?;
+
+    let relay = relays_arg(&relays);
+    let mut actions = vec![NextAction::new(
+        format!("wokhei count --relay={relay}"),
+        "Check the target relay's header and item counts",
+    )];
+    if result["counts"]["failed"].as_u64().unwrap_or(0) > 0 {
+        actions.push(NextAction::new(
+            format!("wokhei import --relay={relay} {input}"),
+            "Re-run after fixing failures; events already imported are skipped",
+        ));
+    }
+    Ok(CommandOutput::new(result).next_actions(actions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::event_to_json;
+
+    fn signed(kind: u16, keys: &Keys) -> Event {
+        EventBuilder::new(Kind::Custom(kind), "")
+            .tag(Tag::identifier("x"))
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    fn backup(header: &Event, items: &[&Event]) -> serde_json::Value {
+        let items: Vec<_> = items.iter().map(|e| event_to_json(e)).collect();
+        json!({
+            "relay": "ws://old",
+            "counts": { "headers": 1, "items": items.len() },
+            "headers": [{ "header": event_to_json(header), "items_count": items.len(), "items": items }],
+        })
+    }
+
+    #[test]
+    fn backup_events_rebuilds_signed_events_header_first() {
+        let keys = Keys::generate();
+        let (header, item) = (signed(39998, &keys), signed(39999, &keys));
+        let envelope = json!({ "ok": true, "result": backup(&header, &[&item, &item]) });
+
+        let (events, failures) = backup_events(&envelope).unwrap();
+        assert!(failures.is_empty());
+        assert_eq!(
+            events.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![header.id, item.id]
+        );
+    }
+
+    #[test]
+    fn backup_events_reports_tampered_entries() {
+        let keys = Keys::generate();
+        let (header, item) = (signed(9998, &keys), signed(9999, &keys));
+        let mut raw = backup(&header, &[&item]);
+        raw["headers"][0]["items"][0]["content"] = json!("tampered");
+
+        let (events, failures) = backup_events(&raw).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(failures[0]["event_id"], item.id.to_hex());
+        assert_eq!(failures[0]["error"]["code"], "INVALID_EVENT");
+    }
+
+    #[test]
+    fn backup_events_rejects_other_json() {
+        let err = backup_events(&json!({ "kind": 1 })).unwrap_err();
+        assert_eq!(err.code(), "INVALID_JSON");
+    }
+
+    #[test]
+    fn parse_backup_reads_jsonl_events() {
+        let keys = Keys::generate();
+        let (header, item) = (signed(39998, &keys), signed(39999, &keys));
+        let contents = format!(
+            "{}\n\n{}\n",
+            serde_json::to_string(&header).unwrap(),
+            serde_json::to_string(&item).unwrap()
+        );
+        let (events, failures) = parse_backup(&contents).unwrap();
+        assert_eq!(events, vec![header.clone(), item]);
+        assert!(failures.is_empty());
+
+        let (single, _) = parse_backup(&serde_json::to_string(&header).unwrap()).unwrap();
+        assert_eq!(single, vec![header]);
+    }
+
+    #[test]
+    fn parse_backup_rejects_garbage() {
+        let err = parse_backup("not json\nat all").unwrap_err();
+        assert_eq!(err.code(), "INVALID_JSON");
+    }
+
+    #[tokio::test]
+    async fn import_skips_events_the_relay_already_has() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let relays = vec![relay.url().await.to_string()];
+        let keys = Keys::generate();
+        let (header, item) = (signed(39998, &keys), signed(39999, &keys));
+
+        let session = WriteSession::connect(&relays).await.unwrap();
+        session.send(&header).await.unwrap();
+        session.disconnect().await;
+
+        let result = import_backup(&relays, &backup(&header, &[&item]).to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            result["counts"],
+            json!({ "events": 2, "imported": 1, "skipped": 1, "failed": 0 })
+        );
+
+        let session = WriteSession::connect(&relays).await.unwrap();
+        let present = existing_ids(session.client(), &relays, &[header.id, item.id]).await;
+        session.disconnect().await;
+        assert_eq!(present, HashSet::from([header.id, item.id]));
+    }
+}
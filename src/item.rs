@@ -4,8 +4,18 @@ use std::time::Duration;
 
 use agcli::{CommandError, CommandOutput, NextAction};
 
+use crate::broadcast::signed_output;
+use crate::bulk::{ItemRow, ItemRows, read_item_rows};
 use crate::error::AppError;
-use crate::keys::load_keys;
+use crate::header::{TagParts, set_tag};
+use crate::publish::{batch_result, failed_entry};
+use crate::query::{fetch_current_addressable, fetch_latest_addressable, replacement_created_at};
+use crate::relays::{WriteSession, relays_arg, reports_json};
+use crate::signer::{Signer, SignerSpec};
+use crate::validate::{
+    HeaderSchema, Violation, check_event, check_field_args, reject, tag_diff, tags_json,
+    violations_json,
+};
 
 // Re-export from dcosl-core
 pub use dcosl_core::item::parse_coordinate_str;
@@ -14,8 +24,23 @@ fn parse_coordinate_str_cmd(input: &str) -> Result<(u16, PublicKey, String), Com
     parse_coordinate_str(input).map_err(|e| CommandError::from(AppError::from(e)))
 }
 
+/// `add-item --from`: flags shared by every row of a bulk file.
+pub struct BulkItemParams {
+    pub relays: Vec<String>,
+    pub signer: SignerSpec,
+    pub header: Option<String>,
+    pub header_coordinate: Option<String>,
+    pub from: String,
+    pub content: Option<String>,
+    pub fields: Vec<String>,
+    pub addressable: bool,
+    pub sign_only: bool,
+    pub dry_run: bool,
+}
+
 pub struct ItemParams {
-    pub relay: String,
+    pub relays: Vec<String>,
+    pub signer: SignerSpec,
     pub header: Option<String>,
     pub header_coordinate: Option<String>,
     pub resource: String,
@@ -23,25 +48,49 @@ pub struct ItemParams {
     pub fields: Vec<String>,
     pub addressable: bool,
     pub d_tag: Option<String>,
+    pub sign_only: bool,
+    pub dry_run: bool,
 }
 
+/// `z` ref for a `--header-coordinate`; needs no relay lookup.
+fn coordinate_z_ref(coord_str: &str) -> Result<String, CommandError> {
+    let (kind_num, pubkey, d_val) = parse_coordinate_str_cmd(coord_str)?;
+    if kind_num != 39998 {
+        return Err(CommandError::from(AppError::InvalidCoordinate {
+            input: coord_str.to_string(),
+        }));
+    }
+    Ok(format!("39998:{}:{d_val}", pubkey.to_hex()))
+}
+
+/// The newest header at a `39998:<pubkey>:<d-tag>` coordinate, if the relays have it.
+async fn fetch_coordinate_header(client: &Client, relay: &str, coord_str: &str) -> Option<Event> {
+    let (_, pubkey, d_val) = parse_coordinate_str(coord_str).ok()?;
+    fetch_latest_addressable(client, relay, Kind::Custom(39998), pubkey, &d_val)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// The `z` ref plus the header event when it could be fetched; a `--header-coordinate`
+/// whose header isn't on the relays still resolves.
 async fn resolve_header_ref(
     client: &Client,
     relay: &str,
     resource: &str,
     header: Option<&str>,
     header_coordinate: Option<&str>,
-) -> Result<String, CommandError> {
+) -> Result<(String, Option<Event>), CommandError> {
     if let Some(coord_str) = header_coordinate {
-        let (kind_num, pubkey, d_val) = parse_coordinate_str_cmd(coord_str)?;
-        if kind_num != 39998 {
-            return Err(CommandError::from(AppError::InvalidCoordinate {
-                input: coord_str.to_string(),
-            }));
-        }
-        Ok(format!("39998:{}:{d_val}", pubkey.to_hex()))
+        let z_ref = coordinate_z_ref(coord_str)?;
+        Ok((
+            z_ref,
+            fetch_coordinate_header(client, relay, coord_str).await,
+        ))
     } else if let Some(header_id_str) = header {
-        resolve_header_by_id(client, relay, resource, header_id_str).await
+        let (z_ref, header_event) =
+            resolve_header_by_id(client, relay, resource, header_id_str).await?;
+        Ok((z_ref, Some(header_event)))
     } else {
         unreachable!()
     }
@@ -52,7 +101,7 @@ async fn resolve_header_by_id(
     relay: &str,
     resource: &str,
     header_id_str: &str,
-) -> Result<String, CommandError> {
+) -> Result<(String, Event), CommandError> {
     let event_id = EventId::parse(header_id_str).map_err(|_| {
         CommandError::from(AppError::InvalidEventId {
             id: header_id_str.to_string(),
@@ -92,9 +141,10 @@ async fn resolve_header_by_id(
         });
 
         let d = d_val.ok_or_else(|| CommandError::from(AppError::HeaderMissingDTag))?;
-        Ok(format!("39998:{}:{d}", header_event.pubkey.to_hex()))
+        let z_ref = format!("39998:{}:{d}", header_event.pubkey.to_hex());
+        Ok((z_ref, header_event))
     } else if header_event.kind == Kind::Custom(9998) {
-        Ok(event_id.to_hex())
+        Ok((event_id.to_hex(), header_event))
     } else {
         Err(CommandError::new(
             "--header must reference a list header event (kind 9998 or 39998)",
@@ -113,29 +163,199 @@ fn build_item_tags(
     dcosl_core::item::build_item_tags(parent_z_ref, resource, fields, d_tag, Some("wokhei"))
 }
 
+fn item_kind(addressable: bool) -> Kind {
+    if addressable {
+        Kind::Custom(39999)
+    } else {
+        Kind::Custom(9999)
+    }
+}
+
+/// Item tags for `parent_z_ref`, filling in the auto d-tag for addressable items.
+fn item_tags(
+    item_kind: Kind,
+    parent_z_ref: &str,
+    resource: &str,
+    fields: &[String],
+    d_tag: Option<String>,
+) -> (Vec<Tag>, Option<String>) {
+    let d_tag = if item_kind == Kind::Custom(39999) && d_tag.is_none() {
+        Some(crate::dtag::item_dtag(parent_z_ref, resource))
+    } else {
+        d_tag
+    };
+    let event_tags = build_item_tags(parent_z_ref, resource, fields, d_tag.as_deref());
+    (event_tags, d_tag)
+}
+
+fn header_schema(header: &Event) -> HeaderSchema {
+    HeaderSchema::from_tags(header.tags.as_slice())
+}
+
+/// Check item `tags` against the header's fields, returning warnings for unknown ones.
+/// Without a header (not on the relays, or no relay at all) there is nothing to check.
+fn enforce_schema(schema: Option<&HeaderSchema>, tags: &[Tag]) -> Result<Vec<String>, AppError> {
+    schema.map_or_else(|| Ok(Vec::new()), |schema| schema.enforce(tags))
+}
+
+/// A failed field check, pointing at the header that declares the fields.
+fn schema_error(err: AppError, relay: &str, header: Option<&Event>) -> CommandError {
+    let err = CommandError::from(err);
+    match header {
+        Some(header) => err.next_actions(vec![NextAction::new(
+            format!("wokhei inspect --relay={relay} {}", header.id.to_hex()),
+            "See the header's required and recommended fields",
+        )]),
+        None => err,
+    }
+}
+
+fn item_event_builder(
+    item_kind: Kind,
+    parent_z_ref: &str,
+    resource: &str,
+    fields: &[String],
+    content: Option<&str>,
+    d_tag: Option<String>,
+) -> (EventBuilder, Option<String>) {
+    let (event_tags, d_tag) = item_tags(item_kind, parent_z_ref, resource, fields, d_tag);
+    let builder = EventBuilder::new(item_kind, content.unwrap_or("")).tags(event_tags);
+    (builder, d_tag)
+}
+
+/// `z` ref for a dry run, which has no relay: `--header=<event-id>` is assumed to be a 9998
+/// header and a note says so.
+fn dry_run_header_ref(
+    header: Option<&str>,
+    header_coordinate: Option<&str>,
+) -> Result<(String, Vec<&'static str>), CommandError> {
+    if let Some(coord) = header_coordinate {
+        return Ok((coordinate_z_ref(coord)?, Vec::new()));
+    }
+    let id_str = header.unwrap_or_default();
+    let event_id = EventId::parse(id_str).map_err(|_| {
+        CommandError::from(AppError::InvalidEventId {
+            id: id_str.to_string(),
+        })
+    })?;
+    let notes = vec![
+        "assumes --header is a kind 9998 header; for a 39998 header the z ref is its coordinate (use --header-coordinate)",
+    ];
+    Ok((event_id.to_hex(), notes))
+}
+
+/// `--dry-run`: the unsigned item exactly as it would be published, plus `DCoSL` rule
+/// violations. Needs no keys or relay, so `--header=<event-id>` is assumed to be a 9998 header.
+fn dry_run_item(params: &ItemParams) -> Result<serde_json::Value, CommandError> {
+    let kind = item_kind(params.addressable);
+    let (parent_z_ref, notes) = dry_run_header_ref(
+        params.header.as_deref(),
+        params.header_coordinate.as_deref(),
+    )?;
+
+    let (tags, d_tag) = item_tags(
+        kind,
+        &parent_z_ref,
+        &params.resource,
+        &params.fields,
+        params.d_tag.clone(),
+    );
+    let mut violations = check_field_args(&params.fields);
+    violations.extend(check_event(kind.as_u16(), &tags));
+
+    let mut result = json!({
+        "dry_run": true,
+        "kind": kind.as_u16(),
+        "header_ref": parent_z_ref,
+        "resource": params.resource,
+        "d_tag": d_tag,
+        "content": params.content.as_deref().unwrap_or(""),
+        "tags": tags_json(&tags),
+        "valid": violations.is_empty(),
+        "violations": violations_json(&violations),
+    });
+    if !notes.is_empty() {
+        result["notes"] = json!(notes);
+    }
+    Ok(result)
+}
+
 fn validate_item_params(params: &ItemParams) -> Result<(), CommandError> {
-    if params.header.is_none() && params.header_coordinate.is_none() {
+    check_header_flags(
+        params.header.as_deref(),
+        params.header_coordinate.as_deref(),
+        params.sign_only,
+    )
+}
+
+fn check_header_flags(
+    header: Option<&str>,
+    header_coordinate: Option<&str>,
+    sign_only: bool,
+) -> Result<(), CommandError> {
+    if header.is_none() && header_coordinate.is_none() {
         return Err(CommandError::new(
             "Specify --header=<event-id> or --header-coordinate=<kind:pubkey:d-tag>",
             "MISSING_ARG",
             "Use --header with an event ID, or --header-coordinate with kind:pubkey:d-tag",
         ));
     }
+    if sign_only && header_coordinate.is_none() {
+        return Err(CommandError::new(
+            "--sign-only cannot look up a --header event ID on a relay",
+            "INVALID_ARGS",
+            "Use --header-coordinate=<39998:pubkey:d-tag> with --sign-only",
+        ));
+    }
     Ok(())
 }
 
+/// `--sign-only`: the parent comes from `--header-coordinate`, so no relay is contacted.
+async fn sign_item_only(signer: Signer, params: ItemParams) -> Result<CommandOutput, CommandError> {
+    let item_kind = item_kind(params.addressable);
+    let parent_z_ref = coordinate_z_ref(params.header_coordinate.as_deref().unwrap_or_default())?;
+    let (builder, d_tag) = item_event_builder(
+        item_kind,
+        &parent_z_ref,
+        &params.resource,
+        &params.fields,
+        params.content.as_deref(),
+        params.d_tag,
+    );
+    let event = signer.sign(builder).await;
+    signer.shutdown().await;
+    let event = event.map_err(CommandError::from)?;
+
+    let mut result = json!({
+        "event_id": event.id.to_hex(), "kind": item_kind.as_u16(),
+        "header_ref": parent_z_ref, "resource": params.resource,
+    });
+    if let Some(ref d) = d_tag {
+        result["d_tag"] = json!(d);
+    }
+    Ok(signed_output(&event, result))
+}
+
 pub async fn add_item(params: ItemParams) -> Result<CommandOutput, CommandError> {
-    let keys = load_keys().map_err(|e| {
-        CommandError::from(e).next_actions(vec![NextAction::new(
-            "wokhei init --generate",
-            "Generate a keypair first",
-        )])
-    })?;
+    if params.dry_run {
+        validate_item_params(&params)?;
+        let actions = vec![NextAction::new(
+            "wokhei add-item <same flags without --dry-run>",
+            "Sign and publish this item",
+        )];
+        return Ok(CommandOutput::new(dry_run_item(&params)?).next_actions(actions));
+    }
+
+    let signer = Signer::load(&params.signer)?;
 
     validate_item_params(&params)?;
+    if params.sign_only {
+        return sign_item_only(signer, params).await;
+    }
 
     let ItemParams {
-        relay,
+        relays,
+        signer: _,
         header,
         header_coordinate,
         resource,
@@ -143,25 +363,18 @@ pub async fn add_item(params: ItemParams) -> Result<CommandOutput, CommandError>
         fields,
         addressable,
         d_tag,
+        sign_only: _,
+        dry_run: _,
     } = params;
 
-    let item_kind = if addressable {
-        Kind::Custom(39999)
-    } else {
-        Kind::Custom(9999)
-    };
+    let item_kind = item_kind(addressable);
 
-    let client = Client::builder().signer(keys.clone()).build();
-    if client.add_relay(&relay).await.is_err() {
-        return Err(CommandError::from(AppError::RelayUnreachable {
-            url: relay.clone(),
-        }));
-    }
-    client.connect().await;
+    let relay = relays_arg(&relays);
+    let session = WriteSession::connect(&relays).await?;
 
     let result = async {
-        let parent_z_ref = resolve_header_ref(
-            &client,
+        let (parent_z_ref, header_event) = resolve_header_ref(
+            session.client(),
             &relay,
             &resource,
             header.as_deref(),
@@ -169,64 +382,488 @@ pub async fn add_item(params: ItemParams) -> Result<CommandOutput, CommandError>
         )
         .await?;
 
-        let d_tag = if addressable && d_tag.is_none() {
-            Some(crate::dtag::item_dtag(&parent_z_ref, &resource))
+        let (tags, d_tag) = item_tags(item_kind, &parent_z_ref, &resource, &fields, d_tag);
+        let schema = header_event.as_ref().map(header_schema);
+        let warnings = enforce_schema(schema.as_ref(), &tags)
+            .map_err(|e| schema_error(e, &relay, header_event.as_ref()))?;
+        let builder = EventBuilder::new(item_kind, content.as_deref().unwrap_or("")).tags(tags);
+        let event = signer.sign(builder).await.map_err(CommandError::from)?;
+        let reports = session.send(&event).await?;
+
+        let event_id = event.id.to_hex();
+        let mut result = json!({
+            "event_id": event_id, "kind": item_kind.as_u16(),
+            "header_ref": parent_z_ref, "resource": resource,
+            "relays": reports_json(&reports),
+        });
+        if let Some(ref d) = d_tag {
+            result["d_tag"] = json!(d);
+        }
+        if !warnings.is_empty() {
+            result["warnings"] = json!(warnings);
+        }
+        let coordinate_mode = header_coordinate.is_some() || parent_z_ref.starts_with("39998:");
+        let header_flag = if coordinate_mode {
+            format!("--header-coordinate=\"{parent_z_ref}\"")
+        } else {
+            format!("--header={}", header.as_deref().unwrap_or(&parent_z_ref))
+        };
+        let list_items_cmd = if coordinate_mode {
+            format!("wokhei list-items --relay={relay} --header-coordinate=\"{parent_z_ref}\"")
         } else {
-            d_tag
+            format!(
+                "wokhei list-items --relay={relay} {}",
+                header.as_deref().unwrap_or(&parent_z_ref)
+            )
         };
+        let actions = vec![
+            NextAction::new(
+                format!("wokhei inspect --relay={relay} {event_id}"),
+                "Inspect the created item",
+            ),
+            NextAction::new(
+                format!("wokhei add-item --relay={relay} {header_flag} --resource=<url>"),
+                "Add another item to this list",
+            ),
+            NextAction::new(list_items_cmd, "List all items in this list"),
+        ];
+        Ok(CommandOutput::new(result).next_actions(actions))
+    }
+    .await;
 
-        let event_tags = build_item_tags(&parent_z_ref, &resource, &fields, d_tag.as_deref());
-        let builder =
-            EventBuilder::new(item_kind, content.as_deref().unwrap_or("")).tags(event_tags);
-
-        match client.send_event_builder(builder).await {
-            Ok(output) => {
-                let event_id = output.val.to_hex();
-                let mut result = json!({
-                    "event_id": event_id, "kind": item_kind.as_u16(),
-                    "header_ref": parent_z_ref, "resource": resource,
-                });
-                if let Some(ref d) = d_tag {
-                    result["d_tag"] = json!(d);
-                }
-                let coordinate_mode =
-                    header_coordinate.is_some() || parent_z_ref.starts_with("39998:");
-                let header_flag = if coordinate_mode {
-                    format!("--header-coordinate=\"{parent_z_ref}\"")
-                } else {
-                    format!("--header={}", header.as_deref().unwrap_or(&parent_z_ref))
-                };
-                let list_items_cmd = if coordinate_mode {
-                    format!(
-                        "wokhei list-items --relay={relay} --header-coordinate=\"{parent_z_ref}\""
-                    )
-                } else {
-                    format!(
-                        "wokhei list-items --relay={relay} {}",
-                        header.as_deref().unwrap_or(&parent_z_ref)
-                    )
-                };
-                let actions = vec![
-                    NextAction::new(
-                        format!("wokhei inspect --relay={relay} {event_id}"),
-                        "Inspect the created item",
-                    ),
-                    NextAction::new(
-                        format!("wokhei add-item --relay={relay} {header_flag} --resource=<url>"),
-                        "Add another item to this list",
-                    ),
-                    NextAction::new(list_items_cmd, "List all items in this list"),
-                ];
-                Ok(CommandOutput::new(result).next_actions(actions))
+    session.disconnect().await;
+    signer.shutdown().await;
+    result
+}
+
+// ---------------------------------------------------------------------------
+// Bulk mode: add-item --from=<file>
+// ---------------------------------------------------------------------------
+
+/// A row with the shared `--fields` first and `--content` as the fallback content.
+fn merge_row(row: ItemRow, params: &BulkItemParams) -> Result<ItemRow, AppError> {
+    let mut fields = params.fields.clone();
+    fields.extend(row.fields);
+    reject(&check_field_args(&fields))?;
+    Ok(ItemRow {
+        content: row.content.or_else(|| params.content.clone()),
+        fields,
+        ..row
+    })
+}
+
+fn dry_run_items(
+    params: &BulkItemParams,
+    rows: ItemRows,
+) -> Result<serde_json::Value, CommandError> {
+    let kind = item_kind(params.addressable);
+    let (parent_z_ref, notes) = dry_run_header_ref(
+        params.header.as_deref(),
+        params.header_coordinate.as_deref(),
+    )?;
+
+    let results = rows
+        .into_iter()
+        .enumerate()
+        .map(|(index, row)| match row {
+            Ok(row) => {
+                let mut fields = params.fields.clone();
+                fields.extend(row.fields);
+                let (tags, d_tag) =
+                    item_tags(kind, &parent_z_ref, &row.resource, &fields, row.d_tag);
+                let mut violations = check_field_args(&fields);
+                violations.extend(check_event(kind.as_u16(), &tags));
+                json!({
+                    "index": index, "ok": true, "resource": row.resource, "d_tag": d_tag,
+                    "tags": tags_json(&tags), "valid": violations.is_empty(),
+                    "violations": violations_json(&violations),
+                })
             }
-            Err(e) => Err(CommandError::from(AppError::RelayRejected {
-                reason: e.to_string(),
-            })),
+            Err(e) => failed_entry(index, &CommandError::from(e)),
+        })
+        .collect::<Vec<_>>();
+
+    let valid = results.iter().all(|r| r["valid"] == true);
+    let mut result = batch_result(results);
+    result["dry_run"] = json!(true);
+    result["kind"] = json!(kind.as_u16());
+    result["header_ref"] = json!(parent_z_ref);
+    result["valid"] = json!(valid);
+    if !notes.is_empty() {
+        result["notes"] = json!(notes);
+    }
+    Ok(result)
+}
+
+/// Sign one row and either send it over `session` or, without one, embed the signed event.
+async fn bulk_row_entry(
+    signer: &Signer,
+    session: Option<&WriteSession>,
+    params: &BulkItemParams,
+    parent_z_ref: &str,
+    schema: Option<&HeaderSchema>,
+    index: usize,
+    row: Result<ItemRow, AppError>,
+) -> serde_json::Value {
+    let item_kind = item_kind(params.addressable);
+    let row = match row.and_then(|row| merge_row(row, params)) {
+        Ok(row) => row,
+        Err(e) => return failed_entry(index, &CommandError::from(e)),
+    };
+    let (tags, d_tag) = item_tags(
+        item_kind,
+        parent_z_ref,
+        &row.resource,
+        &row.fields,
+        row.d_tag,
+    );
+    let warnings = match enforce_schema(schema, &tags) {
+        Ok(warnings) => warnings,
+        Err(e) => {
+            let mut entry = failed_entry(index, &CommandError::from(e));
+            entry["resource"] = json!(row.resource);
+            return entry;
         }
+    };
+    let builder = EventBuilder::new(item_kind, row.content.as_deref().unwrap_or("")).tags(tags);
+    let event = match signer.sign(builder).await {
+        Ok(event) => event,
+        Err(e) => return failed_entry(index, &CommandError::from(e)),
+    };
+
+    let mut entry = json!({
+        "index": index, "ok": true, "event_id": event.id.to_hex(),
+        "resource": row.resource,
+    });
+    if let Some(d) = d_tag {
+        entry["d_tag"] = json!(d);
+    }
+    if !warnings.is_empty() {
+        entry["warnings"] = json!(warnings);
+    }
+    match session {
+        None => entry["event"] = serde_json::to_value(&event).unwrap_or_default(),
+        Some(session) => match session.send(&event).await {
+            Ok(reports) => entry["relays"] = json!(reports_json(&reports)),
+            Err(e) => {
+                let resource = entry["resource"].take();
+                entry = failed_entry(index, &e);
+                entry["resource"] = resource;
+                entry["event_id"] = json!(event.id.to_hex());
+            }
+        },
+    }
+    entry
+}
+
+fn bulk_actions(
+    relays: &[String],
+    parent_z_ref: &str,
+    result: &serde_json::Value,
+) -> Vec<NextAction> {
+    let relay = relays_arg(relays);
+    let mut actions = Vec::new();
+    if result["failed"].as_u64().unwrap_or(0) > 0 {
+        actions.push(NextAction::new(
+            "wokhei add-item <same flags> --from=<file with only the failed rows>",
+            "Fix the rows with ok=false (see results[].error) and add just those",
+        ));
+    }
+    if result.get("signed_only").is_some() {
+        actions.push(NextAction::new(
+            "wokhei broadcast <signed-json-file-or-stdin> [--relay=<url>[,<url>...]]",
+            "Send each results[].event unchanged",
+        ));
+    } else if parent_z_ref.starts_with("39998:") {
+        actions.push(NextAction::new(
+            format!("wokhei list-items --relay={relay} --header-coordinate=\"{parent_z_ref}\""),
+            "List all items in this list",
+        ));
+    } else {
+        actions.push(NextAction::new(
+            format!("wokhei list-items --relay={relay} {parent_z_ref}"),
+            "List all items in this list",
+        ));
+    }
+    actions
+}
+
+/// Add every row of `--from` to one header: the parent is resolved once and all rows go
+/// over one relay session. A bad row fails only its own entry.
+pub async fn add_items(params: BulkItemParams) -> Result<CommandOutput, CommandError> {
+    check_header_flags(
+        params.header.as_deref(),
+        params.header_coordinate.as_deref(),
+        params.sign_only,
+    )?;
+    let rows = read_item_rows(&params.from)?;
+
+    if params.dry_run {
+        let actions = vec![NextAction::new(
+            "wokhei add-item <same flags without --dry-run>",
+            "Sign and publish these items",
+        )];
+        return Ok(CommandOutput::new(dry_run_items(&params, rows)?).next_actions(actions));
+    }
+
+    let signer = Signer::load(&params.signer)?;
+    let session = if params.sign_only {
+        None
+    } else {
+        Some(WriteSession::connect(&params.relays).await?)
+    };
+
+    let result = async {
+        let (parent_z_ref, header_event) = match &session {
+            None => (
+                coordinate_z_ref(params.header_coordinate.as_deref().unwrap_or_default())?,
+                None,
+            ),
+            Some(session) => {
+                resolve_header_ref(
+                    session.client(),
+                    &relays_arg(&params.relays),
+                    "<url>",
+                    params.header.as_deref(),
+                    params.header_coordinate.as_deref(),
+                )
+                .await?
+            }
+        };
+        let schema = header_event.as_ref().map(header_schema);
+
+        let mut results = Vec::with_capacity(rows.len());
+        for (index, row) in rows.into_iter().enumerate() {
+            results.push(
+                bulk_row_entry(
+                    &signer,
+                    session.as_ref(),
+                    &params,
+                    &parent_z_ref,
+                    schema.as_ref(),
+                    index,
+                    row,
+                )
+                .await,
+            );
+        }
+
+        let mut result = batch_result(results);
+        result["kind"] = json!(item_kind(params.addressable).as_u16());
+        result["header_ref"] = json!(parent_z_ref);
+        if params.sign_only {
+            result["signed_only"] = json!(true);
+        }
+        let actions = bulk_actions(&params.relays, &parent_z_ref, &result);
+        Ok(CommandOutput::new(result).next_actions(actions))
     }
     .await;
 
-    client.disconnect().await;
+    if let Some(session) = session {
+        session.disconnect().await;
+    }
+    signer.shutdown().await;
+    result
+}
+
+// ---------------------------------------------------------------------------
+// update-item
+// ---------------------------------------------------------------------------
+
+/// Field edits for `update-item`. Each `set` entry is `key=value` and replaces every tag
+/// named `key`; `unset` removes them; `content` replaces the content.
+#[derive(Debug, Default, Clone)]
+pub struct ItemChanges {
+    pub set: Vec<String>,
+    pub unset: Vec<String>,
+    pub content: Option<String>,
+}
+
+impl ItemChanges {
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.unset.is_empty() && self.content.is_none()
+    }
+
+    /// Malformed `--set` entries, and `z`/`d` in either list: an update keeps the item's
+    /// list and identity.
+    fn violations(&self) -> Vec<Violation> {
+        let mut out = check_field_args(&self.set);
+        for key in self
+            .unset
+            .iter()
+            .filter(|k| matches!(k.as_str(), "z" | "d"))
+        {
+            out.push(Violation::new(
+                "RESERVED_FIELD",
+                format!("field {key:?} is managed by wokhei and cannot be unset"),
+            ));
+        }
+        out
+    }
+
+    /// `tags` with the edits applied; other tags keep their values and order.
+    pub fn apply(&self, tags: &[Tag]) -> Vec<Tag> {
+        let mut parts: TagParts = tags
+            .iter()
+            .map(|t| t.as_slice().to_vec())
+            .filter(|t| !t.is_empty())
+            .collect();
+        for (key, value) in self.set.iter().filter_map(|f| f.split_once('=')) {
+            set_tag(&mut parts, key, Some(vec![value.to_string()]));
+        }
+        parts.retain(|t| !self.unset.contains(&t[0]));
+        parts
+            .into_iter()
+            .filter_map(|t| Tag::parse(t).ok())
+            .collect()
+    }
+}
+
+pub struct UpdateItemParams {
+    pub relays: Vec<String>,
+    pub signer: SignerSpec,
+    /// `39999:<pubkey>:<d-tag>` or the event ID of any version of the item.
+    pub target: String,
+    pub changes: ItemChanges,
+    pub sign_only: bool,
+    pub dry_run: bool,
+}
+
+/// The header an item's `z` points at, if the relays have it.
+async fn parent_header(client: &Client, relay: &str, z_ref: &str) -> Option<Event> {
+    if z_ref.starts_with("39998:") {
+        return fetch_coordinate_header(client, relay, z_ref).await;
+    }
+    let filter = Filter::new().id(EventId::parse(z_ref).ok()?).limit(1);
+    client
+        .fetch_events(filter, Duration::from_secs(10))
+        .await
+        .ok()?
+        .into_iter()
+        .next()
+}
+
+/// The current item, its new tags and content, and the result so far (`diff`, `changed`,
+/// header-schema `warnings`).
+async fn prepare_item_update(
+    client: &Client,
+    relay: &str,
+    params: &UpdateItemParams,
+) -> Result<(Event, Vec<Tag>, String, serde_json::Value), CommandError> {
+    let current =
+        fetch_current_addressable(client, relay, &params.target, Kind::Custom(39999)).await?;
+    let tags = params.changes.apply(current.tags.as_slice());
+    reject(&check_event(39999, &tags)).map_err(CommandError::from)?;
+
+    let header_ref = tags
+        .iter()
+        .map(Tag::as_slice)
+        .find(|t| t[0] == "z")
+        .and_then(|t| t.get(1).cloned())
+        .unwrap_or_default();
+    let header = parent_header(client, relay, &header_ref).await;
+    let schema = header.as_ref().map(header_schema);
+    let warnings = enforce_schema(schema.as_ref(), &tags)
+        .map_err(|e| schema_error(e, relay, header.as_ref()))?;
+
+    let content = params
+        .changes
+        .content
+        .clone()
+        .unwrap_or_else(|| current.content.clone());
+    let d_tag = crate::query::header_d_tag(&current).unwrap_or_default();
+    let mut result = json!({
+        "previous_event_id": current.id.to_hex(),
+        "kind": 39999,
+        "d_tag": d_tag,
+        "coordinate": format!("39999:{}:{d_tag}", current.pubkey.to_hex()),
+        "header_ref": header_ref,
+        "diff": tag_diff(current.tags.as_slice(), &tags),
+        "changed": tags != current.tags.as_slice() || content != current.content,
+    });
+    if content != current.content {
+        result["diff"]["content"] = json!({ "old": current.content, "new": content });
+    }
+    if !warnings.is_empty() {
+        result["warnings"] = json!(warnings);
+    }
+    Ok((current, tags, content, result))
+}
+
+/// Republish an addressable item with `changes` applied, keeping its `d` and `z`.
+/// `--dry-run` needs no keys; nothing is signed when the edits change nothing.
+pub async fn update_item(params: UpdateItemParams) -> Result<CommandOutput, CommandError> {
+    if params.changes.is_empty() {
+        return Err(CommandError::new(
+            "update-item needs at least one change",
+            "MISSING_ARG",
+            "Pass e.g. --set=title=<value>, --unset=<field> or --content=<text>",
+        ));
+    }
+    reject(&params.changes.violations()).map_err(CommandError::from)?;
+    let signer = if params.dry_run {
+        None
+    } else {
+        Some(Signer::load(&params.signer)?)
+    };
+    let relay = relays_arg(&params.relays);
+    let session = WriteSession::connect(&params.relays).await?;
+
+    let result = async {
+        let (current, tags, content, mut result) =
+            prepare_item_update(session.client(), &relay, &params).await?;
+        let Some(signer) = signer.as_ref().filter(|_| result["changed"] == true) else {
+            result["tags"] = tags_json(&tags);
+            result["content"] = json!(content);
+            if params.dry_run {
+                result["dry_run"] = json!(true);
+            }
+            let actions = vec![NextAction::new(
+                format!("wokhei inspect --relay={relay} {}", current.id.to_hex()),
+                "Inspect the current item",
+            )];
+            return Ok(CommandOutput::new(result).next_actions(actions));
+        };
+
+        if signer.public_key().await.map_err(CommandError::from)? != current.pubkey {
+            let coordinate = result["coordinate"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            return Err(CommandError::from(AppError::NotAuthor { coordinate }));
+        }
+        let builder = EventBuilder::new(Kind::Custom(39999), content)
+            .tags(tags)
+            .custom_created_at(replacement_created_at(&current));
+        let event = signer.sign(builder).await.map_err(CommandError::from)?;
+        result["event_id"] = json!(event.id.to_hex());
+        if params.sign_only {
+            return Ok(signed_output(&event, result));
+        }
+
+        let reports = session.send(&event).await?;
+        result["relays"] = json!(reports_json(&reports));
+        let header_ref = result["header_ref"].as_str().unwrap_or_default();
+        let list_items_cmd = if header_ref.starts_with("39998:") {
+            format!("wokhei list-items --relay={relay} --header-coordinate=\"{header_ref}\"")
+        } else {
+            format!("wokhei list-items --relay={relay} {header_ref}")
+        };
+        let actions = vec![
+            NextAction::new(
+                format!("wokhei inspect --relay={relay} {}", event.id.to_hex()),
+                "Inspect the updated item",
+            ),
+            NextAction::new(list_items_cmd, "List all items in this list"),
+        ];
+        Ok(CommandOutput::new(result).next_actions(actions))
+    }
+    .await;
+
+    session.disconnect().await;
+    if let Some(signer) = signer {
+        signer.shutdown().await;
+    }
     result
 }
 
@@ -270,7 +907,10 @@ mod tests {
 
     fn base_params(header: Option<String>, header_coordinate: Option<String>) -> ItemParams {
         ItemParams {
-            relay: "ws://localhost:7777".into(),
+            relays: vec!["ws://localhost:7777".into()],
+            signer: SignerSpec::Local {
+                profile: "default".into(),
+            },
             header,
             header_coordinate,
             resource: "https://example.com".into(),
@@ -278,6 +918,8 @@ mod tests {
             fields: vec![],
             addressable: false,
             d_tag: None,
+            sign_only: false,
+            dry_run: false,
         }
     }
 
@@ -300,6 +942,85 @@ mod tests {
         assert_eq!(err.code, "MISSING_ARG");
     }
 
+    #[test]
+    fn validate_sign_only_requires_coordinate() {
+        let mut p = base_params(Some("abc123".into()), None);
+        p.sign_only = true;
+        let err = validate_item_params(&p).unwrap_err();
+        assert_eq!(err.code, "INVALID_ARGS");
+
+        let mut p = base_params(None, Some("39998:pk:d".into()));
+        p.sign_only = true;
+        assert!(validate_item_params(&p).is_ok());
+    }
+
+    #[test]
+    fn item_event_builder_fills_auto_d_tag_for_addressable() {
+        let (_, d_tag) = item_event_builder(
+            Kind::Custom(39999),
+            "39998:pk:list",
+            "https://x",
+            &[],
+            None,
+            None,
+        );
+        assert_eq!(
+            d_tag,
+            Some(crate::dtag::item_dtag("39998:pk:list", "https://x"))
+        );
+
+        let (_, d_tag) =
+            item_event_builder(Kind::Custom(9999), "abc123", "https://x", &[], None, None);
+        assert!(d_tag.is_none());
+    }
+
+    // -----------------------------------------------------------------------
+    // dry_run_item
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn dry_run_item_with_coordinate_derives_z_and_d() {
+        let pk = test_pubkey_hex();
+        let coord = format!("39998:{pk}:books");
+        let mut p = base_params(None, Some(coord.clone()));
+        p.addressable = true;
+        p.fields = vec!["title=Dune".into()];
+        let j = dry_run_item(&p).unwrap();
+        assert_eq!(j["kind"], 39999);
+        assert_eq!(j["header_ref"], coord);
+        assert_eq!(
+            j["d_tag"],
+            crate::dtag::item_dtag(&coord, "https://example.com")
+        );
+        assert_eq!(j["tags"][0], json!(["z", coord]));
+        assert_eq!(j["valid"], true);
+        assert!(j.get("notes").is_none());
+    }
+
+    #[test]
+    fn dry_run_item_with_header_id_notes_assumption() {
+        let id = EventId::all_zeros().to_hex();
+        let j = dry_run_item(&base_params(Some(id.clone()), None)).unwrap();
+        assert_eq!(j["header_ref"], id);
+        assert!(j["notes"][0].as_str().unwrap().contains("9998"));
+    }
+
+    #[test]
+    fn dry_run_item_reports_bad_fields() {
+        let id = EventId::all_zeros().to_hex();
+        let mut p = base_params(Some(id), None);
+        p.fields = vec!["orphan".into()];
+        let j = dry_run_item(&p).unwrap();
+        assert_eq!(j["valid"], false);
+        assert_eq!(j["violations"][0]["rule"], "FIELD_WITHOUT_VALUE");
+    }
+
+    #[test]
+    fn dry_run_item_rejects_bad_header_id() {
+        let err = dry_run_item(&base_params(Some("nope".into()), None)).unwrap_err();
+        assert_eq!(err.code, "INVALID_EVENT_ID");
+    }
+
     // -----------------------------------------------------------------------
     // build_item_tags (via dcosl-core)
     // -----------------------------------------------------------------------
@@ -354,4 +1075,275 @@ mod tests {
         let tags = build_item_tags("abc123", "https://example.com", &[], None);
         assert!(find_tag(&tags, "d").is_none());
     }
+
+    // -----------------------------------------------------------------------
+    // Bulk mode
+    // -----------------------------------------------------------------------
+
+    fn bulk_params(header_coordinate: String) -> BulkItemParams {
+        BulkItemParams {
+            relays: vec![],
+            signer: SignerSpec::Local {
+                profile: "default".into(),
+            },
+            header: None,
+            header_coordinate: Some(header_coordinate),
+            from: "items.csv".into(),
+            content: Some("shared".into()),
+            fields: vec!["category=jazz".into()],
+            addressable: true,
+            sign_only: false,
+            dry_run: true,
+        }
+    }
+
+    fn row(resource: &str, fields: &[&str]) -> ItemRow {
+        ItemRow {
+            resource: resource.into(),
+            fields: fields.iter().map(|f| (*f).to_string()).collect(),
+            ..ItemRow::default()
+        }
+    }
+
+    #[test]
+    fn merge_row_prepends_shared_fields_and_falls_back_to_shared_content() {
+        let params = bulk_params(format!("39998:{}:jazz", test_pubkey_hex()));
+        let merged = merge_row(row("https://a", &["title=A"]), &params).unwrap();
+        assert_eq!(merged.fields, vec!["category=jazz", "title=A"]);
+        assert_eq!(merged.content.as_deref(), Some("shared"));
+    }
+
+    #[test]
+    fn merge_row_rejects_reserved_columns() {
+        let params = bulk_params(format!("39998:{}:jazz", test_pubkey_hex()));
+        let err = merge_row(row("https://a", &["z=x"]), &params).unwrap_err();
+        assert_eq!(err.code(), "RESERVED_FIELD");
+    }
+
+    #[test]
+    fn dry_run_items_reports_each_row() {
+        let coord = format!("39998:{}:jazz", test_pubkey_hex());
+        let params = bulk_params(coord.clone());
+        let rows = vec![
+            Ok(row("https://a", &["title=A"])),
+            Err(AppError::InvalidRow {
+                reason: "line 3: missing resource".into(),
+            }),
+            Ok(row("https://c", &["d=x"])),
+        ];
+        let j = dry_run_items(&params, rows).unwrap();
+        assert_eq!(j["header_ref"], coord);
+        assert_eq!(j["count"], 3);
+        assert_eq!(j["failed"], 1);
+        assert_eq!(j["valid"], false);
+        assert_eq!(j["results"][0]["valid"], true);
+        assert_eq!(j["results"][0]["tags"][0][0], "z");
+        assert_eq!(j["results"][1]["error"]["code"], "INVALID_ROW");
+        assert_eq!(j["results"][2]["violations"][0]["rule"], "RESERVED_FIELD");
+    }
+
+    #[tokio::test]
+    async fn bulk_rows_share_one_session() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let relays = vec![relay.url().await.to_string()];
+        let session = WriteSession::connect(&relays).await.unwrap();
+        let signer = Signer::Local(Keys::generate());
+        let coord = format!("39998:{}:jazz", test_pubkey_hex());
+        let params = bulk_params(coord.clone());
+
+        let mut entries = Vec::new();
+        for (index, resource) in ["https://a", "https://b"].into_iter().enumerate() {
+            let entry = bulk_row_entry(
+                &signer,
+                Some(&session),
+                &params,
+                &coord,
+                None,
+                index,
+                Ok(row(resource, &[])),
+            )
+            .await;
+            entries.push(entry);
+        }
+        session.disconnect().await;
+
+        assert!(entries.iter().all(|e| e["ok"] == true));
+        assert_eq!(entries[1]["relays"][0]["status"], "accepted");
+        assert_ne!(entries[0]["d_tag"], entries[1]["d_tag"]);
+    }
+
+    #[tokio::test]
+    async fn bulk_rows_enforce_header_required_fields() {
+        let signer = Signer::Local(Keys::generate());
+        let coord = format!("39998:{}:jazz", test_pubkey_hex());
+        let params = bulk_params(coord.clone());
+        let schema = HeaderSchema {
+            required: vec!["title".into()],
+            recommended: vec!["category".into()],
+        };
+
+        let missing = bulk_row_entry(
+            &signer,
+            None,
+            &params,
+            &coord,
+            Some(&schema),
+            0,
+            Ok(row("https://a", &[])),
+        )
+        .await;
+        assert_eq!(missing["ok"], false);
+        assert_eq!(missing["error"]["code"], "MISSING_REQUIRED_FIELDS");
+        assert_eq!(missing["resource"], "https://a");
+
+        let unknown = bulk_row_entry(
+            &signer,
+            None,
+            &params,
+            &coord,
+            Some(&schema),
+            1,
+            Ok(row("https://b", &["title=B", "year=1959"])),
+        )
+        .await;
+        assert_eq!(unknown["ok"], true);
+        assert!(unknown["warnings"][0].as_str().unwrap().contains("year"));
+    }
+
+    #[tokio::test]
+    async fn resolve_header_ref_fetches_coordinate_header() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let url = relay.url().await.to_string();
+        let keys = Keys::generate();
+        let header = EventBuilder::new(Kind::Custom(39998), "")
+            .tags([
+                Tag::custom(TagKind::custom("names"), ["book", "books"]),
+                Tag::custom(TagKind::custom("required"), ["title"]),
+                Tag::identifier("books"),
+            ])
+            .sign_with_keys(&keys)
+            .unwrap();
+        let client = crate::query::connect_client(std::slice::from_ref(&url))
+            .await
+            .unwrap();
+        client.send_event(&header).await.unwrap();
+
+        let coord = format!("39998:{}:books", keys.public_key().to_hex());
+        let (z_ref, found) = resolve_header_ref(&client, &url, "<url>", None, Some(&coord))
+            .await
+            .unwrap();
+        assert_eq!(z_ref, coord);
+        assert_eq!(header_schema(&found.unwrap()).required, vec!["title"]);
+
+        let absent = format!("39998:{}:other", keys.public_key().to_hex());
+        let (_, found) = resolve_header_ref(&client, &url, "<url>", None, Some(&absent))
+            .await
+            .unwrap();
+        assert!(found.is_none());
+        client.disconnect().await;
+    }
+
+    // -----------------------------------------------------------------------
+    // update-item
+    // -----------------------------------------------------------------------
+
+    fn changes(set: &[&str], unset: &[&str]) -> ItemChanges {
+        ItemChanges {
+            set: set.iter().map(ToString::to_string).collect(),
+            unset: unset.iter().map(ToString::to_string).collect(),
+            content: None,
+        }
+    }
+
+    #[test]
+    fn item_changes_set_and_unset_fields_in_place() {
+        let old = build_item_tags(
+            "39998:pk:list",
+            "https://x",
+            &["title=Old".into(), "year=1959".into(), "tag=a".into()],
+            Some("x--1"),
+        );
+        let new = changes(&["title=New", "label=Blue Note"], &["year"]).apply(&old);
+        let names: Vec<&str> = new.iter().map(|t| t.as_slice()[0].as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["z", "r", "client", "title", "tag", "d", "label"]
+        );
+        assert_eq!(
+            tag_values(find_tag(&new, "title").unwrap()),
+            vec!["title", "New"]
+        );
+    }
+
+    #[test]
+    fn item_changes_reject_reserved_and_bare_fields() {
+        let v = changes(&["z=other", "orphan"], &["d"]).violations();
+        let rules: Vec<&str> = v.iter().map(|v| v.rule).collect();
+        assert_eq!(
+            rules,
+            vec!["RESERVED_FIELD", "FIELD_WITHOUT_VALUE", "RESERVED_FIELD"]
+        );
+        assert!(ItemChanges::default().is_empty());
+    }
+
+    #[tokio::test]
+    async fn prepare_item_update_diffs_and_enforces_header_fields() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let url = relay.url().await.to_string();
+        let keys = Keys::generate();
+        let header = EventBuilder::new(Kind::Custom(39998), "")
+            .tags([
+                Tag::custom(TagKind::custom("names"), ["album", "albums"]),
+                Tag::custom(TagKind::custom("required"), ["title"]),
+                Tag::identifier("albums"),
+            ])
+            .sign_with_keys(&keys)
+            .unwrap();
+        let coord = format!("39998:{}:albums", keys.public_key().to_hex());
+        let item = EventBuilder::new(Kind::Custom(39999), "old")
+            .tags(build_item_tags(
+                &coord,
+                "https://kob",
+                &["title=Kind of Blue".into()],
+                Some("kob"),
+            ))
+            .sign_with_keys(&keys)
+            .unwrap();
+        let client = crate::query::connect_client(std::slice::from_ref(&url))
+            .await
+            .unwrap();
+        client.send_event(&header).await.unwrap();
+        client.send_event(&item).await.unwrap();
+
+        let mut params = UpdateItemParams {
+            relays: vec![url.clone()],
+            signer: SignerSpec::Local {
+                profile: "default".into(),
+            },
+            target: format!("39999:{}:kob", keys.public_key().to_hex()),
+            changes: ItemChanges {
+                content: Some("new".into()),
+                ..changes(&["year=1959"], &[])
+            },
+            sign_only: false,
+            dry_run: true,
+        };
+        let (current, tags, content, result) =
+            prepare_item_update(&client, &url, &params).await.unwrap();
+        assert_eq!(current.id, item.id);
+        assert_eq!(content, "new");
+        assert_eq!(find_tag(&tags, "d"), find_tag(current.tags.as_slice(), "d"));
+        assert_eq!(result["header_ref"], coord);
+        assert_eq!(result["changed"], true);
+        assert_eq!(result["diff"]["added"], json!([["year", "1959"]]));
+        assert_eq!(result["diff"]["content"]["old"], "old");
+        assert!(result["warnings"][0].as_str().unwrap().contains("year"));
+
+        params.changes = changes(&[], &["title"]);
+        let err = prepare_item_update(&client, &url, &params)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, "MISSING_REQUIRED_FIELDS");
+        client.disconnect().await;
+    }
 }
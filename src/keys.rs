@@ -12,58 +12,198 @@ use crate::error::AppError;
 // Parameterized path helpers (testable without touching $HOME)
 // ---------------------------------------------------------------------------
 
-fn keys_dir_from(base: &Path) -> PathBuf {
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub fn keys_dir_from(base: &Path) -> PathBuf {
     base.join(".wokhei")
 }
 
-fn keys_path_from(base: &Path) -> PathBuf {
-    keys_dir_from(base).join("keys")
+fn profiles_dir_from(base: &Path) -> PathBuf {
+    keys_dir_from(base).join("profiles")
+}
+
+/// `default` keeps the original `~/.wokhei/keys`; named profiles live in
+/// `~/.wokhei/profiles/<name>/keys`.
+fn profile_dir_from(base: &Path, profile: &str) -> PathBuf {
+    if profile == DEFAULT_PROFILE {
+        keys_dir_from(base)
+    } else {
+        profiles_dir_from(base).join(profile)
+    }
+}
+
+fn keys_path_from(base: &Path, profile: &str) -> PathBuf {
+    profile_dir_from(base, profile).join("keys")
 }
 
-fn home_base() -> PathBuf {
+pub fn home_base() -> PathBuf {
     dirs::home_dir().unwrap_or_else(|| PathBuf::from("."))
 }
 
-fn keys_path() -> PathBuf {
-    keys_path_from(&home_base())
+fn keys_path(profile: &str) -> PathBuf {
+    keys_path_from(&home_base(), profile)
+}
+
+pub fn keys_exist(profile: &str) -> bool {
+    keys_path(profile).exists()
+}
+
+/// Profile names become directory names: lowercase ASCII letters, digits, `-` and `_`.
+pub fn validate_profile(profile: &str) -> Result<(), AppError> {
+    let valid = !profile.is_empty()
+        && profile.len() <= 64
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidProfile {
+            name: profile.to_string(),
+        })
+    }
+}
+
+/// Every profile with a keys file: `default` first (if present), then named ones sorted.
+fn list_profiles_from(base: &Path) -> Vec<String> {
+    let mut profiles = Vec::new();
+    if keys_path_from(base, DEFAULT_PROFILE).exists() {
+        profiles.push(DEFAULT_PROFILE.to_string());
+    }
+
+    let mut named: Vec<String> = fs::read_dir(profiles_dir_from(base))
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name != DEFAULT_PROFILE && validate_profile(name).is_ok())
+                .filter(|name| keys_path_from(base, name).exists())
+                .collect()
+        })
+        .unwrap_or_default();
+    named.sort();
+    profiles.extend(named);
+    profiles
 }
 
-pub fn keys_exist() -> bool {
-    keys_path().exists()
+// ---------------------------------------------------------------------------
+// NIP-49 encrypted storage
+// ---------------------------------------------------------------------------
+
+const NCRYPTSEC_PREFIX: &str = "ncryptsec1";
+
+/// scrypt cost for newly encrypted keys (NIP-49 recommends 16+).
+const NCRYPTSEC_LOG_N: u8 = 16;
+
+fn is_ncryptsec(stored: &str) -> bool {
+    stored.trim_start().starts_with(NCRYPTSEC_PREFIX)
 }
 
-fn load_keys_from(base: &Path) -> Result<Keys, AppError> {
-    let path = keys_path_from(base);
+fn read_passphrase(
+    passphrase: Option<String>,
+    passphrase_file: Option<String>,
+) -> Result<Option<String>, AppError> {
+    if let Some(p) = passphrase.filter(|p| !p.is_empty()) {
+        return Ok(Some(p));
+    }
+    match passphrase_file {
+        Some(path) => {
+            let contents = fs::read_to_string(&path).map_err(|e| AppError::Io {
+                reason: format!("Failed to read passphrase file {path}: {e}"),
+            })?;
+            Ok(Some(contents.trim_end_matches(['\r', '\n']).to_string()))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Passphrase from `WOKHEI_PASSPHRASE`, or the file named by `WOKHEI_PASSPHRASE_FILE`.
+fn passphrase_from_env() -> Result<Option<String>, AppError> {
+    read_passphrase(
+        std::env::var("WOKHEI_PASSPHRASE").ok(),
+        std::env::var("WOKHEI_PASSPHRASE_FILE").ok(),
+    )
+}
+
+fn decrypt_ncryptsec(ncryptsec: &str, passphrase: Option<&str>) -> Result<Keys, AppError> {
+    let passphrase = passphrase.ok_or_else(|| AppError::PassphraseRequired {
+        action: "decrypt ncryptsec key".to_string(),
+    })?;
+    let encrypted =
+        EncryptedSecretKey::from_bech32(ncryptsec).map_err(|e| AppError::DecryptionFailed {
+            reason: e.to_string(),
+        })?;
+    let secret_key = encrypted
+        .decrypt(passphrase)
+        .map_err(|e| AppError::DecryptionFailed {
+            reason: e.to_string(),
+        })?;
+    Ok(Keys::new(secret_key))
+}
+
+fn encrypt_keys(keys: &Keys, passphrase: &str, log_n: u8) -> Result<String, AppError> {
+    let save_failed = |reason: String| AppError::KeysSaveFailed { reason };
+    EncryptedSecretKey::new(keys.secret_key(), passphrase, log_n, KeySecurity::Unknown)
+        .map_err(|e| save_failed(e.to_string()))?
+        .to_bech32()
+        .map_err(|e| save_failed(e.to_string()))
+}
+
+/// Parse stored or imported key material: plain `nsec1…`/hex, or NIP-49 `ncryptsec1…`.
+fn parse_key_material(raw: &str, passphrase: Option<&str>) -> Result<Keys, AppError> {
+    let raw = raw.trim();
+    if is_ncryptsec(raw) {
+        decrypt_ncryptsec(raw, passphrase)
+    } else {
+        Keys::parse(raw).map_err(|_| AppError::InvalidNsec)
+    }
+}
+
+fn read_stored_key(base: &Path, profile: &str) -> Result<String, AppError> {
+    let path = keys_path_from(base, profile);
     if !path.exists() {
         return Err(AppError::KeysNotFound {
             path: path.display().to_string(),
         });
     }
-    let nsec = fs::read_to_string(&path).map_err(|e| AppError::Io {
+    fs::read_to_string(&path).map_err(|e| AppError::Io {
         reason: e.to_string(),
-    })?;
-    Keys::parse(nsec.trim()).map_err(|_| AppError::InvalidNsec)
+    })
 }
 
-pub fn load_keys() -> Result<Keys, AppError> {
-    load_keys_from(&home_base())
+fn load_keys_from(base: &Path, profile: &str, passphrase: Option<&str>) -> Result<Keys, AppError> {
+    parse_key_material(&read_stored_key(base, profile)?, passphrase)
 }
 
-fn save_keys_at(base: &Path, keys: &Keys) -> Result<(), AppError> {
-    let dir = keys_dir_from(base);
-    fs::create_dir_all(&dir).map_err(|e| AppError::KeysSaveFailed {
-        reason: e.to_string(),
-    })?;
+pub fn load_keys(profile: &str) -> Result<Keys, AppError> {
+    let passphrase = passphrase_from_env()?;
+    load_keys_from(&home_base(), profile, passphrase.as_deref())
+}
 
-    let path = keys_path_from(base);
+fn save_keys_at(base: &Path, profile: &str, keys: &Keys) -> Result<(), AppError> {
     let nsec = keys
         .secret_key()
         .to_bech32()
         .map_err(|e| AppError::KeysSaveFailed {
             reason: e.to_string(),
         })?;
+    write_key_file(base, profile, &nsec)
+}
 
-    fs::write(&path, &nsec).map_err(|e| AppError::KeysSaveFailed {
+fn write_key_file(base: &Path, profile: &str, contents: &str) -> Result<(), AppError> {
+    write_secret_file(
+        &profile_dir_from(base, profile),
+        &keys_path_from(base, profile),
+        contents,
+    )
+}
+
+fn write_secret_file(dir: &Path, path: &Path, contents: &str) -> Result<(), AppError> {
+    fs::create_dir_all(dir).map_err(|e| AppError::KeysSaveFailed {
+        reason: e.to_string(),
+    })?;
+
+    fs::write(path, contents).map_err(|e| AppError::KeysSaveFailed {
         reason: e.to_string(),
     })?;
 
@@ -71,7 +211,7 @@ fn save_keys_at(base: &Path, keys: &Keys) -> Result<(), AppError> {
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| {
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|e| {
             AppError::KeysSaveFailed {
                 reason: e.to_string(),
             }
@@ -81,28 +221,98 @@ fn save_keys_at(base: &Path, keys: &Keys) -> Result<(), AppError> {
     Ok(())
 }
 
-fn save_keys(keys: &Keys) -> Result<(), AppError> {
-    save_keys_at(&home_base(), keys)
+fn bunker_client_path_from(base: &Path, profile: &str) -> PathBuf {
+    profile_dir_from(base, profile).join("bunker-client")
+}
+
+/// App-side NIP-46 session key for `profile`, created on first use so a bunker
+/// sees the same client pubkey across invocations. It cannot sign as the user.
+pub fn bunker_client_keys_from(base: &Path, profile: &str) -> Result<Keys, AppError> {
+    let path = bunker_client_path_from(base, profile);
+    if let Ok(stored) = fs::read_to_string(&path) {
+        return Keys::parse(stored.trim()).map_err(|_| AppError::InvalidNsec);
+    }
+
+    let keys = Keys::generate();
+    let nsec = keys
+        .secret_key()
+        .to_bech32()
+        .map_err(|e| AppError::KeysSaveFailed {
+            reason: e.to_string(),
+        })?;
+    write_secret_file(&profile_dir_from(base, profile), &path, &nsec)?;
+    Ok(keys)
+}
+
+fn bunker_pairing_path_from(base: &Path, profile: &str) -> PathBuf {
+    profile_dir_from(base, profile).join("bunker-pairing")
+}
+
+/// The `bunker://` URI a signer paired with through `nostrconnect`, so later runs with the
+/// same `nostrconnect://` URI reuse it instead of waiting for the signer to connect again.
+pub fn bunker_pairing_from(base: &Path, profile: &str, nostrconnect: &str) -> Option<String> {
+    let stored: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(bunker_pairing_path_from(base, profile)).ok()?)
+            .ok()?;
+    if stored["nostrconnect"] != nostrconnect {
+        return None;
+    }
+    stored["bunker"].as_str().map(String::from)
+}
+
+/// Remember the pairing for `nostrconnect`, replacing any earlier one for the profile.
+pub fn save_bunker_pairing_from(
+    base: &Path,
+    profile: &str,
+    nostrconnect: &str,
+    bunker: &str,
+) -> Result<(), AppError> {
+    let pairing = json!({ "nostrconnect": nostrconnect, "bunker": bunker });
+    write_secret_file(
+        &profile_dir_from(base, profile),
+        &bunker_pairing_path_from(base, profile),
+        &pairing.to_string(),
+    )
+}
+
+fn save_keys(profile: &str, keys: &Keys) -> Result<(), AppError> {
+    save_keys_at(&home_base(), profile, keys)
+}
+
+fn save_encrypted_keys(profile: &str, ncryptsec: &str) -> Result<(), AppError> {
+    write_key_file(&home_base(), profile, ncryptsec)
 }
 
-fn keys_result(keys: &Keys) -> serde_json::Value {
+fn keys_result(keys: &Keys, profile: &str, encrypted: bool) -> serde_json::Value {
     let pubkey_hex = keys.public_key().to_hex();
     let npub = keys
         .public_key()
         .to_bech32()
         .unwrap_or_else(|_| pubkey_hex.clone());
     json!({
+        "profile": profile,
         "pubkey": pubkey_hex,
         "npub": npub,
-        "keys_path": keys_path().display().to_string()
+        "keys_path": keys_path(profile).display().to_string(),
+        "encrypted": encrypted,
     })
 }
 
-fn post_init_actions(pubkey_hex: &str) -> Vec<NextAction> {
+/// ` --profile=<name>` suffix for `next_actions`, empty for the default profile.
+pub fn profile_flag(profile: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        String::new()
+    } else {
+        format!(" --profile={profile}")
+    }
+}
+
+fn post_init_actions(pubkey_hex: &str, profile: &str) -> Vec<NextAction> {
+    let flag = profile_flag(profile);
     vec![
-        NextAction::new("wokhei whoami", "Verify your identity"),
+        NextAction::new(format!("wokhei whoami{flag}"), "Verify your identity"),
         NextAction::new(
-            "wokhei create-header --name=<singular> --plural=<plural>",
+            format!("wokhei create-header{flag} --name=<singular> --plural=<plural>"),
             "Create your first list header",
         ),
         NextAction::new(
@@ -136,7 +346,33 @@ fn read_nsec_from_source(source: &str) -> Result<String, CommandError> {
     read_nsec(source, io::stdin())
 }
 
-pub fn init(generate: bool, import: Option<&str>) -> Result<CommandOutput, CommandError> {
+/// Decide what gets written to the keys file: the original ncryptsec for encrypted
+/// imports, a freshly encrypted ncryptsec with `--encrypt`, or `None` for plain nsec.
+fn encrypted_contents(
+    keys: &Keys,
+    imported: Option<&str>,
+    encrypt: bool,
+    passphrase: Option<&str>,
+    log_n: u8,
+) -> Result<Option<String>, AppError> {
+    if let Some(raw) = imported.filter(|raw| is_ncryptsec(raw)) {
+        return Ok(Some(raw.to_string()));
+    }
+    if !encrypt {
+        return Ok(None);
+    }
+    let passphrase = passphrase.ok_or_else(|| AppError::PassphraseRequired {
+        action: "encrypt keys (--encrypt)".to_string(),
+    })?;
+    encrypt_keys(keys, passphrase, log_n).map(Some)
+}
+
+pub fn init(
+    profile: &str,
+    generate: bool,
+    import: Option<&str>,
+    encrypt: bool,
+) -> Result<CommandOutput, CommandError> {
     if !generate && import.is_none() {
         return Err(CommandError::new(
             "Specify --generate or --import <source>",
@@ -149,45 +385,123 @@ pub fn init(generate: bool, import: Option<&str>) -> Result<CommandOutput, Comma
         ]));
     }
 
-    let path = keys_path();
+    let path = keys_path(profile);
     if path.exists() {
         return Err(CommandError::from(AppError::KeysAlreadyExist {
             path: path.display().to_string(),
         })
         .next_actions(vec![NextAction::new(
-            "wokhei whoami",
+            format!("wokhei whoami{}", profile_flag(profile)),
             "Check current identity",
         )]));
     }
 
-    let keys = if generate {
-        Keys::generate()
+    let passphrase = passphrase_from_env().map_err(CommandError::from)?;
+    if encrypt && passphrase.is_none() {
+        return Err(CommandError::from(AppError::PassphraseRequired {
+            action: "encrypt keys (--encrypt)".to_string(),
+        }));
+    }
+
+    let (keys, imported) = if generate {
+        (Keys::generate(), None)
     } else if let Some(source) = import {
-        let nsec = read_nsec_from_source(source)?;
-        Keys::parse(&nsec).map_err(|_| {
-            CommandError::from(AppError::InvalidNsec).next_actions(vec![NextAction::new(
+        let raw = read_nsec_from_source(source)?;
+        let keys = parse_key_material(&raw, passphrase.as_deref()).map_err(|e| {
+            CommandError::from(e).next_actions(vec![NextAction::new(
                 "wokhei init --generate",
                 "Generate a new keypair instead",
             )])
-        })?
+        })?;
+        (keys, Some(raw))
     } else {
         unreachable!()
     };
 
-    save_keys(&keys).map_err(CommandError::from)?;
+    let encrypted = encrypted_contents(
+        &keys,
+        imported.as_deref(),
+        encrypt,
+        passphrase.as_deref(),
+        NCRYPTSEC_LOG_N,
+    )
+    .map_err(CommandError::from)?;
+
+    match encrypted {
+        Some(ref ncryptsec) => save_encrypted_keys(profile, ncryptsec),
+        None => save_keys(profile, &keys),
+    }
+    .map_err(CommandError::from)?;
 
     let pubkey_hex = keys.public_key().to_hex();
-    let actions = post_init_actions(&pubkey_hex);
-    Ok(CommandOutput::new(keys_result(&keys)).next_actions(actions))
+    let actions = post_init_actions(&pubkey_hex, profile);
+    Ok(CommandOutput::new(keys_result(&keys, profile, encrypted.is_some())).next_actions(actions))
+}
+
+/// One `whoami --all` row. Profiles whose key cannot be loaded (e.g. encrypted without a
+/// passphrase) still appear, with the error code instead of a pubkey.
+fn profile_summary(
+    base: &Path,
+    profile: &str,
+    active: &str,
+    passphrase: Option<&str>,
+) -> serde_json::Value {
+    let stored = read_stored_key(base, profile).unwrap_or_default();
+    let mut row = json!({
+        "profile": profile,
+        "active": profile == active,
+        "keys_path": keys_path_from(base, profile).display().to_string(),
+        "encrypted": is_ncryptsec(&stored),
+    });
+    match parse_key_material(&stored, passphrase) {
+        Ok(keys) => {
+            let pubkey = keys.public_key();
+            row["pubkey"] = json!(pubkey.to_hex());
+            row["npub"] = json!(pubkey.to_bech32().unwrap_or_else(|_| pubkey.to_hex()));
+        }
+        Err(e) => row["error"] = json!(e.code()),
+    }
+    row
+}
+
+fn whoami_all(profile: &str) -> Result<CommandOutput, CommandError> {
+    let base = home_base();
+    let passphrase = passphrase_from_env().map_err(CommandError::from)?;
+    let profiles: Vec<serde_json::Value> = list_profiles_from(&base)
+        .iter()
+        .map(|name| profile_summary(&base, name, profile, passphrase.as_deref()))
+        .collect();
+
+    let actions = vec![
+        NextAction::new(
+            "wokhei init --generate --profile=<name>",
+            "Create another identity",
+        ),
+        NextAction::new("wokhei whoami --profile=<name>", "Show a single identity"),
+    ];
+    Ok(CommandOutput::new(json!({
+        "active": profile,
+        "count": profiles.len(),
+        "profiles": profiles,
+    }))
+    .next_actions(actions))
 }
 
-pub fn whoami() -> Result<CommandOutput, CommandError> {
-    let keys = load_keys().map_err(|e| {
-        CommandError::from(e).next_actions(vec![NextAction::new(
-            "wokhei init --generate",
+pub fn whoami(profile: &str, all: bool) -> Result<CommandOutput, CommandError> {
+    if all {
+        return whoami_all(profile);
+    }
+
+    let not_found_actions = || {
+        vec![NextAction::new(
+            format!("wokhei init --generate{}", profile_flag(profile)),
             "Generate a new keypair",
-        )])
-    })?;
+        )]
+    };
+    let stored = read_stored_key(&home_base(), profile)
+        .map_err(|e| CommandError::from(e).next_actions(not_found_actions()))?;
+    let keys =
+        load_keys(profile).map_err(|e| CommandError::from(e).next_actions(not_found_actions()))?;
 
     let pubkey_hex = keys.public_key().to_hex();
     let actions = vec![
@@ -196,11 +510,17 @@ pub fn whoami() -> Result<CommandOutput, CommandError> {
             "List your headers",
         ),
         NextAction::new(
-            "wokhei create-header --name=<singular> --plural=<plural>",
+            format!(
+                "wokhei create-header{} --name=<singular> --plural=<plural>",
+                profile_flag(profile)
+            ),
             "Create a new list header",
         ),
     ];
-    Ok(CommandOutput::new(keys_result(&keys)).next_actions(actions))
+    Ok(
+        CommandOutput::new(keys_result(&keys, profile, is_ncryptsec(&stored)))
+            .next_actions(actions),
+    )
 }
 
 #[cfg(test)]
@@ -222,11 +542,111 @@ mod tests {
     fn keys_path_from_appends_keys() {
         let base = Path::new("/tmp/test-home");
         assert_eq!(
-            keys_path_from(base),
+            keys_path_from(base, DEFAULT_PROFILE),
             PathBuf::from("/tmp/test-home/.wokhei/keys")
         );
     }
 
+    #[test]
+    fn keys_path_from_named_profile() {
+        let base = Path::new("/tmp/test-home");
+        assert_eq!(
+            keys_path_from(base, "curator"),
+            PathBuf::from("/tmp/test-home/.wokhei/profiles/curator/keys")
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // validate_profile / list_profiles_from
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_profile_accepts_slugs() {
+        assert!(validate_profile("curator-bot_2").is_ok());
+    }
+
+    #[test]
+    fn validate_profile_rejects_path_traversal_and_uppercase() {
+        for bad in ["", "../x", "a/b", "Curator", "with space"] {
+            let err = validate_profile(bad).unwrap_err();
+            assert_eq!(err.code(), "INVALID_PROFILE", "{bad:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn list_profiles_default_first_then_sorted() {
+        let dir = tempfile::tempdir().unwrap();
+        save_keys_at(dir.path(), "zeta", &Keys::generate()).unwrap();
+        save_keys_at(dir.path(), "alpha", &Keys::generate()).unwrap();
+        save_keys_at(dir.path(), DEFAULT_PROFILE, &Keys::generate()).unwrap();
+        fs::create_dir_all(profiles_dir_from(dir.path()).join("empty")).unwrap();
+
+        assert_eq!(
+            list_profiles_from(dir.path()),
+            vec!["default", "alpha", "zeta"]
+        );
+    }
+
+    #[test]
+    fn list_profiles_empty_home() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(list_profiles_from(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn profiles_are_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = Keys::generate();
+        let b = Keys::generate();
+        save_keys_at(dir.path(), "a", &a).unwrap();
+        save_keys_at(dir.path(), "b", &b).unwrap();
+        assert_eq!(
+            load_keys_from(dir.path(), "a", None).unwrap().public_key(),
+            a.public_key()
+        );
+        assert_eq!(
+            load_keys_from(dir.path(), "b", None).unwrap().public_key(),
+            b.public_key()
+        );
+        assert!(load_keys_from(dir.path(), DEFAULT_PROFILE, None).is_err());
+    }
+
+    #[test]
+    fn bunker_client_keys_are_created_once_and_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = bunker_client_keys_from(dir.path(), "curator").unwrap();
+        let second = bunker_client_keys_from(dir.path(), "curator").unwrap();
+        assert_eq!(first.public_key(), second.public_key());
+        assert!(bunker_client_path_from(dir.path(), "curator").exists());
+        assert!(!keys_path_from(dir.path(), "curator").exists());
+    }
+
+    #[test]
+    fn bunker_pairing_is_kept_for_its_nostrconnect_uri_only() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(bunker_pairing_from(dir.path(), "curator", "nostrconnect://a").is_none());
+
+        save_bunker_pairing_from(dir.path(), "curator", "nostrconnect://a", "bunker://x").unwrap();
+        assert_eq!(
+            bunker_pairing_from(dir.path(), "curator", "nostrconnect://a").as_deref(),
+            Some("bunker://x")
+        );
+        assert!(bunker_pairing_from(dir.path(), "curator", "nostrconnect://b").is_none());
+        assert!(bunker_pairing_from(dir.path(), DEFAULT_PROFILE, "nostrconnect://a").is_none());
+    }
+
+    #[test]
+    fn profile_summary_reports_error_for_locked_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let ncryptsec = encrypt_keys(&Keys::generate(), "pw", 4).unwrap();
+        write_key_file(dir.path(), "locked", &ncryptsec).unwrap();
+        let row = profile_summary(dir.path(), "locked", "other", None);
+        assert_eq!(row["encrypted"], true);
+        assert_eq!(row["active"], false);
+        assert_eq!(row["error"], "PASSPHRASE_REQUIRED");
+        assert!(row.get("pubkey").is_none());
+    }
+
     // -----------------------------------------------------------------------
     // keys_result — pure function
     // -----------------------------------------------------------------------
@@ -234,21 +654,21 @@ mod tests {
     #[test]
     fn keys_result_contains_pubkey() {
         let keys = Keys::generate();
-        let j = keys_result(&keys);
+        let j = keys_result(&keys, DEFAULT_PROFILE, false);
         assert_eq!(j["pubkey"].as_str().unwrap(), keys.public_key().to_hex());
     }
 
     #[test]
     fn keys_result_npub_starts_with_npub1() {
         let keys = Keys::generate();
-        let j = keys_result(&keys);
+        let j = keys_result(&keys, DEFAULT_PROFILE, false);
         assert!(j["npub"].as_str().unwrap().starts_with("npub1"));
     }
 
     #[test]
     fn keys_result_has_keys_path() {
         let keys = Keys::generate();
-        let j = keys_result(&keys);
+        let j = keys_result(&keys, DEFAULT_PROFILE, false);
         assert!(j["keys_path"].as_str().unwrap().contains(".wokhei/keys"));
     }
 
@@ -258,19 +678,25 @@ mod tests {
 
     #[test]
     fn post_init_actions_non_empty() {
-        let actions = post_init_actions("abc123");
+        let actions = post_init_actions("abc123", DEFAULT_PROFILE);
         assert!(!actions.is_empty());
     }
 
     #[test]
     fn post_init_actions_contains_whoami() {
-        let actions = post_init_actions("abc123");
+        let actions = post_init_actions("abc123", DEFAULT_PROFILE);
         assert!(actions.iter().any(|a| a.command.contains("whoami")));
     }
 
+    #[test]
+    fn post_init_actions_named_profile_adds_flag() {
+        let actions = post_init_actions("abc123", "curator");
+        assert!(actions[0].command.contains("--profile=curator"));
+    }
+
     #[test]
     fn post_init_actions_contains_pubkey() {
-        let actions = post_init_actions("abc123");
+        let actions = post_init_actions("abc123", DEFAULT_PROFILE);
         assert!(actions.iter().any(|a| a.command.contains("abc123")));
     }
 
@@ -317,15 +743,15 @@ mod tests {
     fn save_and_load_roundtrip() {
         let dir = tempfile::tempdir().unwrap();
         let keys = Keys::generate();
-        save_keys_at(dir.path(), &keys).unwrap();
-        let loaded = load_keys_from(dir.path()).unwrap();
+        save_keys_at(dir.path(), DEFAULT_PROFILE, &keys).unwrap();
+        let loaded = load_keys_from(dir.path(), DEFAULT_PROFILE, None).unwrap();
         assert_eq!(loaded.public_key(), keys.public_key());
     }
 
     #[test]
     fn load_from_nonexistent_path_errors() {
         let dir = tempfile::tempdir().unwrap();
-        let err = load_keys_from(dir.path()).unwrap_err();
+        let err = load_keys_from(dir.path(), DEFAULT_PROFILE, None).unwrap_err();
         assert_eq!(err.code(), "KEYS_NOT_FOUND");
     }
 
@@ -333,8 +759,8 @@ mod tests {
     fn save_creates_directory_and_file() {
         let dir = tempfile::tempdir().unwrap();
         let keys = Keys::generate();
-        save_keys_at(dir.path(), &keys).unwrap();
-        assert!(keys_path_from(dir.path()).exists());
+        save_keys_at(dir.path(), DEFAULT_PROFILE, &keys).unwrap();
+        assert!(keys_path_from(dir.path(), DEFAULT_PROFILE).exists());
         assert!(keys_dir_from(dir.path()).is_dir());
     }
 
@@ -344,18 +770,105 @@ mod tests {
         use std::os::unix::fs::PermissionsExt;
         let dir = tempfile::tempdir().unwrap();
         let keys = Keys::generate();
-        save_keys_at(dir.path(), &keys).unwrap();
-        let metadata = fs::metadata(keys_path_from(dir.path())).unwrap();
+        save_keys_at(dir.path(), DEFAULT_PROFILE, &keys).unwrap();
+        let metadata = fs::metadata(keys_path_from(dir.path(), DEFAULT_PROFILE)).unwrap();
         assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
     }
 
+    // -----------------------------------------------------------------------
+    // NIP-49 encrypted storage — low scrypt cost keeps these fast
+    // -----------------------------------------------------------------------
+
+    const TEST_LOG_N: u8 = 4;
+
+    #[test]
+    fn encrypted_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let keys = Keys::generate();
+        let ncryptsec = encrypt_keys(&keys, "hunter2", TEST_LOG_N).unwrap();
+        write_key_file(dir.path(), DEFAULT_PROFILE, &ncryptsec).unwrap();
+
+        let stored = fs::read_to_string(keys_path_from(dir.path(), DEFAULT_PROFILE)).unwrap();
+        assert!(stored.starts_with("ncryptsec1"));
+        let loaded = load_keys_from(dir.path(), DEFAULT_PROFILE, Some("hunter2")).unwrap();
+        assert_eq!(loaded.public_key(), keys.public_key());
+    }
+
+    #[test]
+    fn encrypted_load_without_passphrase_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let ncryptsec = encrypt_keys(&Keys::generate(), "hunter2", TEST_LOG_N).unwrap();
+        write_key_file(dir.path(), DEFAULT_PROFILE, &ncryptsec).unwrap();
+        let err = load_keys_from(dir.path(), DEFAULT_PROFILE, None).unwrap_err();
+        assert_eq!(err.code(), "PASSPHRASE_REQUIRED");
+    }
+
+    #[test]
+    fn encrypted_load_wrong_passphrase_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let ncryptsec = encrypt_keys(&Keys::generate(), "hunter2", TEST_LOG_N).unwrap();
+        write_key_file(dir.path(), DEFAULT_PROFILE, &ncryptsec).unwrap();
+        let err = load_keys_from(dir.path(), DEFAULT_PROFILE, Some("wrong")).unwrap_err();
+        assert_eq!(err.code(), "DECRYPTION_FAILED");
+    }
+
+    #[test]
+    fn encrypted_contents_keeps_imported_ncryptsec_verbatim() {
+        let keys = Keys::generate();
+        let ncryptsec = encrypt_keys(&keys, "pw", TEST_LOG_N).unwrap();
+        let out = encrypted_contents(&keys, Some(&ncryptsec), false, Some("pw"), TEST_LOG_N);
+        assert_eq!(out.unwrap().as_deref(), Some(ncryptsec.as_str()));
+    }
+
+    #[test]
+    fn encrypted_contents_plain_without_encrypt_flag() {
+        let keys = Keys::generate();
+        let out = encrypted_contents(&keys, None, false, Some("pw"), TEST_LOG_N).unwrap();
+        assert!(out.is_none());
+    }
+
+    #[test]
+    fn encrypted_contents_encrypt_requires_passphrase() {
+        let keys = Keys::generate();
+        let err = encrypted_contents(&keys, None, true, None, TEST_LOG_N).unwrap_err();
+        assert_eq!(err.code(), "PASSPHRASE_REQUIRED");
+    }
+
+    #[test]
+    fn read_passphrase_prefers_env_value_over_file() {
+        let out = read_passphrase(Some("direct".into()), Some("/nonexistent".into())).unwrap();
+        assert_eq!(out.as_deref(), Some("direct"));
+    }
+
+    #[test]
+    fn read_passphrase_from_file_strips_trailing_newline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pass");
+        fs::write(&path, "from file\n").unwrap();
+        let out = read_passphrase(None, Some(path.display().to_string())).unwrap();
+        assert_eq!(out.as_deref(), Some("from file"));
+    }
+
+    #[test]
+    fn read_passphrase_none_when_unset() {
+        assert!(read_passphrase(None, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_key_material_accepts_nsec() {
+        let keys = Keys::generate();
+        let nsec = keys.secret_key().to_bech32().unwrap();
+        let parsed = parse_key_material(&nsec, None).unwrap();
+        assert_eq!(parsed.public_key(), keys.public_key());
+    }
+
     // -----------------------------------------------------------------------
     // init — neither flag errors
     // -----------------------------------------------------------------------
 
     #[test]
     fn init_neither_flag_errors() {
-        let err = init(false, None).unwrap_err();
+        let err = init(DEFAULT_PROFILE, false, None, false).unwrap_err();
         assert_eq!(err.code, "MISSING_ARG");
     }
 
@@ -363,7 +876,7 @@ mod tests {
     fn init_generate_does_not_return_missing_arg() {
         // With generate=true the guard must be skipped.
         // It may fail for other reasons (keys already exist, etc.) but NOT MISSING_ARG.
-        match init(true, None) {
+        match init(DEFAULT_PROFILE, true, None, false) {
             Ok(_) => {} // generated keys successfully
             Err(e) => assert_ne!(e.code, "MISSING_ARG"),
         }
@@ -2,14 +2,27 @@
 #[global_allocator]
 static GLOBAL: agcli::Jemalloc = agcli::Jemalloc;
 
+mod broadcast;
+mod bulk;
+mod cache;
 mod delete;
 mod dtag;
 mod error;
+mod export;
 mod header;
+mod import;
 mod item;
 mod keys;
+mod migrate;
+mod mirror;
 mod publish;
 mod query;
+mod reconcile;
+mod relays;
+mod render;
+mod signer;
+mod sync;
+mod validate;
 
 use std::process;
 use std::sync::Arc;
@@ -60,6 +73,51 @@ fn parse_usize_flag(
     }
 }
 
+/// Unix seconds from `value`: a number, an RFC 3339 timestamp, or a `YYYY-MM-DD` date (UTC midnight).
+fn parse_time(value: &str) -> Option<u64> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+    let timestamp = value.parse::<jiff::Timestamp>().ok().or_else(|| {
+        let date = value.parse::<jiff::civil::Date>().ok()?;
+        date.to_zoned(jiff::tz::TimeZone::UTC)
+            .ok()
+            .map(|z| z.timestamp())
+    })?;
+    u64::try_from(timestamp.as_second()).ok()
+}
+
+fn parse_time_flag(req: &CommandRequest<'_>, name: &str) -> Result<Option<u64>, CommandError> {
+    req.flag(name)
+        .map(|v| {
+            parse_time(v).ok_or_else(|| {
+                CommandError::new(
+                    format!("--{name} must be unix seconds, RFC 3339 or YYYY-MM-DD, got: {v}"),
+                    "INVALID_ARGS",
+                    format!("e.g. --{name}=2025-01-31 or --{name}=1738281600"),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// `--offline` / `--cache-first` for the query commands; they are mutually exclusive.
+fn parse_cache_mode(req: &CommandRequest<'_>) -> Result<cache::CacheMode, CommandError> {
+    match (
+        parse_bool_flag(req, "offline")?,
+        parse_bool_flag(req, "cache-first")?,
+    ) {
+        (true, true) => Err(CommandError::new(
+            "--offline and --cache-first are mutually exclusive",
+            "INVALID_ARGS",
+            "Use --offline to never connect, or --cache-first to fall back to the relays",
+        )),
+        (true, false) => Ok(cache::CacheMode::Offline),
+        (false, true) => Ok(cache::CacheMode::CacheFirst),
+        (false, false) => Ok(cache::CacheMode::Refresh),
+    }
+}
+
 fn normalize_import_source(
     import_flag: Option<&str>,
     first_arg: Option<&str>,
@@ -84,12 +142,82 @@ fn resolve_import_source(req: &CommandRequest<'_>) -> Result<Option<String>, Com
     normalize_import_source(req.flag("import"), req.arg(0))
 }
 
-/// Resolve relay URL from --relay flag, `WOKHEI_RELAY` env var, or default.
+/// Collect every `--relay=<url>[,<url>...]` occurrence (agcli keeps only the last one).
+fn relay_flag_values(raw_args: &[String]) -> Vec<String> {
+    relays::split_relays(
+        raw_args
+            .iter()
+            .take_while(|arg| arg.as_str() != "--")
+            .filter_map(|arg| arg.strip_prefix("--relay=")),
+    )
+}
+
+/// Resolve the relay set from repeated/comma-separated --relay flags, `WOKHEI_RELAY`
+/// env var (comma-separated), `~/.wokhei/relays`, or default.
+fn resolve_relays(req: &CommandRequest<'_>) -> Vec<String> {
+    let from_flags = relay_flag_values(req.invocation().raw_args());
+    if !from_flags.is_empty() {
+        return from_flags;
+    }
+
+    let from_env = std::env::var("WOKHEI_RELAY")
+        .map(|v| relays::split_relays([v.as_str()]))
+        .unwrap_or_default();
+    if !from_env.is_empty() {
+        return from_env;
+    }
+
+    let configured = relays::configured_relays();
+    if !configured.is_empty() {
+        return configured;
+    }
+
+    vec![relays::DEFAULT_RELAY.to_string()]
+}
+
+/// Resolve the single relay used by read commands (first of the resolved set).
 fn resolve_relay(req: &CommandRequest<'_>) -> String {
-    req.flag("relay")
+    resolve_relays(req).swap_remove(0)
+}
+
+/// Last `--profile=<name>` before `--`, scanned from raw argv.
+fn profile_flag_value(raw_args: &[String]) -> Option<String> {
+    raw_args
+        .iter()
+        .take_while(|arg| arg.as_str() != "--")
+        .filter_map(|arg| arg.strip_prefix("--profile="))
+        .last()
         .map(String::from)
-        .or_else(|| std::env::var("WOKHEI_RELAY").ok())
-        .unwrap_or_else(|| "ws://localhost:7777".to_string())
+}
+
+/// Resolve the active profile from a --profile value, `WOKHEI_PROFILE` env var, or "default".
+fn resolve_profile_from(flag: Option<&str>) -> Result<String, CommandError> {
+    let profile = flag
+        .map(String::from)
+        .or_else(|| {
+            std::env::var("WOKHEI_PROFILE")
+                .ok()
+                .filter(|v| !v.is_empty())
+        })
+        .unwrap_or_else(|| keys::DEFAULT_PROFILE.to_string());
+    keys::validate_profile(&profile).map_err(CommandError::from)?;
+    Ok(profile)
+}
+
+fn resolve_profile(req: &CommandRequest<'_>) -> Result<String, CommandError> {
+    resolve_profile_from(req.flag("profile"))
+}
+
+/// Resolve the signer for write commands from --signer flag, `WOKHEI_SIGNER` env var,
+/// or the active profile's local key.
+fn resolve_signer(req: &CommandRequest<'_>) -> Result<signer::SignerSpec, CommandError> {
+    let profile = resolve_profile(req)?;
+    let uri = req.flag("signer").map(String::from).or_else(|| {
+        std::env::var("WOKHEI_SIGNER")
+            .ok()
+            .filter(|v| !v.is_empty())
+    });
+    signer::SignerSpec::from_flag(uri.as_deref(), profile).map_err(CommandError::from)
 }
 
 // ---------------------------------------------------------------------------
@@ -101,9 +229,11 @@ fn init_command() -> Command {
         "init",
         "Initialize keypair (generate new or import existing)",
     )
-    .usage("wokhei init --generate | --import=<file-or-stdin>")
+    .usage("wokhei init --generate | --import=<file-or-stdin> [--encrypt] [--profile=<name>]")
     .handler(|req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+        let profile = resolve_profile(req)?;
         let generate = parse_bool_flag(req, "generate")?;
+        let encrypt = parse_bool_flag(req, "encrypt")?;
         let import = resolve_import_source(req)?;
 
         if generate && import.is_some() {
@@ -114,19 +244,35 @@ fn init_command() -> Command {
             ));
         }
 
-        keys::init(generate, import.as_deref())
+        keys::init(&profile, generate, import.as_deref(), encrypt)
     })
 }
 
 fn whoami_command() -> Command {
     Command::new("whoami", "Show current identity (pubkey, npub, keys path)")
-        .usage("wokhei whoami")
-        .handler(|_req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| keys::whoami())
+        .usage("wokhei whoami [--profile=<name>] [--all]")
+        .handler(|req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+            let profile = resolve_profile(req)?;
+            let all = parse_bool_flag(req, "all")?;
+            keys::whoami(&profile, all)
+        })
+}
+
+fn nostrconnect_uri_command() -> Command {
+    Command::new(
+        "nostrconnect-uri",
+        "Show a nostrconnect:// URI to pair a NIP-46 signer app with",
+    )
+    .usage("wokhei nostrconnect-uri [--relay=<url>[,<url>...]] [--profile=<name>]")
+    .handler(|req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+        let profile = resolve_profile(req)?;
+        signer::nostrconnect_uri(&profile, &resolve_relays(req))
+    })
 }
 
 fn create_header_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
     Command::new("create-header", "Create a list header event (kind 9998 or 39998)")
-        .usage("wokhei create-header --name=<singular> --plural=<plural> [--titles=<singular,plural>] [--relay=<url>] [--description=<desc>] [--required=f1,f2] [--recommended=f1,f2] [--tags=t1,t2] [--alt=<text>] [--addressable [--d-tag=<id>]]")
+        .usage("wokhei create-header --name=<singular> --plural=<plural> [--titles=<singular,plural>] [--relay=<url>[,<url>...]] [--profile=<name>] [--signer=bunker://...|nostrconnect://...] [--sign-only | --dry-run] [--description=<desc>] [--required=f1,f2] [--recommended=f1,f2] [--tags=t1,t2] [--alt=<text>] [--addressable [--d-tag=<id>]]")
         .handler(move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
             if req.flag("title").is_some() || req.flag("aliases").is_some() {
                 return Err(CommandError::new(
@@ -155,11 +301,13 @@ fn create_header_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
                 ));
             }
 
-            let relay = resolve_relay(req);
+            let relays = resolve_relays(req);
+            let signer = resolve_signer(req)?;
             let addressable = parse_bool_flag(req, "addressable")?;
 
             let params = header::HeaderParams {
-                relay,
+                relays,
+                signer,
                 name: name.to_string(),
                 plural_name: plural.to_string(),
                 titles,
@@ -170,15 +318,130 @@ fn create_header_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
                 alt: req.flag("alt").map(String::from),
                 addressable,
                 d_tag: req.flag("d-tag").map(String::from),
+                sign_only: parse_bool_flag(req, "sign-only")?,
+                dry_run: parse_bool_flag(req, "dry-run")?,
             };
 
             rt.block_on(header::create_header(params))
         })
 }
 
+fn update_header_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
+    Command::new(
+        "update-header",
+        "Republish an addressable header (kind 39998) with field-level changes",
+    )
+    .usage("wokhei update-header <coordinate|event-id> [--name=<singular>] [--plural=<plural>] [--titles=<singular,plural>] [--description=<desc>] [--alt=<text>] [--add-required=f1,f2] [--remove-required=f1,f2] [--add-recommended=f1,f2] [--remove-recommended=f1,f2] [--add-tags=t1,t2] [--remove-tags=t1,t2] [--relay=<url>[,<url>...]] [--profile=<name>] [--signer=bunker://...|nostrconnect://...] [--sign-only | --dry-run]")
+    .handler(
+        move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+            let target = req.arg(0).ok_or_else(|| {
+                CommandError::new(
+                    "header coordinate or event ID is required",
+                    "MISSING_ARG",
+                    "Provide 39998:<pubkey>:<d-tag> or the header's event ID as a positional argument",
+                )
+            })?;
+            let titles = parse_csv(req.flag("titles"));
+            if !titles.is_empty() && titles.len() != 2 {
+                return Err(CommandError::new(
+                    "--titles requires exactly two comma-separated values",
+                    "INVALID_ARGS",
+                    "Use --titles=<singular,plural>",
+                ));
+            }
+            let changes = header::HeaderChanges {
+                name: req.flag("name").map(String::from),
+                plural_name: req.flag("plural").map(String::from),
+                titles,
+                description: req.flag("description").map(String::from),
+                alt: req.flag("alt").map(String::from),
+                add_required: parse_csv(req.flag("add-required")),
+                remove_required: parse_csv(req.flag("remove-required")),
+                add_recommended: parse_csv(req.flag("add-recommended")),
+                remove_recommended: parse_csv(req.flag("remove-recommended")),
+                add_tags: parse_csv(req.flag("add-tags")),
+                remove_tags: parse_csv(req.flag("remove-tags")),
+            };
+
+            let params = header::UpdateHeaderParams {
+                relays: resolve_relays(req),
+                signer: resolve_signer(req)?,
+                target: target.to_string(),
+                changes,
+                sign_only: parse_bool_flag(req, "sign-only")?,
+                dry_run: parse_bool_flag(req, "dry-run")?,
+            };
+
+            rt.block_on(header::update_header(params))
+        },
+    )
+}
+
+fn update_item_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
+    Command::new(
+        "update-item",
+        "Republish an addressable item (kind 39999) with field or content changes",
+    )
+    .usage("wokhei update-item <coordinate|event-id> [--set=k=v,k2=v2] [--unset=k1,k2] [--content=<text>] [--relay=<url>[,<url>...]] [--profile=<name>] [--signer=bunker://...|nostrconnect://...] [--sign-only | --dry-run]")
+    .handler(
+        move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+            let target = req.arg(0).ok_or_else(|| {
+                CommandError::new(
+                    "item coordinate or event ID is required",
+                    "MISSING_ARG",
+                    "Provide 39999:<pubkey>:<d-tag> or the item's event ID as a positional argument",
+                )
+            })?;
+            let params = item::UpdateItemParams {
+                relays: resolve_relays(req),
+                signer: resolve_signer(req)?,
+                target: target.to_string(),
+                changes: item::ItemChanges {
+                    set: parse_csv(req.flag("set")),
+                    unset: parse_csv(req.flag("unset")),
+                    content: req.flag("content").map(String::from),
+                },
+                sign_only: parse_bool_flag(req, "sign-only")?,
+                dry_run: parse_bool_flag(req, "dry-run")?,
+            };
+
+            rt.block_on(item::update_item(params))
+        },
+    )
+}
+
+/// `add-item --from`: per-item flags come from the file's columns instead.
+fn bulk_item_params(
+    req: &CommandRequest<'_>,
+    from: &str,
+) -> Result<item::BulkItemParams, CommandError> {
+    if let Some(flag) = ["resource", "d-tag"]
+        .into_iter()
+        .find(|f| req.flag(f).is_some())
+    {
+        return Err(CommandError::new(
+            format!("--{flag} cannot be combined with --from"),
+            "INVALID_ARGS",
+            "Put resource and d_tag columns in the --from file instead",
+        ));
+    }
+    Ok(item::BulkItemParams {
+        relays: resolve_relays(req),
+        signer: resolve_signer(req)?,
+        header: req.flag("header").map(String::from),
+        header_coordinate: req.flag("header-coordinate").map(String::from),
+        from: from.to_string(),
+        content: req.flag("content").map(String::from),
+        fields: parse_csv(req.flag("fields")),
+        addressable: parse_bool_flag(req, "addressable")?,
+        sign_only: parse_bool_flag(req, "sign-only")?,
+        dry_run: parse_bool_flag(req, "dry-run")?,
+    })
+}
+
 fn add_item_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
     Command::new("add-item", "Add an item to a list (kind 9999 or 39999)")
-        .usage("wokhei add-item --header=<event-id> | --header-coordinate=<kind:pubkey:d-tag> --resource=<url> [--relay=<url>] [--content=<json>] [--fields=k=v,...] [--addressable [--d-tag=<id>]]")
+        .usage("wokhei add-item --header=<event-id> | --header-coordinate=<kind:pubkey:d-tag> --resource=<url> | --from=<items.csv|items.jsonl> [--relay=<url>[,<url>...]] [--profile=<name>] [--signer=bunker://...|nostrconnect://...] [--sign-only | --dry-run] [--content=<json>] [--fields=k=v,...] [--addressable [--d-tag=<id>]]")
         .handler(move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
             if req.flag("z-tag").is_some() {
                 return Err(CommandError::new(
@@ -188,14 +451,24 @@ fn add_item_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
                 ));
             }
 
+            if let Some(from) = req.flag("from") {
+                return rt.block_on(item::add_items(bulk_item_params(req, from)?));
+            }
+
             let resource = req.flag("resource").ok_or_else(|| {
-                CommandError::new("--resource is required", "MISSING_ARG", "Provide --resource=<url>")
+                CommandError::new(
+                    "--resource is required",
+                    "MISSING_ARG",
+                    "Provide --resource=<url>, or --from=<items.csv|items.jsonl> for many items",
+                )
             })?;
-            let relay = resolve_relay(req);
+            let relays = resolve_relays(req);
+            let signer = resolve_signer(req)?;
             let addressable = parse_bool_flag(req, "addressable")?;
 
             let params = item::ItemParams {
-                relay,
+                relays,
+                signer,
                 header: req.flag("header").map(String::from),
                 header_coordinate: req.flag("header-coordinate").map(String::from),
                 resource: resource.to_string(),
@@ -203,6 +476,8 @@ fn add_item_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
                 fields: parse_csv(req.flag("fields")),
                 addressable,
                 d_tag: req.flag("d-tag").map(String::from),
+                sign_only: parse_bool_flag(req, "sign-only")?,
+                dry_run: parse_bool_flag(req, "dry-run")?,
             };
 
             rt.block_on(item::add_item(params))
@@ -211,24 +486,27 @@ fn add_item_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
 
 fn list_headers_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
     Command::new("list-headers", "List header events from a relay")
-        .usage("wokhei list-headers [--relay=<url>] [--author=<pubkey>] [--tag=<topic>] [--name=<substring>] [--offset=<n>] [--limit=<n>]")
+        .usage("wokhei list-headers [--relay=<url>[,<url>...]] [--author=<pubkey>] [--tag=<topic>] [--name=<substring>] [--offset=<n>] [--limit=<n>] [--offline|--cache-first]")
         .handler(
             move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
-                let relay = resolve_relay(req);
+                let relays = resolve_relays(req);
                 let author = req.flag("author").map(String::from);
                 let tag = req.flag("tag").map(String::from);
                 let name = req.flag("name").map(String::from);
                 let offset = parse_usize_flag(req, "offset", 0)?;
                 let limit = parse_usize_flag(req, "limit", 50)?;
+                let mode = parse_cache_mode(req)?;
 
-                rt.block_on(query::list_headers(relay, author, tag, name, offset, limit))
+                rt.block_on(query::list_headers(
+                    relays, author, tag, name, offset, limit, mode,
+                ))
             },
         )
 }
 
 fn list_items_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
     Command::new("list-items", "List items belonging to a header")
-        .usage("wokhei list-items [<header-id>] [--header-coordinate=<kind:pubkey:d-tag>] [--relay=<url>] [--limit=<n>]")
+        .usage("wokhei list-items [<header-id>] [--header-coordinate=<kind:pubkey:d-tag>] [--relay=<url>[,<url>...]] [--offset=<n>] [--limit=<n>] [--offline|--cache-first]")
         .handler(
             move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
                 let header_id = req.arg(0).map(String::from);
@@ -242,17 +520,26 @@ fn list_items_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
                     ));
                 }
 
-                let relay = resolve_relay(req);
+                let relays = resolve_relays(req);
+                let offset = parse_usize_flag(req, "offset", 0)?;
                 let limit = parse_usize_flag(req, "limit", 100)?;
-
-                rt.block_on(query::list_items(relay, header_id, header_coordinate, limit))
+                let mode = parse_cache_mode(req)?;
+
+                rt.block_on(query::list_items(
+                    relays,
+                    header_id,
+                    header_coordinate,
+                    offset,
+                    limit,
+                    mode,
+                ))
             },
         )
 }
 
 fn inspect_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
     Command::new("inspect", "Inspect a single event in full detail")
-        .usage("wokhei inspect <event-id> [--relay=<url>]")
+        .usage("wokhei inspect <event-id> [--relay=<url>[,<url>...]] [--offline|--cache-first]")
         .handler(
             move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
                 let event_id = req.arg(0).ok_or_else(|| {
@@ -262,16 +549,17 @@ fn inspect_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
                         "Provide an event ID as a positional argument",
                     )
                 })?;
-                let relay = resolve_relay(req);
+                let relays = resolve_relays(req);
+                let mode = parse_cache_mode(req)?;
 
-                rt.block_on(query::inspect(relay, event_id.to_string()))
+                rt.block_on(query::inspect(relays, event_id.to_string(), mode))
             },
         )
 }
 
 fn delete_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
     Command::new("delete", "Delete events (NIP-09 deletion request)")
-        .usage("wokhei delete <event-id>... [--relay=<url>]")
+        .usage("wokhei delete <event-id>... [--relay=<url>[,<url>...]] [--profile=<name>] [--signer=bunker://...|nostrconnect://...] [--sign-only]")
         .handler(
             move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
                 let positionals = req.positionals();
@@ -282,10 +570,12 @@ fn delete_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
                         "Provide one or more event IDs as positional arguments",
                     ));
                 }
-                let relay = resolve_relay(req);
+                let relays = resolve_relays(req);
+                let signer = resolve_signer(req)?;
+                let sign_only = parse_bool_flag(req, "sign-only")?;
                 let event_ids: Vec<String> = positionals.to_vec();
 
-                rt.block_on(delete::delete(relay, event_ids))
+                rt.block_on(delete::delete(relays, &signer, event_ids, sign_only))
             },
         )
 }
@@ -301,23 +591,160 @@ fn count_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
         )
 }
 
+fn export_scope(req: &CommandRequest<'_>) -> Result<export::ExportScope, CommandError> {
+    let scope = export::ExportScope {
+        author: req.flag("author").map(String::from),
+        tag: req.flag("tag").map(String::from),
+        name: req.flag("name").map(String::from),
+        header: req.flag("header").map(String::from),
+        header_coordinate: req.flag("header-coordinate").map(String::from),
+        since: parse_time_flag(req, "since")?,
+        until: parse_time_flag(req, "until")?,
+    };
+    if scope.header.is_some() && scope.header_coordinate.is_some() {
+        return Err(CommandError::new(
+            "--header and --header-coordinate are mutually exclusive",
+            "INVALID_ARGS",
+            "Export one list by event ID or by coordinate, not both",
+        ));
+    }
+    Ok(scope)
+}
+
 fn export_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
-    Command::new("export", "Export all headers and items as JSON backup")
-        .usage("wokhei export [--relay=<url>]")
+    Command::new("export", "Export headers and their items as JSON backup")
+        .usage("wokhei export [--relay=<url>] [--author=<pubkey>] [--tag=<topic>] [--name=<substring>] [--header=<event-id> | --header-coordinate=<kind:pubkey:d-tag>] [--since=<time>] [--until=<time>] [--output=<file> [--format=jsonl|json] [--sync]]")
         .handler(
             move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
                 let relay = resolve_relay(req);
-                rt.block_on(query::export(relay))
+                let scope = export_scope(req)?;
+                let format = req
+                    .flag("format")
+                    .map(export::ExportFormat::parse)
+                    .transpose()?;
+                let output = match (req.flag("output"), format) {
+                    (Some(path), format) => Some(export::ExportFile {
+                        path: path.to_string(),
+                        format: format.unwrap_or(export::ExportFormat::Jsonl),
+                    }),
+                    (None, Some(_)) => {
+                        return Err(CommandError::new(
+                            "--format needs --output",
+                            "INVALID_ARGS",
+                            "Add --output=<file> to stream the export to a file",
+                        ));
+                    }
+                    (None, None) => None,
+                };
+                let sync = parse_bool_flag(req, "sync")?;
+                if sync
+                    && output
+                        .as_ref()
+                        .is_none_or(|o| o.format != export::ExportFormat::Jsonl)
+                {
+                    return Err(CommandError::new(
+                        "--sync appends to a JSON Lines file",
+                        "INVALID_ARGS",
+                        "Use --sync with --output=<file> and --format=jsonl",
+                    ));
+                }
+                rt.block_on(query::export(relay, scope, output, sync))
             },
         )
 }
 
+/// The one list named by a positional header ID or `--header-coordinate`.
+fn single_list_scope(req: &CommandRequest<'_>) -> Result<export::ExportScope, CommandError> {
+    let scope = export::ExportScope {
+        header: req.arg(0).map(String::from),
+        header_coordinate: req.flag("header-coordinate").map(String::from),
+        ..export::ExportScope::default()
+    };
+    match (&scope.header, &scope.header_coordinate) {
+        (None, None) => Err(CommandError::new(
+            "header ID or --header-coordinate is required",
+            "MISSING_ARG",
+            "Provide a header event ID as a positional argument, or use --header-coordinate=<kind:pubkey:d-tag>",
+        )),
+        (Some(_), Some(_)) => Err(CommandError::new(
+            "header ID and --header-coordinate are mutually exclusive",
+            "INVALID_ARGS",
+            "Pick the list by event ID or by coordinate, not both",
+        )),
+        _ => Ok(scope),
+    }
+}
+
+fn export_list_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
+    Command::new(
+        "export-list",
+        "Render one list's items as a CSV, Markdown or HTML table",
+    )
+    .usage("wokhei export-list [<header-id>] [--header-coordinate=<kind:pubkey:d-tag>] [--format=csv|md|html] [--output=<file>] [--relay=<url>[,<url>...]]")
+    .handler(
+        move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+            let scope = single_list_scope(req)?;
+            let format = render::TableFormat::parse(req.flag("format").unwrap_or("csv"))?;
+            let relays = resolve_relays(req);
+            let output = req.flag("output").map(String::from);
+
+            rt.block_on(query::export_list(relays, scope, format, output))
+        },
+    )
+}
+
+fn validate_list_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
+    Command::new(
+        "validate-list",
+        "Check every item of a list against its header's fields",
+    )
+    .usage("wokhei validate-list [<header-id>] [--header-coordinate=<kind:pubkey:d-tag>] [--relay=<url>[,<url>...]] [--offline|--cache-first]")
+    .handler(
+        move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+            let scope = single_list_scope(req)?;
+            let relays = resolve_relays(req);
+            let mode = parse_cache_mode(req)?;
+
+            rt.block_on(query::validate_list(relays, scope, mode))
+        },
+    )
+}
+
+fn migrate_list_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
+    Command::new(
+        "migrate-list",
+        "Copy a regular list (kind 9998/9999) to an addressable one (kind 39998/39999)",
+    )
+    .usage("wokhei migrate-list <header-id> [--d-tag=<id>] [--delete-originals] [--relay=<url>[,<url>...]] [--profile=<name>] [--signer=bunker://...|nostrconnect://...] [--dry-run]")
+    .handler(
+        move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+            let header = req.arg(0).ok_or_else(|| {
+                CommandError::new(
+                    "header event ID is required",
+                    "MISSING_ARG",
+                    "Provide the event ID of the kind 9998 header as a positional argument",
+                )
+            })?;
+            let params = migrate::MigrateParams {
+                relays: resolve_relays(req),
+                signer: resolve_signer(req)?,
+                header: header.to_string(),
+                d_tag: req.flag("d-tag").map(String::from),
+                delete_originals: parse_bool_flag(req, "delete-originals")?,
+                dry_run: parse_bool_flag(req, "dry-run")?,
+            };
+
+            rt.block_on(migrate::migrate_list(params))
+        },
+    )
+}
+
 fn publish_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
     Command::new(
         "publish",
-        "Sign and publish raw event JSON from file or stdin",
+        "Sign and publish raw event JSON (one object, a JSON array, or JSON Lines) from file or stdin",
     )
-    .usage("wokhei publish <json-file-or-stdin> [--relay=<url>]")
+    .usage("wokhei publish <json-file-or-stdin> [--relay=<url>[,<url>...]] [--profile=<name>] [--signer=bunker://...|nostrconnect://...] [--sign-only] [--no-validate]")
     .handler(
         move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
             let input = req.arg(0).ok_or_else(|| {
@@ -327,9 +754,99 @@ fn publish_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
                     "Provide a JSON file path, or use - for stdin",
                 )
             })?;
-            let relay = resolve_relay(req);
+            let relays = resolve_relays(req);
+            let signer = resolve_signer(req)?;
+            let sign_only = parse_bool_flag(req, "sign-only")?;
+            let validate = !parse_bool_flag(req, "no-validate")?;
+
+            rt.block_on(publish::publish(
+                relays,
+                &signer,
+                input.to_string(),
+                sign_only,
+                validate,
+            ))
+        },
+    )
+}
+
+fn broadcast_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
+    Command::new(
+        "broadcast",
+        "Send an already-signed event (e.g. from --sign-only) unchanged",
+    )
+    .usage("wokhei broadcast <signed-json-file-or-stdin> [--relay=<url>[,<url>...]]")
+    .handler(
+        move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+            let input = req.arg(0).ok_or_else(|| {
+                CommandError::new(
+                    "signed event JSON source is required",
+                    "MISSING_ARG",
+                    "Provide a JSON file path, or use - for stdin",
+                )
+            })?;
+            let relays = resolve_relays(req);
+
+            rt.block_on(broadcast::broadcast(relays, input.to_string()))
+        },
+    )
+}
+
+fn import_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
+    Command::new(
+        "import",
+        "Republish an export backup's signed events unchanged, skipping ones already stored",
+    )
+    .usage("wokhei import <backup-json-file-or-stdin> [--relay=<url>[,<url>...]]")
+    .handler(
+        move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+            let input = req.arg(0).ok_or_else(|| {
+                CommandError::new(
+                    "backup JSON source is required",
+                    "MISSING_ARG",
+                    "Provide the file written from `wokhei export`, or use - for stdin",
+                )
+            })?;
+            let relays = resolve_relays(req);
+
+            rt.block_on(import::import(relays, input.to_string()))
+        },
+    )
+}
+
+fn mirror_command(rt: Arc<tokio::runtime::Runtime>) -> Command {
+    Command::new(
+        "mirror",
+        "Copy DCoSL lists and their deletions from one relay to others",
+    )
+    .usage("wokhei mirror --from=<url> --to=<url>[,<url>...] [--author=<pubkey>] [--tag=<topic>] [--name=<substring>] [--header=<event-id> | --header-coordinate=<kind:pubkey:d-tag>] [--since=<time>] [--until=<time>]")
+    .handler(
+        move |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+            let from = req.flag("from").ok_or_else(|| {
+                CommandError::new(
+                    "--from is required",
+                    "MISSING_ARG",
+                    "Name the relay to copy from, e.g. --from=wss://dcosl.brainstorm.world",
+                )
+            })?;
+            let to = relays::split_relays(req.flag("to"));
+            if to.is_empty() {
+                return Err(CommandError::new(
+                    "--to is required",
+                    "MISSING_ARG",
+                    "Name the relay(s) to copy into, e.g. --to=ws://localhost:7777",
+                ));
+            }
+            if to.iter().any(|t| t == from) {
+                return Err(CommandError::new(
+                    format!("--from and --to both name {from}"),
+                    "INVALID_ARGS",
+                    "Mirror into a different relay than the source",
+                ));
+            }
+            let scope = export_scope(req)?;
 
-            rt.block_on(publish::publish(relay, input.to_string()))
+            rt.block_on(mirror::mirror(from.to_string(), to, scope))
         },
     )
 }
@@ -361,6 +878,11 @@ fn main() {
         println!("{json}");
     }));
 
+    // Root envelope reports the profile selected for this invocation
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let active_profile = resolve_profile_from(profile_flag_value(&args).as_deref());
+    let keys_configured = active_profile.as_deref().is_ok_and(keys::keys_exist);
+
     // Build tokio runtime
     let rt = Arc::new(tokio::runtime::Runtime::new().expect("Failed to create tokio runtime"));
 
@@ -370,18 +892,28 @@ fn main() {
     )
     .version(env!("CARGO_PKG_VERSION"))
     .schema_version("wokhei.v1")
-    .root_field("keys_configured", json!(keys::keys_exist()))
+    .root_field("keys_configured", json!(keys_configured))
+    .root_field("profile", json!(active_profile.ok()))
     .command(init_command())
     .command(whoami_command())
+    .command(nostrconnect_uri_command())
     .command(create_header_command(rt.clone()))
+    .command(update_header_command(rt.clone()))
     .command(add_item_command(rt.clone()))
+    .command(update_item_command(rt.clone()))
     .command(list_headers_command(rt.clone()))
     .command(list_items_command(rt.clone()))
     .command(inspect_command(rt.clone()))
     .command(delete_command(rt.clone()))
     .command(count_command(rt.clone()))
     .command(export_command(rt.clone()))
-    .command(publish_command(rt));
+    .command(export_list_command(rt.clone()))
+    .command(validate_list_command(rt.clone()))
+    .command(migrate_list_command(rt.clone()))
+    .command(publish_command(rt.clone()))
+    .command(broadcast_command(rt.clone()))
+    .command(import_command(rt.clone()))
+    .command(mirror_command(rt));
 
     let execution = cli.run_env();
 
@@ -416,6 +948,23 @@ mod tests {
         assert!(normalize_import_source(Some("true"), None).is_err());
     }
 
+    // -----------------------------------------------------------------------
+    // parse_time
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn parse_time_accepts_seconds_rfc3339_and_dates() {
+        assert_eq!(parse_time("1738281600"), Some(1_738_281_600));
+        assert_eq!(parse_time("2025-01-31T00:00:00Z"), Some(1_738_281_600));
+        assert_eq!(parse_time("2025-01-31"), Some(1_738_281_600));
+    }
+
+    #[test]
+    fn parse_time_rejects_garbage_and_pre_epoch() {
+        assert_eq!(parse_time("yesterday"), None);
+        assert_eq!(parse_time("1969-12-31"), None);
+    }
+
     // -----------------------------------------------------------------------
     // parse_csv — direct unit tests
     // -----------------------------------------------------------------------
@@ -458,6 +1007,23 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn cache_mode_flags_are_mutually_exclusive() {
+        let cli = AgentCli::new("test", "t").command(Command::new("c", "c").handler(
+            |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
+                let mode = parse_cache_mode(req)?;
+                Ok(CommandOutput::new(
+                    json!({ "offline": mode == cache::CacheMode::Offline }),
+                ))
+            },
+        ));
+        let exec = cli.run_argv(["test", "c", "--offline"]);
+        let j: serde_json::Value = serde_json::from_str(&exec.to_json()).unwrap();
+        assert_eq!(j["result"]["offline"], true);
+        let exec = cli.run_argv(["test", "c", "--offline", "--cache-first"]);
+        assert!(!exec.envelope().ok());
+    }
+
     #[test]
     fn bool_flag_absent_is_false() {
         let exec = bool_flag_cli().run_argv(["test", "c"]);
@@ -536,14 +1102,39 @@ mod tests {
     }
 
     // -----------------------------------------------------------------------
-    // resolve_relay — tested via AgentCli::run_argv
+    // relay_flag_values — pure function
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn relay_flag_values_collects_repeated_and_comma_separated() {
+        let args: Vec<String> = ["c", "--relay=ws://a:1,ws://b:2", "--relay=ws://c:3"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            relay_flag_values(&args),
+            vec!["ws://a:1", "ws://b:2", "ws://c:3"]
+        );
+    }
+
+    #[test]
+    fn relay_flag_values_stops_at_double_dash() {
+        let args: Vec<String> = ["c", "--relay=ws://a:1", "--", "--relay=ws://b:2"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(relay_flag_values(&args), vec!["ws://a:1"]);
+    }
+
+    // -----------------------------------------------------------------------
+    // resolve_relays — tested via AgentCli::run_argv
     // These tests mutate WOKHEI_RELAY env var — run serially via nextest config.
     // -----------------------------------------------------------------------
 
     fn relay_cli() -> AgentCli {
         AgentCli::new("test", "t").command(Command::new("c", "c").handler(
             |req: &CommandRequest<'_>, _ctx: &mut ExecutionContext| {
-                let v = resolve_relay(req);
+                let v = resolve_relays(req).join(",");
                 Ok(CommandOutput::new(json!({ "v": v })))
             },
         ))
@@ -559,8 +1150,18 @@ mod tests {
 
     #[test]
     fn resolve_relay_default_fallback() {
-        unsafe { std::env::remove_var("WOKHEI_RELAY") };
+        // A ~/.wokhei/relays file in the real home would win over the built-in default.
+        let home = tempfile::tempdir().unwrap();
+        let real_home = std::env::var_os("HOME");
+        unsafe {
+            std::env::remove_var("WOKHEI_RELAY");
+            std::env::set_var("HOME", home.path());
+        }
         let exec = relay_cli().run_argv(["test", "c"]);
+        match real_home {
+            Some(real_home) => unsafe { std::env::set_var("HOME", real_home) },
+            None => unsafe { std::env::remove_var("HOME") },
+        }
         assert!(exec.envelope().ok());
         assert_eq!(relay_result(&exec), "ws://localhost:7777");
     }
@@ -588,4 +1189,48 @@ mod tests {
         assert_eq!(relay_result(&exec), "ws://flagrelay:9999");
         unsafe { std::env::remove_var("WOKHEI_RELAY") };
     }
+
+    #[test]
+    fn resolve_relays_repeated_flags() {
+        let exec = relay_cli().run_argv(["test", "c", "--relay=ws://a:1", "--relay=ws://b:2"]);
+        assert_eq!(relay_result(&exec), "ws://a:1,ws://b:2");
+    }
+
+    #[test]
+    fn resolve_relays_env_var_comma_separated() {
+        unsafe { std::env::set_var("WOKHEI_RELAY", "ws://a:1, ws://b:2") };
+        let exec = relay_cli().run_argv(["test", "c"]);
+        assert_eq!(relay_result(&exec), "ws://a:1,ws://b:2");
+        unsafe { std::env::remove_var("WOKHEI_RELAY") };
+    }
+
+    // -----------------------------------------------------------------------
+    // profile_flag_value / resolve_profile_from
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn profile_flag_value_takes_last_before_double_dash() {
+        let args: Vec<String> = ["c", "--profile=a", "--profile=b", "--", "--profile=c"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(profile_flag_value(&args).as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn resolve_profile_rejects_invalid_name() {
+        let err = resolve_profile_from(Some("../escape")).unwrap_err();
+        assert_eq!(err.code, "INVALID_PROFILE");
+    }
+
+    // Mutates WOKHEI_PROFILE — runs serially with the relay env tests via nextest config.
+    #[test]
+    fn resolve_profile_flag_env_default_precedence() {
+        unsafe { std::env::remove_var("WOKHEI_PROFILE") };
+        assert_eq!(resolve_profile_from(None).unwrap(), "default");
+        unsafe { std::env::set_var("WOKHEI_PROFILE", "envbot") };
+        assert_eq!(resolve_profile_from(None).unwrap(), "envbot");
+        assert_eq!(resolve_profile_from(Some("flagbot")).unwrap(), "flagbot");
+        unsafe { std::env::remove_var("WOKHEI_PROFILE") };
+    }
 }
@@ -0,0 +1,625 @@
+use std::collections::{HashMap, HashSet};
+
+use agcli::{CommandError, CommandOutput, NextAction};
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::dtag::{header_dtag, item_dtag};
+use crate::error::AppError;
+use crate::export::ExportScope;
+use crate::header::{TagParts, set_tag};
+use crate::publish::{batch_result, failed_entry};
+use crate::query::{QUERY_TIMEOUT, fetch_items_for_header_event, fetch_latest_addressable};
+use crate::relays::{WriteSession, relays_arg, reports_json};
+use crate::signer::{Signer, SignerSpec};
+
+/// Most event ids one NIP-09 deletion request names.
+const DELETION_CHUNK: usize = 500;
+
+pub struct MigrateParams {
+    pub relays: Vec<String>,
+    pub signer: SignerSpec,
+    /// Event ID of the kind 9998 header.
+    pub header: String,
+    pub d_tag: Option<String>,
+    pub delete_originals: bool,
+    pub dry_run: bool,
+}
+
+/// An item of the old list and its kind 39999 replacement.
+struct ItemPlan {
+    original: Event,
+    d_tag: String,
+    tags: Vec<Tag>,
+    /// Replacement already published by an earlier run.
+    existing: Option<EventId>,
+}
+
+/// The kind 39998 header replacing `original`, and the items moving under it.
+struct MigrationPlan {
+    original: Event,
+    d_tag: String,
+    coordinate: String,
+    tags: Vec<Tag>,
+    existing: Option<EventId>,
+    items: Vec<ItemPlan>,
+}
+
+fn tag_parts(tags: &[Tag]) -> TagParts {
+    tags.iter()
+        .map(|t| t.as_slice().to_vec())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn parse_parts(parts: TagParts) -> Vec<Tag> {
+    parts
+        .into_iter()
+        .filter_map(|t| Tag::parse(t).ok())
+        .collect()
+}
+
+fn first_value<'a>(event: &'a Event, name: &str) -> Option<&'a str> {
+    event
+        .tags
+        .iter()
+        .map(Tag::as_slice)
+        .find(|t| t.first().map(String::as_str) == Some(name))
+        .and_then(|t| t.get(1))
+        .map(String::as_str)
+}
+
+/// The old header's tags with `d` added in front.
+fn migrated_header_tags(header: &Event, d_tag: &str) -> Vec<Tag> {
+    let mut parts = tag_parts(header.tags.as_slice());
+    parts.retain(|t| t[0] != "d");
+    parts.insert(0, vec!["d".to_string(), d_tag.to_string()]);
+    parse_parts(parts)
+}
+
+/// Replacements for `items` (oldest first) moving from `old_ref` to `coordinate`; `a` tags
+/// naming `old_ref` move too. The `d` tag comes from the resource; an item without one, or
+/// repeating an earlier item's, is keyed by its event id.
+fn plan_items(old_ref: &str, coordinate: &str, items: Vec<Event>) -> Vec<ItemPlan> {
+    let mut used = HashSet::new();
+    items
+        .into_iter()
+        .map(|original| {
+            let id_dtag = || item_dtag(coordinate, &original.id.to_hex());
+            let mut d_tag = first_value(&original, "r")
+                .map_or_else(id_dtag, |resource| item_dtag(coordinate, resource));
+            if !used.insert(d_tag.clone()) {
+                d_tag = id_dtag();
+                used.insert(d_tag.clone());
+            }
+            let mut parts = tag_parts(original.tags.as_slice());
+            for tag in parts.iter_mut().filter(|t| t[0] == "a" && t.len() > 1) {
+                if tag[1] == old_ref {
+                    tag[1] = coordinate.to_string();
+                }
+            }
+            set_tag(&mut parts, "z", Some(vec![coordinate.to_string()]));
+            set_tag(&mut parts, "d", Some(vec![d_tag.clone()]));
+            ItemPlan {
+                tags: parse_parts(parts),
+                original,
+                d_tag,
+                existing: None,
+            }
+        })
+        .collect()
+}
+
+async fn fetch_old_header(
+    client: &Client,
+    relay: &str,
+    id_str: &str,
+) -> Result<Event, CommandError> {
+    let id = EventId::parse(id_str).map_err(|_| {
+        CommandError::from(AppError::InvalidEventId {
+            id: id_str.to_string(),
+        })
+    })?;
+    let header = client
+        .fetch_events(Filter::new().id(id).limit(1), QUERY_TIMEOUT)
+        .await
+        .map_err(|_| {
+            CommandError::from(AppError::RelayUnreachable {
+                url: relay.to_string(),
+            })
+        })?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            CommandError::from(AppError::HeaderNotFound {
+                event_id: id_str.to_string(),
+            })
+        })?;
+    match header.kind {
+        Kind::Custom(9998) => Ok(header),
+        Kind::Custom(39998) => Err(CommandError::new(
+            format!("{id_str} is already an addressable list header"),
+            "INVALID_ARGS",
+            "Edit it in place with `wokhei update-header` instead",
+        )),
+        _ => Err(CommandError::new(
+            format!("{id_str} is not a list header"),
+            "INVALID_ARGS",
+            "Pass the event ID of a kind 9998 list header",
+        )),
+    }
+}
+
+/// Work out the new header and items. A header at the target coordinate is reused when it
+/// is this migration's (same tags and content), and so are the items already under it.
+async fn plan_migration(
+    client: &Client,
+    relay: &str,
+    params: &MigrateParams,
+) -> Result<MigrationPlan, CommandError> {
+    let original = fetch_old_header(client, relay, &params.header).await?;
+    let pubkey_hex = original.pubkey.to_hex();
+    let d_tag = params.d_tag.clone().unwrap_or_else(|| {
+        header_dtag(
+            first_value(&original, "names").unwrap_or_default(),
+            &pubkey_hex,
+        )
+    });
+    let coordinate = format!("39998:{pubkey_hex}:{d_tag}");
+    let tags = migrated_header_tags(&original, &d_tag);
+
+    let current =
+        fetch_latest_addressable(client, relay, Kind::Custom(39998), original.pubkey, &d_tag)
+            .await?;
+    let mut done = HashMap::new();
+    if let Some(current) = &current {
+        if current.tags.as_slice() != tags.as_slice() || current.content != original.content {
+            return Err(CommandError::from(AppError::CoordinateTaken { coordinate }));
+        }
+        for item in fetch_items_for_header_event(client, relay, current, &ExportScope::default())
+            .await?
+            .into_iter()
+            .filter(|item| item.pubkey == original.pubkey)
+        {
+            if let Some(d) = first_value(&item, "d") {
+                done.entry(d.to_string()).or_insert(item.id);
+            }
+        }
+    }
+
+    let mut items =
+        fetch_items_for_header_event(client, relay, &original, &ExportScope::default()).await?;
+    items.reverse();
+    let mut items = plan_items(&original.id.to_hex(), &coordinate, items);
+    for item in &mut items {
+        item.existing = done.get(&item.d_tag).copied();
+    }
+    Ok(MigrationPlan {
+        existing: current.map(|e| e.id),
+        original,
+        d_tag,
+        coordinate,
+        tags,
+        items,
+    })
+}
+
+fn dry_run_json(plan: &MigrationPlan) -> serde_json::Value {
+    let items: Vec<serde_json::Value> = plan
+        .items
+        .iter()
+        .map(|item| {
+            json!({
+                "old_id": item.original.id.to_hex(),
+                "d_tag": item.d_tag,
+                "resource": first_value(&item.original, "r"),
+                "existing": item.existing.is_some(),
+            })
+        })
+        .collect();
+    json!({
+        "dry_run": true,
+        "header": {
+            "old_id": plan.original.id.to_hex(),
+            "kind": 39998,
+            "d_tag": plan.d_tag,
+            "coordinate": plan.coordinate,
+            "existing": plan.existing.is_some(),
+        },
+        "count": items.len(),
+        "items": items,
+    })
+}
+
+/// Sign and send one item's replacement, or reuse the one an earlier run published.
+async fn publish_item(
+    signer: &Signer,
+    session: &WriteSession,
+    index: usize,
+    item: &ItemPlan,
+) -> serde_json::Value {
+    let mut entry = json!({
+        "index": index, "ok": true, "old_id": item.original.id.to_hex(),
+        "d_tag": item.d_tag, "resource": first_value(&item.original, "r"),
+    });
+    if let Some(id) = item.existing {
+        entry["event_id"] = json!(id.to_hex());
+        entry["existing"] = json!(true);
+        return entry;
+    }
+    let builder = EventBuilder::new(Kind::Custom(39999), item.original.content.clone())
+        .tags(item.tags.clone());
+    let sent = match signer.sign(builder).await {
+        Ok(event) => session.send(&event).await.map(|reports| (event, reports)),
+        Err(e) => Err(CommandError::from(e)),
+    };
+    match sent {
+        Ok((event, reports)) => {
+            entry["event_id"] = json!(event.id.to_hex());
+            entry["relays"] = json!(reports_json(&reports));
+        }
+        Err(e) => {
+            let (old_id, resource) = (entry["old_id"].take(), entry["resource"].take());
+            entry = failed_entry(index, &e);
+            entry["old_id"] = old_id;
+            entry["resource"] = resource;
+        }
+    }
+    entry
+}
+
+/// File NIP-09 deletions for `ids`, a chunk per request. A failed request is reported, not
+/// raised: the new list is already published.
+async fn delete_ids(signer: &Signer, session: &WriteSession, ids: &[EventId]) -> serde_json::Value {
+    let mut deletion_ids = Vec::new();
+    let mut errors = Vec::new();
+    for chunk in ids.chunks(DELETION_CHUNK) {
+        let builder = EventBuilder::delete(EventDeletionRequest::new().ids(chunk.iter().copied()));
+        let sent = match signer.sign(builder).await {
+            Ok(event) => session.send(&event).await.map(|_| event.id),
+            Err(e) => Err(CommandError::from(e)),
+        };
+        match sent {
+            Ok(id) => deletion_ids.push(id.to_hex()),
+            Err(e) => errors.push(json!({ "code": e.code, "message": e.message })),
+        }
+    }
+    let mut result = json!({
+        "deletion_event_ids": deletion_ids,
+        "deleted_ids": ids.iter().map(EventId::to_hex).collect::<Vec<_>>(),
+        "note": "NIP-09: deletion is a REQUEST — relays may or may not honor it",
+    });
+    if !errors.is_empty() {
+        result["errors"] = json!(errors);
+    }
+    result
+}
+
+/// Originals that can be deleted: items whose replacement is published and that the signer
+/// wrote, plus the header once every item has moved. Returns `(delete, keep)`.
+fn deletable(plan: &MigrationPlan, results: &[serde_json::Value]) -> (Vec<EventId>, Vec<EventId>) {
+    let author = plan.original.pubkey;
+    let (mut delete, mut keep) = (Vec::new(), Vec::new());
+    for (item, entry) in plan.items.iter().zip(results) {
+        if entry["ok"] == true && item.original.pubkey == author {
+            delete.push(item.original.id);
+        } else {
+            keep.push(item.original.id);
+        }
+    }
+    if results.iter().all(|r| r["ok"] == true) {
+        delete.push(plan.original.id);
+    } else {
+        keep.push(plan.original.id);
+    }
+    (delete, keep)
+}
+
+fn migrate_command(relay: &str, params: &MigrateParams) -> String {
+    let d_flag = params
+        .d_tag
+        .as_ref()
+        .map(|d| format!(" --d-tag={d}"))
+        .unwrap_or_default();
+    format!(
+        "wokhei migrate-list --relay={relay} {}{d_flag}",
+        params.header
+    )
+}
+
+fn migrate_actions(
+    relay: &str,
+    params: &MigrateParams,
+    coordinate: &str,
+    failed: bool,
+) -> Vec<NextAction> {
+    let rerun = migrate_command(relay, params);
+    let mut actions = Vec::new();
+    if failed {
+        actions.push(NextAction::new(
+            rerun,
+            "Re-run to retry the failed items; migrated ones are reused",
+        ));
+    } else if !params.delete_originals {
+        actions.push(NextAction::new(
+            format!("{rerun} --delete-originals"),
+            "Once the new list checks out, request deletion of the originals",
+        ));
+    }
+    actions.push(NextAction::new(
+        format!("wokhei list-items --relay={relay} --header-coordinate=\"{coordinate}\""),
+        "List the migrated items",
+    ));
+    actions.push(NextAction::new(
+        format!("wokhei validate-list --relay={relay} --header-coordinate=\"{coordinate}\""),
+        "Check the migrated items against the header",
+    ));
+    actions
+}
+
+/// Publish the new header (unless reused), then every item, then the deletions.
+async fn run_migration(
+    signer: &Signer,
+    session: &WriteSession,
+    params: &MigrateParams,
+    plan: &MigrationPlan,
+) -> Result<serde_json::Value, CommandError> {
+    if signer.public_key().await.map_err(CommandError::from)? != plan.original.pubkey {
+        return Err(CommandError::from(AppError::NotAuthor {
+            coordinate: plan.original.id.to_hex(),
+        }));
+    }
+    let mut header = json!({
+        "old_id": plan.original.id.to_hex(),
+        "kind": 39998,
+        "d_tag": plan.d_tag,
+        "coordinate": plan.coordinate,
+    });
+    if let Some(id) = plan.existing {
+        header["event_id"] = json!(id.to_hex());
+        header["existing"] = json!(true);
+    } else {
+        let builder = EventBuilder::new(Kind::Custom(39998), plan.original.content.clone())
+            .tags(plan.tags.clone());
+        let event = signer.sign(builder).await.map_err(CommandError::from)?;
+        let reports = session.send(&event).await?;
+        header["event_id"] = json!(event.id.to_hex());
+        header["relays"] = json!(reports_json(&reports));
+    }
+
+    let mut results = Vec::with_capacity(plan.items.len());
+    for (index, item) in plan.items.iter().enumerate() {
+        results.push(publish_item(signer, session, index, item).await);
+    }
+    let mapping: serde_json::Map<String, serde_json::Value> = std::iter::once(&header)
+        .chain(results.iter().filter(|r| r["ok"] == true))
+        .map(|r| {
+            (
+                r["old_id"].as_str().unwrap_or_default().to_string(),
+                r["event_id"].clone(),
+            )
+        })
+        .collect();
+    let deletions = if params.delete_originals {
+        let (delete, keep) = deletable(plan, &results);
+        let mut deletions = delete_ids(signer, session, &delete).await;
+        deletions["kept_ids"] = json!(keep.iter().map(EventId::to_hex).collect::<Vec<_>>());
+        Some(deletions)
+    } else {
+        None
+    };
+
+    let mut result = batch_result(results);
+    result["header"] = header;
+    result["mapping"] = serde_json::Value::Object(mapping);
+    if let Some(deletions) = deletions {
+        result["deletions"] = deletions;
+    }
+    Ok(result)
+}
+
+/// Move a regular list (kind 9998 header, items referencing it by event id) to an
+/// addressable one: a kind 39998 header and a kind 39999 copy of every item. The originals
+/// stay unless `--delete-originals`; the output maps each old event id to its new one.
+pub async fn migrate_list(params: MigrateParams) -> Result<CommandOutput, CommandError> {
+    let signer = if params.dry_run {
+        None
+    } else {
+        Some(Signer::load(&params.signer)?)
+    };
+    let relay = relays_arg(&params.relays);
+    let session = WriteSession::connect(&params.relays).await?;
+
+    let result = async {
+        let plan = plan_migration(session.client(), &relay, &params).await?;
+        let Some(signer) = &signer else {
+            let actions = vec![NextAction::new(
+                migrate_command(&relay, &params),
+                "Run the migration",
+            )];
+            return Ok(CommandOutput::new(dry_run_json(&plan)).next_actions(actions));
+        };
+        let result = run_migration(signer, &session, &params, &plan).await?;
+        let failed = result["failed"].as_u64().unwrap_or(0) > 0;
+        let actions = migrate_actions(&relay, &params, &plan.coordinate, failed);
+        Ok(CommandOutput::new(result).next_actions(actions))
+    }
+    .await;
+
+    session.disconnect().await;
+    if let Some(signer) = signer {
+        signer.shutdown().await;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(keys: &Keys) -> Event {
+        EventBuilder::new(Kind::Custom(9998), "")
+            .tags([
+                Tag::custom(TagKind::custom("names"), ["Jazz Album", "jazz albums"]),
+                Tag::custom(TagKind::custom("required"), ["title"]),
+                Tag::hashtag("jazz"),
+            ])
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    fn item(keys: &Keys, header_id: &str, resource: Option<&str>, secs: u64) -> Event {
+        let mut tags = vec![
+            Tag::custom(TagKind::custom("z"), [header_id]),
+            Tag::custom(TagKind::custom("title"), ["Kind of Blue"]),
+        ];
+        if let Some(resource) = resource {
+            tags.push(Tag::custom(TagKind::custom("r"), [resource]));
+        }
+        EventBuilder::new(Kind::Custom(9999), "notes")
+            .tags(tags)
+            .custom_created_at(Timestamp::from_secs(secs))
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    fn params(relays: Vec<String>, header: &Event) -> MigrateParams {
+        MigrateParams {
+            relays,
+            signer: SignerSpec::Local {
+                profile: "default".into(),
+            },
+            header: header.id.to_hex(),
+            d_tag: None,
+            delete_originals: false,
+            dry_run: false,
+        }
+    }
+
+    #[test]
+    fn migrated_header_tags_put_d_first_and_keep_the_rest() {
+        let old = header(&Keys::generate());
+        let tags = migrated_header_tags(&old, "jazz-album--1234abcd");
+        assert_eq!(tags[0].as_slice(), ["d", "jazz-album--1234abcd"]);
+        assert_eq!(&tags[1..], old.tags.as_slice());
+    }
+
+    #[test]
+    fn plan_items_repoint_z_and_key_repeats_by_event_id() {
+        let keys = Keys::generate();
+        let coord = format!("39998:{}:jazz", keys.public_key().to_hex());
+        let first = item(&keys, "old", Some("https://a"), 1);
+        let repeat = item(&keys, "old", Some("https://a"), 2);
+        let bare = item(&keys, "old", None, 3);
+        let a_only = EventBuilder::new(Kind::Custom(9999), "")
+            .tags([
+                Tag::custom(TagKind::custom("a"), ["old"]),
+                Tag::custom(TagKind::custom("a"), ["other"]),
+            ])
+            .sign_with_keys(&keys)
+            .unwrap();
+        let plans = plan_items(
+            "old",
+            &coord,
+            vec![first, repeat.clone(), bare.clone(), a_only],
+        );
+
+        assert_eq!(plans[0].d_tag, item_dtag(&coord, "https://a"));
+        assert_eq!(plans[1].d_tag, item_dtag(&coord, &repeat.id.to_hex()));
+        assert_eq!(plans[2].d_tag, item_dtag(&coord, &bare.id.to_hex()));
+        let tags = &plans[0].tags;
+        assert_eq!(tags[0].as_slice(), ["z", coord.as_str()]);
+        assert_eq!(tags[1].as_slice(), ["title", "Kind of Blue"]);
+        assert_eq!(tags[3].as_slice(), ["d", plans[0].d_tag.as_str()]);
+        let moved: Vec<&[String]> = plans[3].tags.iter().map(Tag::as_slice).collect();
+        assert_eq!(moved[0], ["a", coord.as_str()]);
+        assert_eq!(moved[1], ["a", "other"]);
+        assert_eq!(moved[2], ["z", coord.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn migration_maps_every_event_and_reruns_reuse_them() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let relays = vec![relay.url().await.to_string()];
+        let keys = Keys::generate();
+        let old = header(&keys);
+        let items = [
+            item(&keys, &old.id.to_hex(), Some("https://a"), 1),
+            item(&keys, &old.id.to_hex(), Some("https://b"), 2),
+        ];
+        let session = WriteSession::connect(&relays).await.unwrap();
+        for event in std::iter::once(&old).chain(&items) {
+            session.send(event).await.unwrap();
+        }
+        let signer = Signer::Local(keys.clone());
+        let mut params = params(relays.clone(), &old);
+
+        let plan = plan_migration(session.client(), &relays[0], &params)
+            .await
+            .unwrap();
+        let result = run_migration(&signer, &session, &params, &plan)
+            .await
+            .unwrap();
+        assert_eq!(result["succeeded"], 2);
+        assert_eq!(result["mapping"].as_object().unwrap().len(), 3);
+        assert_eq!(result["results"][0]["old_id"], items[0].id.to_hex());
+        let coordinate = result["header"]["coordinate"].as_str().unwrap();
+        assert!(coordinate.ends_with(&header_dtag("Jazz Album", &keys.public_key().to_hex())));
+
+        params.delete_originals = true;
+        let plan = plan_migration(session.client(), &relays[0], &params)
+            .await
+            .unwrap();
+        assert!(plan.existing.is_some());
+        assert!(plan.items.iter().all(|i| i.existing.is_some()));
+        let rerun = run_migration(&signer, &session, &params, &plan)
+            .await
+            .unwrap();
+        assert_eq!(rerun["mapping"], result["mapping"]);
+        assert_eq!(
+            rerun["deletions"]["deleted_ids"].as_array().unwrap().len(),
+            3
+        );
+
+        let other = Signer::Local(Keys::generate());
+        let err = run_migration(&other, &session, &params, &plan)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, "NOT_AUTHOR");
+        session.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn migration_refuses_a_coordinate_holding_another_list() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let relays = vec![relay.url().await.to_string()];
+        let keys = Keys::generate();
+        let old = header(&keys);
+        let taken = EventBuilder::new(Kind::Custom(39998), "")
+            .tags([
+                Tag::identifier("jazz"),
+                Tag::custom(TagKind::custom("names"), ["record", "records"]),
+            ])
+            .sign_with_keys(&keys)
+            .unwrap();
+        let session = WriteSession::connect(&relays).await.unwrap();
+        session.send(&old).await.unwrap();
+        session.send(&taken).await.unwrap();
+
+        let mut params = params(relays.clone(), &old);
+        params.d_tag = Some("jazz".into());
+        let err = plan_migration(session.client(), &relays[0], &params)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code, "COORDINATE_TAKEN");
+
+        params.header = taken.id.to_hex();
+        let err = plan_migration(session.client(), &relays[0], &params)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code, "INVALID_ARGS");
+        session.disconnect().await;
+    }
+}
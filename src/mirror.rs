@@ -0,0 +1,356 @@
+use std::collections::HashSet;
+
+use agcli::{CommandError, CommandOutput, NextAction};
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::export::ExportScope;
+use crate::import::{LOOKUP_CHUNK, existing_ids};
+use crate::query::{
+    QUERY_TIMEOUT, connect_client, export_headers, fetch_all_events, fetch_items_for_header_event,
+};
+use crate::reconcile::{Method, negentropy_diff};
+use crate::relays::{WriteSession, relays_arg};
+
+const DCOSL_KINDS: [u16; 4] = [9998, 39998, 9999, 39999];
+
+fn rejection(event: &Event, err: &CommandError) -> serde_json::Value {
+    json!({
+        "event_id": event.id.to_hex(),
+        "kind": event.kind.as_u16(),
+        "error": { "code": err.code, "message": err.message },
+    })
+}
+
+fn authors(events: &[Event]) -> Vec<PublicKey> {
+    events
+        .iter()
+        .map(|e| e.pubkey)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Headers in `scope` on `from`, each followed by its items.
+async fn list_events(
+    client: &Client,
+    from: &str,
+    scope: &ExportScope,
+) -> Result<Vec<Event>, CommandError> {
+    let mut events = Vec::new();
+    for header in export_headers(client, from, scope).await? {
+        let items = fetch_items_for_header_event(client, from, &header, scope).await?;
+        if scope.keeps(&header, items.len()) {
+            events.push(header);
+            events.extend(items);
+        }
+    }
+    Ok(events)
+}
+
+/// Which of `ids` are `DCoSL` events on any of `relays`.
+async fn dcosl_ids_on(client: &Client, relays: &[String], ids: &[EventId]) -> HashSet<EventId> {
+    let mut found = HashSet::new();
+    for chunk in ids.chunks(LOOKUP_CHUNK) {
+        let filter = Filter::new()
+            .ids(chunk.iter().copied())
+            .kinds(DCOSL_KINDS.map(Kind::Custom));
+        if let Ok(events) = client
+            .fetch_events_from(relays, filter, QUERY_TIMEOUT)
+            .await
+        {
+            found.extend(events.into_iter().map(|e| e.id));
+        }
+    }
+    found
+}
+
+/// Which of `coordinates` name a `DCoSL` event on any of `relays`, looked up one by one:
+/// deleted list coordinates are few.
+async fn dcosl_coordinates_on(
+    client: &Client,
+    relays: &[String],
+    coordinates: &[Coordinate],
+) -> HashSet<Coordinate> {
+    let mut found = HashSet::new();
+    for coordinate in coordinates {
+        let filter = Filter::new()
+            .kind(coordinate.kind)
+            .author(coordinate.public_key)
+            .identifier(&coordinate.identifier)
+            .limit(1);
+        if let Ok(events) = client
+            .fetch_events_from(relays, filter, QUERY_TIMEOUT)
+            .await
+            && !events.is_empty()
+        {
+            found.insert(coordinate.clone());
+        }
+    }
+    found
+}
+
+/// Kind-5 deletions on `from` by the authors of `events` that target a `DCoSL` event, by id
+/// or by list coordinate: one being mirrored, or one `to` still holds (the usual case, since
+/// `from` dropped the event when it honoured the deletion).
+async fn related_deletions(
+    source: &Client,
+    from: &str,
+    target: &Client,
+    to: &[String],
+    events: &[Event],
+) -> Result<Vec<Event>, CommandError> {
+    let mut deletions = Vec::new();
+    for chunk in authors(events).chunks(LOOKUP_CHUNK) {
+        let filter = Filter::new()
+            .kind(Kind::EventDeletion)
+            .authors(chunk.iter().copied());
+        deletions.extend(fetch_all_events(source, from, filter).await?);
+    }
+
+    let mirrored: HashSet<EventId> = events.iter().map(|e| e.id).collect();
+    let unknown: Vec<EventId> = deletions
+        .iter()
+        .flat_map(|d| d.tags.event_ids())
+        .filter(|id| !mirrored.contains(id))
+        .copied()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let on_target = dcosl_ids_on(target, to, &unknown).await;
+
+    let mirrored_lists: HashSet<Coordinate> = events
+        .iter()
+        .filter_map(Event::coordinate)
+        .map(CoordinateBorrow::into_owned)
+        .collect();
+    let unknown_lists: Vec<Coordinate> = deletions
+        .iter()
+        .flat_map(|d| d.tags.coordinates())
+        .filter(|c| DCOSL_KINDS.contains(&c.kind.as_u16()) && !mirrored_lists.contains(*c))
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let lists_on_target = dcosl_coordinates_on(target, to, &unknown_lists).await;
+
+    deletions.retain(|d| {
+        d.tags
+            .event_ids()
+            .any(|id| mirrored.contains(id) || on_target.contains(id))
+            || d.tags
+                .coordinates()
+                .any(|c| mirrored_lists.contains(c) || lists_on_target.contains(c))
+    });
+    Ok(deletions)
+}
+
+/// Ids of `events` that some relay in `to` lacks: by negentropy where every target speaks
+/// it, otherwise by looking the ids up on each target.
+async fn missing_on_targets(
+    client: &Client,
+    to: &[String],
+    events: &[Event],
+) -> (HashSet<EventId>, Method) {
+    let kinds = DCOSL_KINDS
+        .map(Kind::Custom)
+        .into_iter()
+        .chain([Kind::EventDeletion]);
+    let filter = Filter::new().kinds(kinds).authors(authors(events));
+    let known: Vec<&Event> = events.iter().collect();
+
+    let mut missing = HashSet::new();
+    for relay in to {
+        let Some(diff) = negentropy_diff(client, relay, &filter, &known).await else {
+            let ids: Vec<EventId> = events.iter().map(|e| e.id).collect();
+            let present = existing_ids(client, to, &ids).await;
+            let missing = ids.into_iter().filter(|id| !present.contains(id)).collect();
+            return (missing, Method::Paging);
+        };
+        missing.extend(diff.local);
+    }
+    (missing, Method::Negentropy)
+}
+
+/// Copy `events` to `to`, skipping those every target relay already has.
+async fn copy_events(to: &[String], events: &[Event]) -> Result<serde_json::Value, CommandError> {
+    let session = WriteSession::connect(to).await?;
+    let (missing, method) = missing_on_targets(session.client(), to, events).await;
+
+    let mut copied = 0usize;
+    let mut rejected = Vec::new();
+    for event in events.iter().filter(|e| missing.contains(&e.id)) {
+        match session.send(event).await {
+            Ok(_) => copied += 1,
+            Err(e) => rejected.push(rejection(event, &e)),
+        }
+    }
+    session.disconnect().await;
+
+    Ok(json!({
+        "counts": {
+            "events": events.len(),
+            "copied": copied,
+            "skipped": events.len() - missing.len(),
+            "rejected": rejected.len(),
+        },
+        "method": method.as_str(),
+        "rejected": rejected,
+    }))
+}
+
+async fn mirror_events(
+    from: &str,
+    to: &[String],
+    scope: &ExportScope,
+) -> Result<serde_json::Value, CommandError> {
+    let source = connect_client(&[from.to_string()])
+        .await
+        .map_err(CommandError::from)?;
+    let fetched = async {
+        let mut events = list_events(&source, from, scope).await?;
+        let target = connect_client(to).await.map_err(CommandError::from)?;
+        let deletions = related_deletions(&source, from, &target, to, &events).await;
+        target.disconnect().await;
+        let deletions = deletions?;
+        let count = deletions.len();
+        events.extend(deletions);
+        Ok::<_, CommandError>((events, count))
+    }
+    .await;
+    source.disconnect().await;
+    let (events, deletions) = fetched?;
+
+    let mut result = copy_events(to, &events).await?;
+    result["from"] = json!(from);
+    result["to"] = json!(to);
+    result["counts"]["deletions"] = json!(deletions);
+    if let Some(scope) = scope.to_json() {
+        result["scope"] = scope;
+    }
+    Ok(result)
+}
+
+/// Copy `DCoSL` lists (and deletions of them) from one relay to others.
+pub async fn mirror(
+    from: String,
+    to: Vec<String>,
+    scope: ExportScope,
+) -> Result<CommandOutput, CommandError> {
+    let result = mirror_events(&from, &to, &scope).await?;
+
+    let target = relays_arg(&to);
+    let mut actions = vec![NextAction::new(
+        format!("wokhei count --relay={}", to[0]),
+        "Compare the target relay's counts with the source",
+    )];
+    if result["counts"]["rejected"].as_u64().unwrap_or(0) > 0 {
+        actions.push(NextAction::new(
+            format!("wokhei mirror --from={from} --to={target}"),
+            "Re-run after fixing rejections; events already copied are skipped",
+        ));
+    }
+    Ok(CommandOutput::new(result).next_actions(actions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr_relay_builder::MockRelay;
+
+    fn list(keys: &Keys) -> (Event, Event, Event) {
+        let header = EventBuilder::new(Kind::Custom(9998), "")
+            .tag(Tag::custom(TagKind::custom("names"), ["a", "as"]))
+            .sign_with_keys(keys)
+            .unwrap();
+        let item = |r: &str| {
+            EventBuilder::new(Kind::Custom(9999), "")
+                .tags([
+                    Tag::custom(TagKind::custom("z"), [header.id.to_hex()]),
+                    Tag::custom(TagKind::custom("r"), [r]),
+                ])
+                .sign_with_keys(keys)
+                .unwrap()
+        };
+        let (kept, deleted) = (item("https://kept"), item("https://deleted"));
+        (header, kept, deleted)
+    }
+
+    #[tokio::test]
+    async fn mirror_copies_lists_and_deletions_and_skips_existing() {
+        let (from_relay, to_relay) = (
+            MockRelay::run().await.unwrap(),
+            MockRelay::run().await.unwrap(),
+        );
+        let from = from_relay.url().await.to_string();
+        let to = vec![to_relay.url().await.to_string()];
+        let keys = Keys::generate();
+        let (header, kept, deleted) = list(&keys);
+        let deletion = EventBuilder::delete(EventDeletionRequest::new().id(deleted.id))
+            .sign_with_keys(&keys)
+            .unwrap();
+        let unrelated = EventBuilder::text_note("hi").sign_with_keys(&keys).unwrap();
+
+        let source = connect_client(std::slice::from_ref(&from)).await.unwrap();
+        for event in [&header, &kept, &deleted, &deletion, &unrelated] {
+            source.send_event(event).await.unwrap();
+        }
+        source.disconnect().await;
+        let target = connect_client(&to).await.unwrap();
+        target.send_event(&header).await.unwrap();
+        target.send_event(&deleted).await.unwrap();
+        target.disconnect().await;
+
+        let result = mirror_events(&from, &to, &ExportScope::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            result["counts"],
+            json!({ "events": 3, "copied": 2, "skipped": 1, "rejected": 0, "deletions": 1 })
+        );
+        assert_eq!(result["method"], "negentropy");
+    }
+
+    #[tokio::test]
+    async fn coordinate_deletions_are_kept_only_for_mirrored_or_held_lists() {
+        let (from_relay, to_relay) = (
+            MockRelay::run().await.unwrap(),
+            MockRelay::run().await.unwrap(),
+        );
+        let from = from_relay.url().await.to_string();
+        let to = vec![to_relay.url().await.to_string()];
+        let keys = Keys::generate();
+        let named = |d: &str| {
+            EventBuilder::new(Kind::Custom(39998), "")
+                .tags([
+                    Tag::custom(TagKind::custom("names"), ["a", "as"]),
+                    Tag::identifier(d),
+                ])
+                .sign_with_keys(&keys)
+                .unwrap()
+        };
+        let (mirrored, held) = (named("mirrored"), named("held"));
+        let delete = |d: &str| {
+            let coordinate = Coordinate::new(Kind::Custom(39998), keys.public_key()).identifier(d);
+            EventBuilder::delete(EventDeletionRequest::new().coordinate(coordinate))
+                .sign_with_keys(&keys)
+                .unwrap()
+        };
+        let deletions = [delete("mirrored"), delete("held"), delete("elsewhere")];
+
+        let source = connect_client(std::slice::from_ref(&from)).await.unwrap();
+        for deletion in &deletions {
+            source.send_event(deletion).await.unwrap();
+        }
+        let target = connect_client(&to).await.unwrap();
+        target.send_event(&held).await.unwrap();
+
+        let kept = related_deletions(&source, &from, &target, &to, &[mirrored])
+            .await
+            .unwrap();
+        let kept: HashSet<EventId> = kept.iter().map(|d| d.id).collect();
+        assert_eq!(kept, HashSet::from([deletions[0].id, deletions[1].id]));
+        source.disconnect().await;
+        target.disconnect().await;
+    }
+}
@@ -6,10 +6,13 @@ use serde_json::json;
 
 use agcli::{CommandError, CommandOutput, NextAction};
 
+use crate::broadcast::signed_output;
 use crate::error::AppError;
-use crate::keys::load_keys;
+use crate::relays::{WriteSession, relays_arg, reports_json};
+use crate::signer::{Signer, SignerSpec};
+use crate::validate::{Violation, check_event, reject};
 
-fn read_json_input<R: io::Read>(input: &str, stdin: R) -> Result<String, CommandError> {
+pub fn read_json_input<R: io::Read>(input: &str, stdin: R) -> Result<String, CommandError> {
     if input == "-" {
         let mut buf = String::new();
         let mut reader = stdin;
@@ -28,81 +31,429 @@ fn read_json_input<R: io::Read>(input: &str, stdin: R) -> Result<String, Command
     }
 }
 
-pub async fn publish(relay: String, input: String) -> Result<CommandOutput, CommandError> {
-    let keys = load_keys().map_err(|e| {
-        CommandError::from(e).next_actions(vec![NextAction::new(
-            "wokhei init --generate",
-            "Generate a keypair first",
-        )])
-    })?;
+/// Unsigned event fields read from `publish` input.
+struct Template {
+    kind: u16,
+    content: String,
+    tags: Vec<Tag>,
+}
 
-    // Read JSON input
-    let json_str = read_json_input(&input, io::stdin())?;
+/// Read one tag; `None` if it is not an array of at least two strings.
+fn parse_tag(tag_val: &serde_json::Value) -> Option<Tag> {
+    let tag_arr = tag_val.as_array()?;
+    let parts: Vec<String> = tag_arr
+        .iter()
+        .map(|v| v.as_str().map(String::from))
+        .collect::<Option<_>>()?;
+    (parts.len() >= 2).then(|| {
+        let kind = TagKind::custom(&parts[0]);
+        let values: Vec<&str> = parts[1..].iter().map(String::as_str).collect();
+        Tag::custom(kind, values)
+    })
+}
 
-    // Parse as unsigned event JSON
-    let raw: serde_json::Value = serde_json::from_str(&json_str).map_err(|_| {
-        CommandError::from(AppError::InvalidJson {
-            reason: "Failed to parse JSON input".to_string(),
-        })
-    })?;
+/// Parse an event template and check it against the `DCoSL` rules.
+///
+/// With `validate = false` the legacy behaviour applies: a missing kind defaults to 1
+/// and malformed tags are dropped.
+fn parse_template(raw: &serde_json::Value, validate: bool) -> Result<Template, AppError> {
+    let mut violations = Vec::new();
 
-    // Extract kind, content, tags
-    #[allow(clippy::cast_possible_truncation)] // Nostr kinds fit in u16
-    let kind_num = raw["kind"].as_u64().unwrap_or(1) as u16;
-    let content = raw["content"].as_str().unwrap_or("");
+    let kind = match raw.get("kind") {
+        None => {
+            violations.push(Violation::new("MISSING_KIND", "event template has no kind"));
+            1
+        }
+        Some(v) => v
+            .as_u64()
+            .and_then(|k| u16::try_from(k).ok())
+            .unwrap_or_else(|| {
+                violations.push(Violation::new(
+                    "INVALID_KIND",
+                    format!("kind {v} is not an integer between 0 and 65535"),
+                ));
+                1
+            }),
+    };
+    let content = raw["content"].as_str().unwrap_or("").to_string();
 
-    let event_tags: Vec<Tag> = raw["tags"]
-        .as_array()
-        .map(|tags_arr| {
-            tags_arr
-                .iter()
-                .filter_map(|tag_val| {
-                    tag_val.as_array().and_then(|tag_arr| {
-                        let parts: Vec<String> = tag_arr
-                            .iter()
-                            .filter_map(|v| v.as_str().map(String::from))
-                            .collect();
-                        (parts.len() >= 2).then(|| {
-                            let kind = TagKind::custom(&parts[0]);
-                            let values: Vec<&str> = parts[1..].iter().map(String::as_str).collect();
-                            Tag::custom(kind, values)
-                        })
-                    })
+    let mut tags = Vec::new();
+    for (i, tag_val) in raw["tags"].as_array().into_iter().flatten().enumerate() {
+        match parse_tag(tag_val) {
+            Some(tag) => tags.push(tag),
+            None => violations.push(Violation::new(
+                "MALFORMED_TAG",
+                format!("tag #{i} {tag_val} is not an array of at least two strings"),
+            )),
+        }
+    }
+
+    if !validate {
+        return Ok(Template {
+            kind,
+            content,
+            tags,
+        });
+    }
+
+    violations.extend(check_event(kind, &tags));
+    reject(&violations)?;
+    Ok(Template {
+        kind,
+        content,
+        tags,
+    })
+}
+
+/// `publish` input: one template, or a batch from a JSON array or JSON Lines.
+#[derive(Debug)]
+enum PublishInput {
+    Single(serde_json::Value),
+    Batch(Vec<Result<serde_json::Value, AppError>>),
+}
+
+fn parse_publish_input(json_str: &str) -> Result<PublishInput, AppError> {
+    if let Ok(raw) = serde_json::from_str::<serde_json::Value>(json_str) {
+        return Ok(match raw {
+            serde_json::Value::Array(items) => {
+                PublishInput::Batch(items.into_iter().map(Ok).collect())
+            }
+            raw => PublishInput::Single(raw),
+        });
+    }
+
+    let lines: Vec<(usize, &str)> = json_str
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect();
+    if lines.len() < 2 {
+        return Err(AppError::InvalidJson {
+            reason: "Failed to parse JSON input".to_string(),
+        });
+    }
+    Ok(PublishInput::Batch(
+        lines
+            .into_iter()
+            .map(|(n, line)| {
+                serde_json::from_str(line).map_err(|e| AppError::InvalidJson {
+                    reason: format!("line {}: {e}", n + 1),
                 })
-                .collect()
-        })
-        .unwrap_or_default();
+            })
+            .collect(),
+    ))
+}
 
-    let builder = EventBuilder::new(Kind::Custom(kind_num), content).tags(event_tags);
+/// Per-event entry for a batch entry that was not published.
+pub fn failed_entry(index: usize, err: &CommandError) -> serde_json::Value {
+    json!({
+        "index": index,
+        "ok": false,
+        "error": { "code": err.code, "message": err.message },
+    })
+}
 
-    let client = Client::builder().signer(keys).build();
-    if client.add_relay(&relay).await.is_err() {
-        return Err(CommandError::from(AppError::RelayUnreachable {
-            url: relay.clone(),
-        }));
+/// Batch summary around the per-event `results`, kept in input order.
+pub fn batch_result(results: Vec<serde_json::Value>) -> serde_json::Value {
+    let succeeded = results.iter().filter(|r| r["ok"] == true).count();
+    json!({
+        "count": results.len(),
+        "succeeded": succeeded,
+        "failed": results.len() - succeeded,
+        "results": serde_json::Value::Array(results),
+    })
+}
+
+/// Sign every valid template and send them all over one relay session.
+///
+/// A bad template, signature or relay rejection fails only its own entry.
+async fn publish_batch(
+    relays: &[String],
+    signer: Signer,
+    templates: Vec<Result<Template, AppError>>,
+    sign_only: bool,
+) -> Result<serde_json::Value, CommandError> {
+    let mut results = vec![serde_json::Value::Null; templates.len()];
+    let mut ready = Vec::new();
+    for (index, template) in templates.into_iter().enumerate() {
+        let event = match template {
+            Ok(t) => {
+                let builder = EventBuilder::new(Kind::Custom(t.kind), t.content).tags(t.tags);
+                signer.sign(builder).await
+            }
+            Err(e) => Err(e),
+        };
+        match event {
+            Ok(event) => ready.push((index, event)),
+            Err(e) => results[index] = failed_entry(index, &CommandError::from(e)),
+        }
     }
-    client.connect().await;
+    signer.shutdown().await;
 
-    let result = match client.send_event_builder(builder).await {
-        Ok(output) => {
-            let event_id = output.val.to_hex();
-            let result = json!({
-                "event_id": event_id,
-                "kind": kind_num,
-            });
+    let session = if sign_only || ready.is_empty() {
+        None
+    } else {
+        Some(WriteSession::connect(relays).await?)
+    };
+    for (index, event) in ready {
+        let mut entry = json!({
+            "index": index,
+            "ok": true,
+            "event_id": event.id.to_hex(),
+            "kind": event.kind.as_u16(),
+        });
+        match &session {
+            None => entry["event"] = serde_json::to_value(&event).unwrap_or_default(),
+            Some(session) => match session.send(&event).await {
+                Ok(reports) => entry["relays"] = json!(reports_json(&reports)),
+                Err(e) => {
+                    entry = failed_entry(index, &e);
+                    entry["event_id"] = json!(event.id.to_hex());
+                }
+            },
+        }
+        results[index] = entry;
+    }
+    if let Some(session) = session {
+        session.disconnect().await;
+    }
+
+    let mut result = batch_result(results);
+    if sign_only {
+        result["signed_only"] = json!(true);
+    }
+    Ok(result)
+}
+
+fn batch_actions(relays: &[String], result: &serde_json::Value) -> Vec<NextAction> {
+    let mut actions = Vec::new();
+    if result["failed"].as_u64().unwrap_or(0) > 0 {
+        actions.push(NextAction::new(
+            "wokhei publish <json-file-or-stdin>",
+            "Fix the entries with ok=false (see results[].error) and publish just those",
+        ));
+    }
+    let first_id = result["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|r| r["ok"] == true)
+        .and_then(|r| r["event_id"].as_str());
+    if let Some(event_id) = first_id {
+        if result.get("signed_only").is_some() {
+            actions.push(NextAction::new(
+                "wokhei broadcast <signed-json-file-or-stdin> [--relay=<url>[,<url>...]]",
+                "Send each results[].event unchanged",
+            ));
+        } else {
+            actions.push(NextAction::new(
+                format!("wokhei inspect --relay={} {event_id}", relays_arg(relays)),
+                "Inspect the first published event",
+            ));
+        }
+    }
+    actions
+}
 
-            let actions = vec![NextAction::new(
-                format!("wokhei inspect --relay={relay} {event_id}"),
-                "Inspect the published event",
-            )];
+pub async fn publish(
+    relays: Vec<String>,
+    signer: &SignerSpec,
+    input: String,
+    sign_only: bool,
+    validate: bool,
+) -> Result<CommandOutput, CommandError> {
+    // Read JSON input
+    let json_str = read_json_input(&input, io::stdin())?;
 
-            Ok(CommandOutput::new(result).next_actions(actions))
+    // One event template, or a JSON array / JSON Lines batch
+    let raw = match parse_publish_input(&json_str).map_err(CommandError::from)? {
+        PublishInput::Single(raw) => raw,
+        PublishInput::Batch(entries) => {
+            if entries.is_empty() {
+                return Err(CommandError::from(AppError::InvalidJson {
+                    reason: "batch input contains no event templates".to_string(),
+                }));
+            }
+            let templates = entries
+                .into_iter()
+                .map(|raw| raw.and_then(|raw| parse_template(&raw, validate)))
+                .collect();
+            let signer = Signer::load(signer)?;
+            let result = publish_batch(&relays, signer, templates, sign_only).await?;
+            let actions = batch_actions(&relays, &result);
+            return Ok(CommandOutput::new(result).next_actions(actions));
         }
-        Err(e) => Err(CommandError::from(AppError::RelayRejected {
-            reason: e.to_string(),
-        })),
     };
 
-    client.disconnect().await;
-    result
+    let Template {
+        kind: kind_num,
+        content,
+        tags,
+    } = parse_template(&raw, validate).map_err(CommandError::from)?;
+
+    let signer = Signer::load(signer)?;
+    let builder = EventBuilder::new(Kind::Custom(kind_num), content).tags(tags);
+
+    let event = signer.sign(builder).await;
+    signer.shutdown().await;
+    let event = event.map_err(CommandError::from)?;
+
+    if sign_only {
+        let result = json!({ "event_id": event.id.to_hex(), "kind": kind_num });
+        return Ok(signed_output(&event, result));
+    }
+
+    let session = WriteSession::connect(&relays).await?;
+    let sent = session.send(&event).await;
+    session.disconnect().await;
+    let reports = sent?;
+
+    let relay = relays_arg(&relays);
+    let event_id = event.id.to_hex();
+    let result = json!({
+        "event_id": event_id,
+        "kind": kind_num,
+        "relays": reports_json(&reports),
+    });
+
+    let actions = vec![NextAction::new(
+        format!("wokhei inspect --relay={relay} {event_id}"),
+        "Inspect the published event",
+    )];
+
+    Ok(CommandOutput::new(result).next_actions(actions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coord() -> String {
+        format!("39998:{}:books", Keys::generate().public_key().to_hex())
+    }
+
+    #[test]
+    fn valid_header_template_parses() {
+        let raw = json!({
+            "kind": 39998,
+            "content": "",
+            "tags": [["names", "book", "books"], ["d", "books"]],
+        });
+        let t = parse_template(&raw, true).unwrap();
+        assert_eq!(t.kind, 39998);
+        assert_eq!(t.tags.len(), 2);
+    }
+
+    #[test]
+    fn missing_kind_is_rejected_when_validating() {
+        let err = parse_template(&json!({ "tags": [] }), true).err().unwrap();
+        assert_eq!(err.code(), "MISSING_KIND");
+    }
+
+    #[test]
+    fn short_tag_is_rejected_when_validating() {
+        let raw = json!({ "kind": 1, "tags": [["t"], ["t", "ok"]] });
+        let err = parse_template(&raw, true).err().unwrap();
+        assert_eq!(err.code(), "MALFORMED_TAG");
+        assert!(err.to_string().contains("tag #0"));
+    }
+
+    #[test]
+    fn header_without_plural_is_rejected() {
+        let raw = json!({ "kind": 9998, "tags": [["names", "book"]] });
+        let err = parse_template(&raw, true).err().unwrap();
+        assert_eq!(err.code(), "NAMES_INCOMPLETE");
+    }
+
+    #[test]
+    fn addressable_item_violations_are_all_reported() {
+        let raw = json!({ "kind": 39999, "tags": [["z", "not-a-ref"]] });
+        let err = parse_template(&raw, true).err().unwrap();
+        assert_eq!(err.code(), "MALFORMED_Z");
+        assert!(err.to_string().contains("MISSING_D_TAG"));
+    }
+
+    #[test]
+    fn valid_item_template_parses() {
+        let raw = json!({ "kind": 9999, "tags": [["z", coord()], ["r", "https://x"]] });
+        assert!(parse_template(&raw, true).is_ok());
+    }
+
+    #[test]
+    fn no_validate_keeps_legacy_defaults() {
+        let raw = json!({ "tags": [["names", "book"], ["t"]] });
+        let t = parse_template(&raw, false).unwrap();
+        assert_eq!(t.kind, 1);
+        assert_eq!(t.tags.len(), 1);
+    }
+
+    #[test]
+    fn single_object_input_is_not_a_batch() {
+        let input = "{\n  \"kind\": 1,\n  \"content\": \"hi\"\n}";
+        assert!(matches!(
+            parse_publish_input(input),
+            Ok(PublishInput::Single(_))
+        ));
+    }
+
+    #[test]
+    fn json_array_input_is_a_batch() {
+        let Ok(PublishInput::Batch(entries)) = parse_publish_input(r#"[{"kind":1},{"kind":2}]"#)
+        else {
+            panic!("expected batch");
+        };
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn jsonl_input_skips_blank_lines_and_reports_bad_lines() {
+        let input = "{\"kind\":1}\n\nnot json\n{\"kind\":2}\n";
+        let Ok(PublishInput::Batch(entries)) = parse_publish_input(input) else {
+            panic!("expected batch");
+        };
+        assert_eq!(entries.len(), 3);
+        let err = entries[1].as_ref().unwrap_err();
+        assert_eq!(err.code(), "INVALID_JSON");
+        assert!(err.to_string().contains("line 3"));
+    }
+
+    #[test]
+    fn unparseable_single_line_is_invalid_json() {
+        let err = parse_publish_input("{nope").unwrap_err();
+        assert_eq!(err.code(), "INVALID_JSON");
+    }
+
+    #[tokio::test]
+    async fn batch_publishes_valid_entries_and_reports_failures() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let relays = vec![relay.url().await.to_string()];
+        let templates = vec![
+            parse_template(&json!({ "kind": 1, "content": "a" }), true),
+            parse_template(&json!({ "kind": 9998, "tags": [["names", "x"]] }), true),
+            parse_template(&json!({ "kind": 1, "content": "b" }), true),
+        ];
+
+        let result = publish_batch(&relays, Signer::Local(Keys::generate()), templates, false)
+            .await
+            .unwrap();
+        assert_eq!(result["count"], 3);
+        assert_eq!(result["succeeded"], 2);
+        assert_eq!(result["failed"], 1);
+        assert_eq!(result["results"][0]["relays"][0]["status"], "accepted");
+        assert_eq!(result["results"][1]["index"], 1);
+        assert_eq!(result["results"][1]["error"]["code"], "NAMES_INCOMPLETE");
+        assert_eq!(result["results"][2]["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn batch_sign_only_embeds_events_without_connecting() {
+        let templates = vec![parse_template(&json!({ "kind": 1, "content": "a" }), true)];
+        let relays = vec!["ws://127.0.0.1:1".to_string()];
+        let result = publish_batch(&relays, Signer::Local(Keys::generate()), templates, true)
+            .await
+            .unwrap();
+        assert_eq!(result["signed_only"], true);
+        assert_eq!(result["results"][0]["event"]["content"], "a");
+        assert_eq!(batch_actions(&relays, &result).len(), 1);
+    }
 }
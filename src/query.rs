@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 
 use nostr_sdk::prelude::*;
@@ -6,15 +6,24 @@ use serde_json::json;
 
 use agcli::{CommandError, CommandOutput, NextAction};
 
+use crate::cache::{CacheMode, EventCache};
 use crate::error::AppError;
+use crate::export::{ExportFile, ExportScope, ExportWriter};
+use crate::reconcile;
+use crate::relays::{CONNECT_TIMEOUT, relays_arg};
+use crate::render::{ListTable, TableFormat};
+use crate::sync::{SyncState, written_events};
+use crate::validate::{HeaderSchema, ItemReport, check_list_items};
 
 // Re-export from dcosl-core
 pub use dcosl_core::query::{
     event_to_json, header_d_tag, paginate, sort_event_json_desc, sort_events_desc,
 };
 
-const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+pub const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
 const FETCH_PAGE_SIZE: usize = 500;
+/// Largest limit `second_by_limit` asks for when draining one crowded second.
+const MAX_SECOND_LIMIT: usize = FETCH_PAGE_SIZE * 128;
 
 fn header_query_command(
     relay: &str,
@@ -23,11 +32,13 @@ fn header_query_command(
     name: Option<&String>,
     offset: usize,
     limit: usize,
+    mode: CacheMode,
 ) -> String {
     let mut parts = vec![
         "wokhei list-headers".to_string(),
         format!("--relay={relay}"),
     ];
+    parts.extend(mode.flag().map(String::from));
 
     if let Some(author) = author {
         parts.push(format!("--author={author}"));
@@ -45,6 +56,65 @@ fn header_query_command(
     parts.join(" ")
 }
 
+/// Previous / next / last-non-empty page actions for an offset-paginated listing.
+fn page_actions(
+    total: usize,
+    offset: usize,
+    limit: usize,
+    page_count: usize,
+    page_command: impl Fn(usize) -> String,
+) -> Vec<NextAction> {
+    let mut actions = Vec::new();
+    let step = limit.max(1);
+
+    if offset > 0 {
+        actions.push(NextAction::new(
+            page_command(offset.saturating_sub(step)),
+            "Go to the previous page",
+        ));
+    }
+
+    if limit > 0 && offset.saturating_add(limit) < total {
+        actions.push(NextAction::new(
+            page_command(offset.saturating_add(limit)),
+            "Go to the next page",
+        ));
+    }
+
+    if total > 0 && page_count == 0 {
+        let last_offset = ((total - 1) / step) * step;
+        actions.push(NextAction::new(
+            page_command(last_offset),
+            "Jump to the last non-empty page",
+        ));
+    }
+
+    actions
+}
+
+fn item_query_command(
+    relay: &str,
+    header_id: Option<&String>,
+    header_coordinate: Option<&String>,
+    offset: usize,
+    limit: usize,
+    mode: CacheMode,
+) -> String {
+    let mut parts = vec!["wokhei list-items".to_string(), format!("--relay={relay}")];
+    parts.extend(mode.flag().map(String::from));
+
+    if let Some(coordinate) = header_coordinate {
+        parts.push(format!("--header-coordinate={coordinate}"));
+    } else if let Some(id) = header_id {
+        parts.push(id.clone());
+    }
+
+    parts.push(format!("--offset={offset}"));
+    parts.push(format!("--limit={limit}"));
+
+    parts.join(" ")
+}
+
 fn item_add_command(relay: &str, header_ref: &str, coordinate_mode: bool) -> String {
     if coordinate_mode {
         format!("wokhei add-item --relay={relay} --header-coordinate={header_ref} --resource=<url>")
@@ -53,19 +123,414 @@ fn item_add_command(relay: &str, header_ref: &str, coordinate_mode: bool) -> Str
     }
 }
 
-async fn connect_client(relay: &str) -> Result<Client, AppError> {
+/// Connect to every relay in the set. Relays that don't connect within `CONNECT_TIMEOUT`
+/// are dropped from the client, so queries against them fail and get reported as
+/// unreachable instead of quietly returning nothing. Fails only if none connects.
+pub async fn connect_client(relays: &[String]) -> Result<Client, AppError> {
     let client = Client::default();
-    client
-        .add_relay(relay)
+    let mut added = Vec::with_capacity(relays.len());
+    for relay in relays {
+        let Ok(url) = RelayUrl::parse(relay) else {
+            continue;
+        };
+        if client.add_relay(url.clone()).await.is_ok() {
+            added.push(url);
+        }
+    }
+    let connected = client.try_connect(CONNECT_TIMEOUT).await;
+    for url in added
+        .iter()
+        .filter(|url| connected.failed.contains_key(*url))
+    {
+        let _ = client.force_remove_relay(url.clone()).await;
+    }
+    if added.len() <= connected.failed.len() {
+        client.disconnect().await;
+        return Err(AppError::RelayUnreachable {
+            url: relays_arg(relays),
+        });
+    }
+    Ok(client)
+}
+
+/// The newest version of the addressable event `kind:pubkey:d` on the client's relays.
+pub async fn fetch_latest_addressable(
+    client: &Client,
+    relay: &str,
+    kind: Kind,
+    pubkey: PublicKey,
+    d: &str,
+) -> Result<Option<Event>, CommandError> {
+    let filter = Filter::new().kind(kind).author(pubkey).identifier(d);
+    let events = client
+        .fetch_events(filter, QUERY_TIMEOUT)
         .await
-        .map_err(|_| AppError::RelayUnreachable {
-            url: relay.to_string(),
+        .map_err(|_| {
+            CommandError::from(AppError::RelayUnreachable {
+                url: relay.to_string(),
+            })
         })?;
-    client.connect().await;
-    Ok(client)
+    Ok(events.into_iter().max_by_key(|e| (e.created_at, e.id)))
+}
+
+/// The current version of the addressable event `target` names: a `kind:pubkey:d-tag`
+/// coordinate, or the id of any version of it.
+pub async fn fetch_current_addressable(
+    client: &Client,
+    relay: &str,
+    target: &str,
+    kind: Kind,
+) -> Result<Event, CommandError> {
+    let not_found = || {
+        let event_id = target.to_string();
+        CommandError::from(if kind == Kind::Custom(39998) {
+            AppError::HeaderNotFound { event_id }
+        } else {
+            AppError::EventNotFound { event_id }
+        })
+    };
+    let wrong_kind = || {
+        CommandError::new(
+            format!("{target} is not a kind {} event", kind.as_u16()),
+            "INVALID_ARGS",
+            "Only addressable events can be updated in place; pass their coordinate or event ID",
+        )
+    };
+
+    let (pubkey, d) = if target.contains(':') {
+        let (kind_num, pubkey, d) = dcosl_core::item::parse_coordinate_str(target)
+            .map_err(|e| CommandError::from(AppError::from(e)))?;
+        if kind_num != kind.as_u16() {
+            return Err(wrong_kind());
+        }
+        (pubkey, d)
+    } else {
+        let id = EventId::parse(target).map_err(|_| {
+            CommandError::from(AppError::InvalidEventId {
+                id: target.to_string(),
+            })
+        })?;
+        let event = client
+            .fetch_events(Filter::new().id(id).limit(1), QUERY_TIMEOUT)
+            .await
+            .map_err(|_| {
+                CommandError::from(AppError::RelayUnreachable {
+                    url: relay.to_string(),
+                })
+            })?
+            .into_iter()
+            .next()
+            .ok_or_else(not_found)?;
+        if event.kind != kind {
+            return Err(wrong_kind());
+        }
+        (event.pubkey, header_d_tag(&event).unwrap_or_default())
+    };
+    fetch_latest_addressable(client, relay, kind, pubkey, &d)
+        .await?
+        .ok_or_else(not_found)
+}
+
+/// `created_at` for a replacement of `previous`: now, or just after it if its clock ran ahead.
+pub fn replacement_created_at(previous: &Event) -> Timestamp {
+    Timestamp::now().max(previous.created_at + 1u64)
+}
+
+// ---------------------------------------------------------------------------
+// Multi-relay merge
+// ---------------------------------------------------------------------------
+
+/// Events merged across relays: deduplicated by id, and by `(kind, pubkey, d)` for
+/// addressable events (newest wins), remembering which relays each event was seen on.
+#[derive(Default)]
+struct MergedEvents {
+    events: HashMap<EventId, Event>,
+    seen_on: HashMap<EventId, Vec<String>>,
+    latest: HashMap<(u16, PublicKey, String), EventId>,
+    unreachable: Vec<String>,
+}
+
+/// NIP-01 replacement order: newer `created_at` wins, ties go to the lowest id.
+fn replaces(candidate: &Event, current: &Event) -> bool {
+    candidate.created_at > current.created_at
+        || (candidate.created_at == current.created_at && candidate.id < current.id)
+}
+
+impl MergedEvents {
+    fn insert(&mut self, relay: &str, event: Event) {
+        if let Some(relays) = self.seen_on.get_mut(&event.id) {
+            if !relays.iter().any(|r| r == relay) {
+                relays.push(relay.to_string());
+            }
+            return;
+        }
+
+        if event.kind.is_addressable() {
+            let key = (
+                event.kind.as_u16(),
+                event.pubkey,
+                header_d_tag(&event).unwrap_or_default(),
+            );
+            if let Some(current_id) = self.latest.get(&key).copied() {
+                if !replaces(&event, &self.events[&current_id]) {
+                    return;
+                }
+                self.events.remove(&current_id);
+                self.seen_on.remove(&current_id);
+            }
+            self.latest.insert(key, event.id);
+        }
+
+        self.seen_on.insert(event.id, vec![relay.to_string()]);
+        self.events.insert(event.id, event);
+    }
+
+    /// `Err` only when no relay in `relays` answered.
+    fn reachable(self, relays: &[String]) -> Result<Self, CommandError> {
+        if self.unreachable.len() == relays.len() {
+            return Err(CommandError::from(AppError::RelayUnreachable {
+                url: relays_arg(relays),
+            }));
+        }
+        Ok(self)
+    }
+
+    /// Note `relay` as unreachable when that is why it failed. Any other failure, such as
+    /// a capped second, would leave the merged answer silently short, so it is returned.
+    fn skip_unreachable(&mut self, relay: &str, err: CommandError) -> Result<(), CommandError> {
+        if err.code != "RELAY_UNREACHABLE" {
+            return Err(err);
+        }
+        self.unreachable.push(relay.to_string());
+        Ok(())
+    }
+
+    fn sorted_events(&self) -> Vec<Event> {
+        let mut events: Vec<Event> = self.events.values().cloned().collect();
+        sort_events_desc(&mut events);
+        events
+    }
+
+    fn event_json(&self, event: &Event) -> serde_json::Value {
+        let mut obj = event_to_json(event);
+        obj["relays"] = json!(self.seen_on.get(&event.id).cloned().unwrap_or_default());
+        obj
+    }
+
+    fn sorted_json(&self) -> Vec<serde_json::Value> {
+        self.sorted_events()
+            .iter()
+            .map(|e| self.event_json(e))
+            .collect()
+    }
+}
+
+async fn fetch_filters(
+    client: &Client,
+    relay: &str,
+    filters: &[&Filter],
+) -> Result<Vec<Event>, CommandError> {
+    let mut events = Vec::new();
+    for filter in filters {
+        events.extend(fetch_all_events(client, relay, (*filter).clone()).await?);
+    }
+    Ok(events)
+}
+
+/// Run `fetch_all_events` for every filter against each relay and merge the results.
+/// Fails when every relay is unreachable, or when any relay fails for another reason.
+async fn fetch_merged(
+    client: &Client,
+    relays: &[String],
+    filters: &[&Filter],
+) -> Result<MergedEvents, CommandError> {
+    let mut merged = MergedEvents::default();
+    for relay in relays {
+        match fetch_filters(client, relay, filters).await {
+            Ok(events) => {
+                for event in events {
+                    merged.insert(relay, event);
+                }
+            }
+            Err(err) => merged.skip_unreachable(relay, err)?,
+        }
+    }
+
+    merged.reachable(relays)
+}
+
+/// Where query commands get events: the relays (connected on first use), the local
+/// cache, or both, depending on `mode`. Relay answers are written back to the cache.
+struct EventSource<'a> {
+    relays: &'a [String],
+    mode: CacheMode,
+    cache: EventCache,
+    client: Option<Client>,
+    from_cache: bool,
+    /// Relays that didn't answer `NEG-OPEN`; later filters page through them directly.
+    no_negentropy: HashSet<String>,
+}
+
+impl<'a> EventSource<'a> {
+    fn new(relays: &'a [String], mode: CacheMode) -> Self {
+        Self::with_cache(relays, mode, EventCache::load())
+    }
+
+    fn with_cache(relays: &'a [String], mode: CacheMode, cache: EventCache) -> Self {
+        Self {
+            relays,
+            mode,
+            cache,
+            client: None,
+            from_cache: false,
+            no_negentropy: HashSet::new(),
+        }
+    }
+
+    async fn client(&mut self) -> Result<&Client, CommandError> {
+        if self.client.is_none() {
+            self.client = Some(
+                connect_client(self.relays)
+                    .await
+                    .map_err(CommandError::from)?,
+            );
+        }
+        Ok(self.client.as_ref().expect("client was just connected"))
+    }
+
+    /// Cached matches for `filters`, or `None` when the relays should be asked instead.
+    fn cached(&mut self, filters: &[&Filter]) -> Option<MergedEvents> {
+        if self.mode == CacheMode::Refresh {
+            return None;
+        }
+        let mut merged = MergedEvents::default();
+        for filter in filters {
+            for (event, relays) in self.cache.matching(self.relays, filter) {
+                for relay in relays {
+                    merged.insert(&relay, event.clone());
+                }
+            }
+        }
+        if merged.events.is_empty() && self.mode == CacheMode::CacheFirst {
+            return None;
+        }
+        self.from_cache = true;
+        Some(merged)
+    }
+
+    fn remember(&mut self, merged: &MergedEvents) {
+        for (id, relays) in &merged.seen_on {
+            for relay in relays {
+                self.cache.record(relay, &merged.events[id]);
+            }
+        }
+    }
+
+    /// One relay's events for `filter`. Where the relay speaks negentropy, only events the
+    /// cache lacks are downloaded, and cached events the relay no longer has are forgotten.
+    /// A relay that doesn't is remembered, so later filters skip the `NEG-OPEN` timeout.
+    async fn fetch_relay(
+        &mut self,
+        relay: &str,
+        filter: &Filter,
+    ) -> Result<Vec<Event>, CommandError> {
+        if self.no_negentropy.contains(relay) {
+            let client = self.client().await?;
+            return fetch_pages(client, relay, filter, FETCH_PAGE_SIZE, false).await;
+        }
+        let known: Vec<Event> = self
+            .cache
+            .matching(&[relay.to_string()], filter)
+            .into_iter()
+            .map(|(event, _)| event.clone())
+            .collect();
+        let known_refs: Vec<&Event> = known.iter().collect();
+        let client = self.client().await?;
+        let Some(diff) = reconcile::negentropy_diff(client, relay, filter, &known_refs).await
+        else {
+            let events = fetch_pages(client, relay, filter, FETCH_PAGE_SIZE, false).await;
+            if filter.limit.is_none() {
+                self.no_negentropy.insert(relay.to_string());
+            }
+            return events;
+        };
+        let fetched = reconcile::fetch_ids(client, relay, &diff.remote).await?;
+        for id in &diff.local {
+            self.cache.forget(relay, id);
+        }
+        Ok(known
+            .into_iter()
+            .filter(|e| !diff.local.contains(&e.id))
+            .chain(fetched)
+            .collect())
+    }
+
+    async fn fetch_relay_all(
+        &mut self,
+        relay: &str,
+        filters: &[&Filter],
+    ) -> Result<Vec<Event>, CommandError> {
+        let mut events = Vec::new();
+        for filter in filters {
+            events.extend(self.fetch_relay(relay, filter).await?);
+        }
+        Ok(events)
+    }
+
+    async fn fetch(&mut self, filter: &Filter) -> Result<MergedEvents, CommandError> {
+        self.fetch_all(&[filter]).await
+    }
+
+    /// Everything matching any of `filters`, merged across relays.
+    async fn fetch_all(&mut self, filters: &[&Filter]) -> Result<MergedEvents, CommandError> {
+        if let Some(cached) = self.cached(filters) {
+            return Ok(cached);
+        }
+        let mut merged = MergedEvents::default();
+        for relay in self.relays {
+            match self.fetch_relay_all(relay, filters).await {
+                Ok(events) => {
+                    for event in events {
+                        merged.insert(relay, event);
+                    }
+                }
+                Err(err) => merged.skip_unreachable(relay, err)?,
+            }
+        }
+        let merged = merged.reachable(self.relays)?;
+        self.remember(&merged);
+        Ok(merged)
+    }
+
+    /// Every item pointing at `parent_ref`.
+    async fn fetch_items(&mut self, parent_ref: &str) -> Result<MergedEvents, CommandError> {
+        let (z_filter, a_filter) = item_filters(parent_ref);
+        self.fetch_all(&[&z_filter, &a_filter]).await
+    }
+
+    /// `"cache"` if any answer came from the cache, else `"relays"`.
+    fn source(&self) -> &'static str {
+        if self.from_cache { "cache" } else { "relays" }
+    }
+
+    /// Disconnect and persist what the relays returned. A cache that can't be written is
+    /// not worth failing a read-only command over, so the reason comes back as a warning.
+    async fn close(self) -> Option<String> {
+        if let Some(client) = &self.client {
+            client.disconnect().await;
+        }
+        self.cache.save().err().map(|e| e.to_string())
+    }
+}
+
+/// Report a cache `EventSource::close` couldn't save as `cache_warning` in `result`.
+fn note_cache_warning(result: &mut serde_json::Value, warning: Option<String>) {
+    if let Some(warning) = warning {
+        result["cache_warning"] = json!(warning);
+    }
 }
 
-fn build_header_filter(
+pub fn build_header_filter(
     author: Option<&String>,
     tag: Option<&String>,
 ) -> Result<Filter, CommandError> {
@@ -87,57 +552,158 @@ fn build_header_filter(
     Ok(filter)
 }
 
+/// Every event matching `base_filter` on `relay`, paged newest first by `created_at`.
 pub async fn fetch_all_events(
     client: &Client,
     relay: &str,
     base_filter: Filter,
+) -> Result<Vec<Event>, CommandError> {
+    fetch_pages(client, relay, &base_filter, FETCH_PAGE_SIZE, true).await
+}
+
+async fn fetch_page(client: &Client, relay: &str, filter: Filter) -> Result<Events, CommandError> {
+    client
+        .fetch_events_from([relay], filter, QUERY_TIMEOUT)
+        .await
+        .map_err(|_| {
+            CommandError::from(AppError::RelayUnreachable {
+                url: relay.to_string(),
+            })
+        })
+}
+
+/// Pages move `until` to one second below the oldest event seen. A full page may have
+/// cut through that second, so it is drained by `boundary_second` first; otherwise any
+/// events sharing it beyond the page size would be skipped. `negentropy` is false for a
+/// relay already known to lack it, so its boundary seconds go straight to `second_by_limit`.
+async fn fetch_pages(
+    client: &Client,
+    relay: &str,
+    base_filter: &Filter,
+    page_size: usize,
+    mut negentropy: bool,
 ) -> Result<Vec<Event>, CommandError> {
     let mut all_events: Vec<Event> = Vec::new();
-    let mut seen_ids: HashSet<String> = HashSet::new();
+    let mut seen_ids: HashSet<EventId> = HashSet::new();
     let mut until_secs: Option<u64> = None;
 
     loop {
-        let mut filter = base_filter.clone().limit(FETCH_PAGE_SIZE);
+        let mut filter = base_filter.clone().limit(page_size);
         if let Some(secs) = until_secs {
             filter = filter.until(Timestamp::from_secs(secs));
         }
 
-        let batch = client
-            .fetch_events(filter, QUERY_TIMEOUT)
-            .await
-            .map_err(|_| {
-                CommandError::from(AppError::RelayUnreachable {
-                    url: relay.to_string(),
-                })
-            })?;
-
+        let batch = fetch_page(client, relay, filter).await?;
         if batch.is_empty() {
             break;
         }
 
-        let mut oldest_created_at = u64::MAX;
-        for event in batch.iter() {
-            oldest_created_at = oldest_created_at.min(event.created_at.as_secs());
-            let event_id = event.id.to_hex();
-            if seen_ids.insert(event_id) {
-                all_events.push(event.clone());
+        let full = batch.len() >= page_size;
+        let oldest = batch
+            .iter()
+            .map(|e| e.created_at.as_secs())
+            .min()
+            .unwrap_or(0);
+        for event in batch {
+            if seen_ids.insert(event.id) {
+                all_events.push(event);
             }
         }
-
-        if batch.len() < FETCH_PAGE_SIZE || oldest_created_at == 0 {
+        if !full {
             break;
         }
 
-        let next_until = oldest_created_at.saturating_sub(1);
-        if until_secs == Some(next_until) {
-            break;
+        let rest = {
+            let known: Vec<&Event> = all_events
+                .iter()
+                .filter(|e| e.created_at.as_secs() == oldest)
+                .collect();
+            boundary_second(
+                client,
+                relay,
+                base_filter,
+                oldest,
+                &known,
+                page_size,
+                &mut negentropy,
+            )
+            .await?
+        };
+        for event in rest {
+            if seen_ids.insert(event.id) {
+                all_events.push(event);
+            }
         }
+
+        let Some(next_until) = oldest.checked_sub(1) else {
+            break;
+        };
         until_secs = Some(next_until);
     }
 
     Ok(all_events)
 }
 
+/// Events in `base_filter` created at exactly `secs` that `known` lacks. Negentropy lists
+/// the whole second without a limit; relays without it (`negentropy` is then cleared so
+/// later pages don't wait for it again) are asked with a growing limit instead.
+async fn boundary_second(
+    client: &Client,
+    relay: &str,
+    base_filter: &Filter,
+    secs: u64,
+    known: &[&Event],
+    page_size: usize,
+    negentropy: &mut bool,
+) -> Result<Vec<Event>, CommandError> {
+    let at = Timestamp::from_secs(secs);
+    let mut second = base_filter.clone().since(at).until(at);
+    second.limit = None;
+
+    if *negentropy {
+        match reconcile::missing_events(client, relay, &second, known).await? {
+            Some(missing) => return Ok(missing),
+            None => *negentropy = false,
+        }
+    }
+    second_by_limit(client, relay, &second, page_size).await
+}
+
+/// Re-ask for one second with a doubling limit. The relay has already filled a page of
+/// `page_size`, so an answer shorter than any batch it filled is the whole second. An answer
+/// short of the limit but not of that may be the relay's cap (strfry's default is 500), as
+/// may a second still full at `MAX_SECOND_LIMIT`: a NIP-45 count then decides, and without a
+/// matching count the second fails with `RELAY_LIMIT_CAPPED` instead of coming back short.
+async fn second_by_limit(
+    client: &Client,
+    relay: &str,
+    second: &Filter,
+    page_size: usize,
+) -> Result<Vec<Event>, CommandError> {
+    let mut served = page_size;
+    let mut limit = page_size;
+    loop {
+        limit = limit.saturating_mul(2).min(MAX_SECOND_LIMIT);
+        let batch = fetch_page(client, relay, second.clone().limit(limit)).await?;
+        let returned = batch.len();
+        if returned < served {
+            return Ok(batch.into_iter().collect());
+        }
+        if returned == limit && limit < MAX_SECOND_LIMIT {
+            served = returned;
+            continue;
+        }
+        if count_filter(client, relay, second.clone()).await.ok() == Some(returned) {
+            return Ok(batch.into_iter().collect());
+        }
+        return Err(CommandError::from(AppError::RelayLimitCapped {
+            url: relay.to_string(),
+            created_at: second.since.map_or(0, |t| t.as_secs()),
+            returned,
+        }));
+    }
+}
+
 async fn count_filter(client: &Client, relay: &str, filter: Filter) -> Result<usize, CommandError> {
     let relay_handle = client.relay(relay).await.map_err(|_| {
         CommandError::from(AppError::RelayUnreachable {
@@ -156,20 +722,22 @@ async fn count_filter(client: &Client, relay: &str, filter: Filter) -> Result<us
 }
 
 pub async fn list_headers(
-    relay: String,
+    relays: Vec<String>,
     author: Option<String>,
     tag: Option<String>,
     name: Option<String>,
     offset: usize,
     limit: usize,
+    mode: CacheMode,
 ) -> Result<CommandOutput, CommandError> {
-    let client = connect_client(&relay).await.map_err(CommandError::from)?;
+    let mut source = EventSource::new(&relays, mode);
+    let relay = relays_arg(&relays);
 
     let headers_result = async {
         let filter = build_header_filter(author.as_ref(), tag.as_ref())?;
-        let events = fetch_all_events(&client, &relay, filter).await?;
+        let merged = source.fetch(&filter).await?;
 
-        let mut headers: Vec<serde_json::Value> = events.iter().map(event_to_json).collect();
+        let mut headers: Vec<serde_json::Value> = merged.sorted_json();
 
         // Client-side name substring filter (Nostr can't do substring search)
         if let Some(ref name_filter) = name {
@@ -212,103 +780,89 @@ pub async fn list_headers(
             }
         }
 
-        if offset > 0 {
-            let step = limit.max(1);
-            let prev_offset = offset.saturating_sub(step);
-            actions.push(NextAction::new(
-                header_query_command(
-                    &relay,
-                    author.as_ref(),
-                    tag.as_ref(),
-                    name.as_ref(),
-                    prev_offset,
-                    limit,
-                ),
-                "Go to the previous page",
-            ));
-        }
-
-        if has_more {
-            actions.push(NextAction::new(
-                header_query_command(
-                    &relay,
-                    author.as_ref(),
-                    tag.as_ref(),
-                    name.as_ref(),
-                    offset.saturating_add(limit),
-                    limit,
-                ),
-                "Go to the next page",
-            ));
-        }
-
-        if total > 0 && page_count == 0 {
-            let step = limit.max(1);
-            let last_offset = ((total - 1) / step) * step;
-            actions.push(NextAction::new(
+        actions.extend(page_actions(
+            total,
+            offset,
+            limit,
+            page_count,
+            |page_offset| {
                 header_query_command(
                     &relay,
                     author.as_ref(),
                     tag.as_ref(),
                     name.as_ref(),
-                    last_offset,
+                    page_offset,
                     limit,
-                ),
-                "Jump to the last non-empty page",
-            ));
-        }
+                    mode,
+                )
+            },
+        ));
 
         actions.push(NextAction::new(
             format!("wokhei create-header --relay={relay} --name=<singular> --plural=<plural>"),
             "Create a new list header",
         ));
 
-        Ok(CommandOutput::new(json!({
+        let result = json!({
             "total": total,
             "count": page_count,
             "offset": offset,
             "limit": limit,
             "has_more": has_more,
+            "source": source.source(),
+            "unreachable_relays": merged.unreachable,
             "headers": page_headers,
-        }))
-        .next_actions(actions))
+        });
+        Ok((result, actions))
     }
     .await;
 
-    client.disconnect().await;
-    headers_result
+    let warning = source.close().await;
+    let (mut result, actions) = headers_result?;
+    note_cache_warning(&mut result, warning);
+    Ok(CommandOutput::new(result).next_actions(actions))
 }
 
 pub async fn list_items(
-    relay: String,
+    relays: Vec<String>,
     header_id: Option<String>,
     header_coordinate: Option<String>,
+    offset: usize,
     limit: usize,
+    mode: CacheMode,
 ) -> Result<CommandOutput, CommandError> {
-    let client = connect_client(&relay).await.map_err(CommandError::from)?;
-
-    let (all_items, header_ref, coordinate_mode) = if let Some(ref coord_str) = header_coordinate {
-        let normalized_ref = normalize_coordinate_ref(coord_str)?;
-        let items = fetch_items_by_parent_ref(&client, &relay, &normalized_ref, limit).await?;
-        (items, normalized_ref, true)
-    } else {
-        let id_str = header_id.as_deref().unwrap_or("");
-        let event_id = EventId::parse(id_str).map_err(|_| {
-            CommandError::from(AppError::InvalidEventId {
-                id: id_str.to_string(),
-            })
-        })?;
-        let header_event = fetch_header_event_by_id(&client, &relay, event_id).await?;
-        let (resolved_ref, resolved_coordinate_mode) = z_ref_for_header_event(&header_event)?;
-        let items = fetch_items_by_parent_ref(&client, &relay, &resolved_ref, limit).await?;
-        (items, resolved_ref, resolved_coordinate_mode)
-    };
+    let mut source = EventSource::new(&relays, mode);
+    let relay = relays_arg(&relays);
+
+    let fetched = async {
+        if let Some(ref coord_str) = header_coordinate {
+            let normalized_ref = normalize_coordinate_ref(coord_str)?;
+            let items = source.fetch_items(&normalized_ref).await?;
+            Ok::<_, CommandError>((items, normalized_ref, true))
+        } else {
+            let id_str = header_id.as_deref().unwrap_or("");
+            let event_id = EventId::parse(id_str).map_err(|_| {
+                CommandError::from(AppError::InvalidEventId {
+                    id: id_str.to_string(),
+                })
+            })?;
+            let header_event = fetch_header_event_by_id(&mut source, event_id).await?;
+            let (resolved_ref, resolved_coordinate_mode) = z_ref_for_header_event(&header_event)?;
+            let items = source.fetch_items(&resolved_ref).await?;
+            Ok((items, resolved_ref, resolved_coordinate_mode))
+        }
+    }
+    .await;
 
-    client.disconnect().await;
+    let origin = source.source();
+    let warning = source.close().await;
+    let (merged, header_ref, coordinate_mode) = fetched?;
+    let all_items = merged.sorted_json();
+    let total = all_items.len();
 
     let add_item_cmd = item_add_command(&relay, &header_ref, coordinate_mode);
 
-    if all_items.is_empty() {
+    if total == 0 && offset == 0 {
         return Err(
             CommandError::from(AppError::NoResults).next_actions(vec![NextAction::new(
                 add_item_cmd,
@@ -317,23 +871,53 @@ pub async fn list_items(
         );
     }
 
-    let actions = vec![
-        NextAction::new(add_item_cmd, "Add another item to this list"),
-        NextAction::new(
+    let page_items = paginate(&all_items, offset, limit);
+    let has_more = limit > 0 && offset.saturating_add(limit) < total;
+    let page_count = page_items.len();
+
+    let mut actions = vec![NextAction::new(
+        add_item_cmd,
+        "Add another item to this list",
+    )];
+    if let Some(first) = page_items.first() {
+        actions.push(NextAction::new(
             format!(
                 "wokhei inspect --relay={relay} {}",
-                all_items[0]["event_id"].as_str().unwrap_or("")
+                first["event_id"].as_str().unwrap_or("")
             ),
             "Inspect the first item",
-        ),
-    ];
+        ));
+    }
+    actions.extend(page_actions(
+        total,
+        offset,
+        limit,
+        page_count,
+        |page_offset| {
+            item_query_command(
+                &relay,
+                header_id.as_ref(),
+                header_coordinate.as_ref(),
+                page_offset,
+                limit,
+                mode,
+            )
+        },
+    ));
 
-    Ok(CommandOutput::new(json!({
-        "count": all_items.len(),
+    let mut result = json!({
+        "total": total,
+        "count": page_count,
+        "offset": offset,
+        "limit": limit,
+        "has_more": has_more,
         "header_ref": header_ref,
-        "items": all_items,
-    }))
-    .next_actions(actions))
+        "source": origin,
+        "unreachable_relays": merged.unreachable,
+        "items": page_items,
+    });
+    note_cache_warning(&mut result, warning);
+    Ok(CommandOutput::new(result).next_actions(actions))
 }
 
 fn normalize_coordinate_ref(coord_str: &str) -> Result<String, CommandError> {
@@ -349,21 +933,13 @@ fn normalize_coordinate_ref(coord_str: &str) -> Result<String, CommandError> {
 }
 
 async fn fetch_header_event_by_id(
-    client: &Client,
-    relay: &str,
+    source: &mut EventSource<'_>,
     event_id: EventId,
 ) -> Result<Event, CommandError> {
     let filter = Filter::new().id(event_id).limit(1);
-    let header_events = client
-        .fetch_events(filter, QUERY_TIMEOUT)
-        .await
-        .map_err(|_| {
-            CommandError::from(AppError::RelayUnreachable {
-                url: relay.to_string(),
-            })
-        })?;
+    let mut header_events = source.fetch(&filter).await?;
 
-    header_events.into_iter().next().ok_or_else(|| {
+    header_events.events.remove(&event_id).ok_or_else(|| {
         CommandError::from(AppError::HeaderNotFound {
             event_id: event_id.to_hex(),
         })
@@ -389,71 +965,46 @@ fn z_ref_for_header_event(header_event: &Event) -> Result<(String, bool), Comman
     }
 }
 
-async fn fetch_items_by_parent_ref(
-    client: &Client,
-    relay: &str,
-    parent_ref: &str,
-    limit: usize,
-) -> Result<Vec<serde_json::Value>, CommandError> {
+/// Items pointing at `parent_ref` through a `z` tag or an `a` tag.
+fn item_filters(parent_ref: &str) -> (Filter, Filter) {
     let item_kinds = vec![Kind::Custom(9999), Kind::Custom(39999)];
 
-    let z_filter = Filter::new()
-        .kinds(item_kinds.clone())
-        .custom_tag(
-            SingleLetterTag::lowercase(Alphabet::Z),
-            parent_ref.to_string(),
-        )
-        .limit(limit);
-
-    let a_filter = Filter::new()
-        .kinds(item_kinds)
-        .custom_tag(
-            SingleLetterTag::lowercase(Alphabet::A),
-            parent_ref.to_string(),
-        )
-        .limit(limit);
-
-    let relay_err = || {
-        CommandError::from(AppError::RelayUnreachable {
-            url: relay.to_string(),
-        })
-    };
-
-    let (z_result, a_result) = tokio::join!(
-        client.fetch_events(z_filter, QUERY_TIMEOUT),
-        client.fetch_events(a_filter, QUERY_TIMEOUT),
+    let z_filter = Filter::new().kinds(item_kinds.clone()).custom_tag(
+        SingleLetterTag::lowercase(Alphabet::Z),
+        parent_ref.to_string(),
     );
 
-    let z_events = z_result.map_err(|_| relay_err())?;
-    let a_events = a_result.map_err(|_| relay_err())?;
-
-    let mut seen = HashSet::new();
-    let items = z_events
-        .iter()
-        .chain(a_events.iter())
-        .filter(|e| seen.insert(e.id))
-        .map(event_to_json)
-        .collect();
+    let a_filter = Filter::new().kinds(item_kinds).custom_tag(
+        SingleLetterTag::lowercase(Alphabet::A),
+        parent_ref.to_string(),
+    );
 
-    Ok(items)
+    (z_filter, a_filter)
 }
 
-async fn fetch_items_for_header_event(
+pub async fn fetch_items_for_header_event(
     client: &Client,
     relay: &str,
     header_event: &Event,
+    scope: &ExportScope,
 ) -> Result<Vec<Event>, CommandError> {
-    let (z_ref, _) = z_ref_for_header_event(header_event)?;
-    let z_filter = Filter::new()
-        .kinds(vec![Kind::Custom(9999), Kind::Custom(39999)])
-        .custom_tag(SingleLetterTag::lowercase(Alphabet::Z), z_ref);
-    let mut items = fetch_all_events(client, relay, z_filter).await?;
+    let mut items = Vec::new();
+    let mut seen = HashSet::new();
+    for filter in header_items_filters(header_event, scope)? {
+        for event in fetch_all_events(client, relay, filter).await? {
+            if seen.insert(event.id) {
+                items.push(event);
+            }
+        }
+    }
     sort_events_desc(&mut items);
     Ok(items)
 }
 
 pub async fn count(relay: String) -> Result<CommandOutput, CommandError> {
-    let client = connect_client(&relay).await.map_err(CommandError::from)?;
+    let client = connect_client(std::slice::from_ref(&relay))
+        .await
+        .map_err(CommandError::from)?;
 
     let count_fut = async {
         let (
@@ -534,31 +1085,210 @@ pub async fn count(relay: String) -> Result<CommandOutput, CommandError> {
     result
 }
 
-pub async fn export(relay: String) -> Result<CommandOutput, CommandError> {
-    let client = connect_client(&relay).await.map_err(CommandError::from)?;
-
-    let result = async {
-        let header_filter = Filter::new().kinds(vec![Kind::Custom(9998), Kind::Custom(39998)]);
-        let mut header_events = fetch_all_events(&client, &relay, header_filter).await?;
-        sort_events_desc(&mut header_events);
+/// Headers in `scope`, newest first. `--header`/`--header-coordinate` that match nothing
+/// are an error rather than an empty backup.
+pub async fn export_headers(
+    client: &Client,
+    relay: &str,
+    scope: &ExportScope,
+) -> Result<Vec<Event>, CommandError> {
+    let mut header_events = fetch_all_events(client, relay, scope.header_filter()?).await?;
+    header_events.retain(|h| scope.matches_name(h));
+    sort_events_desc(&mut header_events);
+
+    if header_events.is_empty()
+        && let Some(reference) = scope.header.as_ref().or(scope.header_coordinate.as_ref())
+    {
+        return Err(CommandError::from(AppError::HeaderNotFound {
+            event_id: reference.clone(),
+        })
+        .next_actions(vec![NextAction::new(
+            format!("wokhei list-headers --relay={relay}"),
+            "Find the header on this relay",
+        )]));
+    }
+    Ok(header_events)
+}
 
-        let mut exported_headers = Vec::with_capacity(header_events.len());
-        let mut total_items = 0usize;
+/// Stream every header in scope and its items into `file` as they're fetched.
+async fn export_to_file(
+    client: &Client,
+    relay: &str,
+    header_events: &[Event],
+    scope: &ExportScope,
+    file: &ExportFile,
+    exported_at: u64,
+) -> Result<serde_json::Value, CommandError> {
+    let mut writer = ExportWriter::create(file, relay, exported_at).map_err(CommandError::from)?;
+    for header_event in header_events {
+        let items = fetch_items_for_header_event(client, relay, header_event, scope).await?;
+        if scope.keeps(header_event, items.len()) {
+            writer
+                .write_list(header_event, &items)
+                .map_err(CommandError::from)?;
+        }
+    }
+    let (headers, items) = writer.finish().map_err(CommandError::from)?;
+
+    Ok(json!({
+        "relay": relay,
+        "exported_at": exported_at,
+        "output": file.path,
+        "format": file.format.as_str(),
+        "counts": {
+            "headers": headers,
+            "items": items,
+        },
+    }))
+}
 
-        for header_event in &header_events {
-            let items = fetch_items_for_header_event(&client, &relay, header_event).await?;
-            total_items = total_items.saturating_add(items.len());
+/// Items of one header (through `z` or `a`, like `item_filters`), limited to `scope`.
+fn header_items_filters(
+    header_event: &Event,
+    scope: &ExportScope,
+) -> Result<[Filter; 2], CommandError> {
+    let (z_ref, _) = z_ref_for_header_event(header_event)?;
+    let (z_filter, a_filter) = item_filters(&z_ref);
+    Ok([scope.item_filter(z_filter), scope.item_filter(a_filter)])
+}
 
-            let item_json: Vec<serde_json::Value> = items.iter().map(event_to_json).collect();
+/// Items of `header_event` the relay holds but `written` lacks, reconciled by negentropy
+/// for each of its item filters. `None` when the relay doesn't speak it.
+async fn missing_items(
+    client: &Client,
+    relay: &str,
+    header_event: &Event,
+    scope: &ExportScope,
+    written: &[Event],
+) -> Result<Option<Vec<Event>>, CommandError> {
+    let mut missing = Vec::new();
+    let mut seen = HashSet::new();
+    for filter in header_items_filters(header_event, scope)? {
+        let known: Vec<&Event> = written
+            .iter()
+            .filter(|e| filter.match_event(e, MatchEventOptions::new()))
+            .collect();
+        let Some(events) = reconcile::missing_events(client, relay, &filter, &known).await? else {
+            return Ok(None);
+        };
+        missing.extend(events.into_iter().filter(|e| seen.insert(e.id)));
+    }
+    Ok(Some(missing))
+}
 
-            exported_headers.push(json!({
-                "header": event_to_json(header_event),
-                "items_count": item_json.len(),
-                "items": item_json,
-            }));
+/// `--sync`: append only events not yet in the output file for this relay, scope and file.
+/// Relays that speak negentropy are reconciled against the file, so only missing items are
+/// downloaded (late or back-dated ones included); otherwise items newer than the saved cursor
+/// are paged through. The first run (or a missing output file) writes everything.
+async fn sync_to_file(
+    client: &Client,
+    relay: &str,
+    header_events: &[Event],
+    scope: &ExportScope,
+    file: &ExportFile,
+    exported_at: u64,
+    state: &mut SyncState,
+) -> Result<serde_json::Value, CommandError> {
+    let key = SyncState::key(relay, scope.to_json().as_ref(), &file.path);
+    let previous = state
+        .get(&key)
+        .filter(|_| std::path::Path::new(&file.path).exists())
+        .cloned();
+    let written = if previous.is_some() {
+        written_events(&file.path)
+    } else {
+        Vec::new()
+    };
+    let written_ids: HashSet<EventId> = written.iter().map(|e| e.id).collect();
+    let mut negentropy = previous.is_some();
+    let is_new = |e: &Event| previous.as_ref().is_none_or(|c| c.is_new(e));
+
+    let item_scope = ExportScope {
+        since: match (&previous, scope.since) {
+            (Some(c), since) => Some(since.map_or(c.since, |s| s.max(c.since))),
+            (None, since) => since,
+        },
+        ..scope.clone()
+    };
+    let mut writer = match previous {
+        Some(_) => ExportWriter::append(file),
+        None => ExportWriter::create(file, relay, exported_at),
+    }
+    .map_err(CommandError::from)?;
+
+    let mut cursor = previous.clone().unwrap_or_default();
+    for header_event in header_events {
+        let mut reconciled = None;
+        if negentropy {
+            reconciled = missing_items(client, relay, header_event, scope, &written).await?;
+            negentropy = reconciled.is_some();
+        }
+        let mut new_items = match reconciled {
+            Some(missing) => missing,
+            None => fetch_items_for_header_event(client, relay, header_event, &item_scope)
+                .await?
+                .into_iter()
+                .filter(|e| is_new(e) && !written_ids.contains(&e.id))
+                .collect(),
+        };
+        sort_events_desc(&mut new_items);
+        let new_items: Vec<&Event> = new_items.iter().collect();
+        let header_changed =
+            !written_ids.contains(&header_event.id) && scope.keeps(header_event, new_items.len());
+
+        writer
+            .write_changes(header_changed.then_some(header_event), &new_items)
+            .map_err(CommandError::from)?;
+        if header_changed {
+            cursor.observe(header_event);
+        }
+        for item in new_items {
+            cursor.observe(item);
         }
+    }
+    let (headers, items) = writer.finish().map_err(CommandError::from)?;
 
-        let actions = vec![
+    let since = cursor.since;
+    state.set(key, cursor);
+    let method = if negentropy {
+        reconcile::Method::Negentropy
+    } else {
+        reconcile::Method::Paging
+    };
+
+    Ok(json!({
+        "relay": relay,
+        "exported_at": exported_at,
+        "output": file.path,
+        "format": file.format.as_str(),
+        "counts": {
+            "headers": headers,
+            "items": items,
+        },
+        "sync": {
+            "appended": previous.is_some(),
+            "previous_cursor": previous.map(|c| c.since),
+            "cursor": since,
+            "method": method.as_str(),
+        },
+    }))
+}
+
+pub async fn export(
+    relay: String,
+    scope: ExportScope,
+    output: Option<ExportFile>,
+    sync: bool,
+) -> Result<CommandOutput, CommandError> {
+    let client = connect_client(std::slice::from_ref(&relay))
+        .await
+        .map_err(CommandError::from)?;
+
+    let result = async {
+        let header_events = export_headers(&client, &relay, &scope).await?;
+        let exported_at = Timestamp::now().as_secs();
+
+        let mut actions = vec![
             NextAction::new(
                 format!("wokhei count --relay={relay}"),
                 "Get quick relay counts",
@@ -569,16 +1299,64 @@ pub async fn export(relay: String) -> Result<CommandOutput, CommandError> {
             ),
         ];
 
-        Ok(CommandOutput::new(json!({
-            "relay": relay,
-            "exported_at": Timestamp::now().as_secs(),
-            "counts": {
-                "headers": exported_headers.len(),
-                "items": total_items,
-            },
-            "headers": exported_headers,
-        }))
-        .next_actions(actions))
+        let mut result = if let Some(file) = &output {
+            actions.push(NextAction::new(
+                format!("wokhei import --relay=<target-relay> {}", file.path),
+                "Restore this backup onto another relay",
+            ));
+            if sync {
+                let mut state = SyncState::load();
+                let result = sync_to_file(
+                    &client,
+                    &relay,
+                    &header_events,
+                    &scope,
+                    file,
+                    exported_at,
+                    &mut state,
+                )
+                .await?;
+                state.save().map_err(CommandError::from)?;
+                result
+            } else {
+                export_to_file(&client, &relay, &header_events, &scope, file, exported_at).await?
+            }
+        } else {
+            let mut exported_headers = Vec::with_capacity(header_events.len());
+            let mut total_items = 0usize;
+
+            for header_event in &header_events {
+                let items =
+                    fetch_items_for_header_event(&client, &relay, header_event, &scope).await?;
+                if !scope.keeps(header_event, items.len()) {
+                    continue;
+                }
+                total_items = total_items.saturating_add(items.len());
+
+                let item_json: Vec<serde_json::Value> = items.iter().map(event_to_json).collect();
+
+                exported_headers.push(json!({
+                    "header": event_to_json(header_event),
+                    "items_count": item_json.len(),
+                    "items": item_json,
+                }));
+            }
+
+            json!({
+                "relay": relay,
+                "exported_at": exported_at,
+                "counts": {
+                    "headers": exported_headers.len(),
+                    "items": total_items,
+                },
+                "headers": exported_headers,
+            })
+        };
+        if let Some(scope) = scope.to_json() {
+            result["scope"] = scope;
+        }
+
+        Ok(CommandOutput::new(result).next_actions(actions))
     }
     .await;
 
@@ -586,33 +1364,173 @@ pub async fn export(relay: String) -> Result<CommandOutput, CommandError> {
     result
 }
 
-pub async fn inspect(relay: String, event_id_str: String) -> Result<CommandOutput, CommandError> {
+/// Render one list's items as a table (`export-list`).
+pub async fn export_list(
+    relays: Vec<String>,
+    scope: ExportScope,
+    format: TableFormat,
+    output: Option<String>,
+) -> Result<CommandOutput, CommandError> {
+    let client = connect_client(&relays).await.map_err(CommandError::from)?;
+    let relay = relays_arg(&relays);
+
+    let fetched = async {
+        let headers = fetch_merged(&client, &relays, &[&scope.header_filter()?]).await?;
+        let reference = scope.header.as_ref().or(scope.header_coordinate.as_ref());
+        let header_event = headers.sorted_events().into_iter().next().ok_or_else(|| {
+            CommandError::from(AppError::HeaderNotFound {
+                event_id: reference.cloned().unwrap_or_default(),
+            })
+        })?;
+        let (z_ref, _) = z_ref_for_header_event(&header_event)?;
+        let (z_filter, a_filter) = item_filters(&z_ref);
+        let items = fetch_merged(&client, &relays, &[&z_filter, &a_filter]).await?;
+        Ok::<_, CommandError>((header_event, z_ref, items.sorted_events()))
+    }
+    .await;
+    client.disconnect().await;
+    let (header_event, z_ref, items) = fetched?;
+
+    let table = ListTable::new(&header_event, &items);
+    let rendered = table.render(format).map_err(CommandError::from)?;
+
+    let mut result = json!({
+        "header_id": header_event.id.to_hex(),
+        "header_ref": z_ref,
+        "title": table.title,
+        "format": format.as_str(),
+        "columns": table.columns,
+        "rows": table.rows.len(),
+    });
+    if let Some(path) = &output {
+        std::fs::write(path, &rendered).map_err(|e| {
+            CommandError::from(AppError::Io {
+                reason: format!("Failed to write {path}: {e}"),
+            })
+        })?;
+        result["output"] = json!(path);
+    } else {
+        result["rendered"] = json!(rendered);
+    }
+
+    let actions = vec![NextAction::new(
+        format!(
+            "wokhei list-items --relay={relay} {}",
+            header_event.id.to_hex()
+        ),
+        "See the same items as JSON",
+    )];
+    Ok(CommandOutput::new(result).next_actions(actions))
+}
+
+/// `Err(ListInvalid)` naming each item that breaks the header schema, otherwise the report
+/// with unknown-field warnings.
+fn list_report(
+    header_event: &Event,
+    header_ref: &str,
+    items: &[Event],
+) -> Result<serde_json::Value, AppError> {
+    let schema = HeaderSchema::from_tags(header_event.tags.as_slice());
+    let reports = check_list_items(&schema, header_ref, items);
+    let invalid: Vec<&ItemReport> = reports
+        .iter()
+        .filter(|r| !r.violations.is_empty())
+        .collect();
+    if !invalid.is_empty() {
+        let details = invalid
+            .iter()
+            .map(|r| {
+                let rules: Vec<String> = r
+                    .violations
+                    .iter()
+                    .map(|v| format!("{}: {}", v.rule, v.message))
+                    .collect();
+                format!("{} {}", r.event_id.to_hex(), rules.join(", "))
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        return Err(AppError::ListInvalid {
+            invalid: invalid.len(),
+            total: items.len(),
+            details,
+        });
+    }
+    Ok(json!({
+        "header_id": header_event.id.to_hex(),
+        "header_ref": header_ref,
+        "required": schema.required,
+        "recommended": schema.recommended,
+        "total": items.len(),
+        "valid": true,
+        "warnings": reports.iter().map(ItemReport::to_json).collect::<Vec<_>>(),
+    }))
+}
+
+/// `validate-list`: check every item of one list against its header. Any violation fails
+/// the command, so CI can gate on the exit code.
+pub async fn validate_list(
+    relays: Vec<String>,
+    scope: ExportScope,
+    mode: CacheMode,
+) -> Result<CommandOutput, CommandError> {
+    let mut source = EventSource::new(&relays, mode);
+    let relay = relays_arg(&relays);
+
+    let fetched = async {
+        let headers = source.fetch(&scope.header_filter()?).await?;
+        let reference = scope.header.as_ref().or(scope.header_coordinate.as_ref());
+        let header_event = headers.sorted_events().into_iter().next().ok_or_else(|| {
+            CommandError::from(AppError::HeaderNotFound {
+                event_id: reference.cloned().unwrap_or_default(),
+            })
+        })?;
+        let (z_ref, _) = z_ref_for_header_event(&header_event)?;
+        let items = source.fetch_items(&z_ref).await?;
+        Ok::<_, CommandError>((header_event, z_ref, items.sorted_events()))
+    }
+    .await;
+    let origin = source.source();
+    let warning = source.close().await;
+    let (header_event, z_ref, items) = fetched?;
+
+    let header_id = header_event.id.to_hex();
+    let mut result = list_report(&header_event, &z_ref, &items).map_err(|e| {
+        CommandError::from(e).next_actions(vec![NextAction::new(
+            format!("wokhei list-items --relay={relay} {header_id}"),
+            "See the items and their tags",
+        )])
+    })?;
+    result["source"] = json!(origin);
+    note_cache_warning(&mut result, warning);
+
+    let actions = vec![NextAction::new(
+        format!("wokhei export-list --relay={relay} {header_id}"),
+        "Render the validated list as a table",
+    )];
+    Ok(CommandOutput::new(result).next_actions(actions))
+}
+
+pub async fn inspect(
+    relays: Vec<String>,
+    event_id_str: String,
+    mode: CacheMode,
+) -> Result<CommandOutput, CommandError> {
     let event_id = EventId::parse(&event_id_str).map_err(|_| {
         CommandError::from(AppError::InvalidEventId {
             id: event_id_str.clone(),
         })
     })?;
 
-    let client = connect_client(&relay).await.map_err(CommandError::from)?;
+    let mut source = EventSource::new(&relays, mode);
+    let relay = relays_arg(&relays);
 
     let filter = Filter::new().id(event_id).limit(1);
-    let events = client
-        .fetch_events(filter, QUERY_TIMEOUT)
-        .await
-        .map_err(|_| CommandError::from(AppError::RelayUnreachable { url: relay.clone() }));
+    let merged = source.fetch(&filter).await;
+    let origin = source.source();
+    let warning = source.close().await;
+    let merged = merged?;
 
-    let events = match events {
-        Ok(ev) => {
-            client.disconnect().await;
-            ev
-        }
-        Err(e) => {
-            client.disconnect().await;
-            return Err(e);
-        }
-    };
-
-    let event = events.into_iter().next().ok_or_else(|| {
+    let event = merged.events.get(&event_id).ok_or_else(|| {
         CommandError::from(AppError::EventNotFound {
             event_id: event_id_str.clone(),
         })
@@ -622,7 +1540,9 @@ pub async fn inspect(relay: String, event_id_str: String) -> Result<CommandOutpu
         )])
     })?;
 
-    let ev_json = event_to_json(&event);
+    let mut ev_json = merged.event_json(event);
+    ev_json["source"] = json!(origin);
+    note_cache_warning(&mut ev_json, warning);
     let kind = event.kind.as_u16();
 
     let mut actions = vec![];
@@ -761,6 +1681,117 @@ mod tests {
         assert_eq!(tags_arr[1][1], "39998:deadbeef:my-list");
     }
 
+    fn make_addressable(keys: &Keys, d: &str, created_at: u64) -> Event {
+        EventBuilder::new(Kind::Custom(39999), "")
+            .tags(vec![Tag::identifier(d)])
+            .custom_created_at(Timestamp::from_secs(created_at))
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    #[test]
+    fn merged_events_dedupes_by_id_and_records_relays() {
+        let event = make_event(Kind::Custom(9999), "", vec![]);
+        let mut merged = MergedEvents::default();
+        merged.insert("ws://a:1", event.clone());
+        merged.insert("ws://b:2", event.clone());
+        merged.insert("ws://b:2", event.clone());
+
+        assert_eq!(merged.events.len(), 1);
+        let j = merged.event_json(&event);
+        assert_eq!(j["relays"], json!(["ws://a:1", "ws://b:2"]));
+    }
+
+    #[test]
+    fn merged_events_keeps_newest_addressable_version() {
+        let keys = Keys::generate();
+        let old = make_addressable(&keys, "item", 100);
+        let new = make_addressable(&keys, "item", 200);
+        let mut merged = MergedEvents::default();
+        merged.insert("ws://a:1", new.clone());
+        merged.insert("ws://b:2", old.clone());
+
+        assert_eq!(merged.events.len(), 1);
+        assert!(merged.events.contains_key(&new.id));
+        assert_eq!(merged.seen_on[&new.id], vec!["ws://a:1"]);
+    }
+
+    #[test]
+    fn merged_events_newer_addressable_replaces_older() {
+        let keys = Keys::generate();
+        let old = make_addressable(&keys, "item", 100);
+        let new = make_addressable(&keys, "item", 200);
+        let mut merged = MergedEvents::default();
+        merged.insert("ws://a:1", old.clone());
+        merged.insert("ws://b:2", new.clone());
+
+        assert_eq!(merged.sorted_events(), vec![new.clone()]);
+        assert!(!merged.seen_on.contains_key(&old.id));
+    }
+
+    #[test]
+    fn merged_events_distinct_d_tags_are_kept() {
+        let keys = Keys::generate();
+        let mut merged = MergedEvents::default();
+        merged.insert("ws://a:1", make_addressable(&keys, "one", 100));
+        merged.insert("ws://a:1", make_addressable(&keys, "two", 100));
+        assert_eq!(merged.events.len(), 2);
+    }
+
+    #[test]
+    fn merged_events_skip_only_unreachable_relays() {
+        let mut merged = MergedEvents::default();
+        let down = CommandError::from(AppError::RelayUnreachable {
+            url: "ws://a:1".into(),
+        });
+        merged.skip_unreachable("ws://a:1", down).unwrap();
+        assert_eq!(merged.unreachable, vec!["ws://a:1"]);
+
+        let capped = CommandError::from(AppError::RelayLimitCapped {
+            url: "ws://b:1".into(),
+            created_at: 1_000,
+            returned: 500,
+        });
+        let err = merged.skip_unreachable("ws://b:1", capped).unwrap_err();
+        assert_eq!(err.code, "RELAY_LIMIT_CAPPED");
+        assert_eq!(merged.unreachable, vec!["ws://a:1"]);
+    }
+
+    #[test]
+    fn page_actions_first_page_only_has_next() {
+        let actions = page_actions(25, 0, 10, 10, |o| format!("page {o}"));
+        let cmds: Vec<_> = actions.iter().map(|a| a.command.as_str()).collect();
+        assert_eq!(cmds, vec!["page 10"]);
+    }
+
+    #[test]
+    fn page_actions_middle_page_has_prev_and_next() {
+        let actions = page_actions(25, 10, 10, 10, |o| format!("page {o}"));
+        let cmds: Vec<_> = actions.iter().map(|a| a.command.as_str()).collect();
+        assert_eq!(cmds, vec!["page 0", "page 20"]);
+    }
+
+    #[test]
+    fn page_actions_past_end_jumps_to_last_page() {
+        let actions = page_actions(25, 40, 10, 0, |o| format!("page {o}"));
+        let cmds: Vec<_> = actions.iter().map(|a| a.command.as_str()).collect();
+        assert_eq!(cmds, vec!["page 30", "page 20"]);
+    }
+
+    #[test]
+    fn item_query_command_keeps_header_reference_and_cache_mode() {
+        let id = "ab".repeat(32);
+        assert_eq!(
+            item_query_command("ws://a", Some(&id), None, 10, 5, CacheMode::Offline),
+            format!("wokhei list-items --relay=ws://a --offline {id} --offset=10 --limit=5")
+        );
+        let coordinate = "39998:pk:d".to_string();
+        assert_eq!(
+            item_query_command("ws://a", None, Some(&coordinate), 0, 5, CacheMode::Refresh),
+            "wokhei list-items --relay=ws://a --header-coordinate=39998:pk:d --offset=0 --limit=5"
+        );
+    }
+
     #[test]
     fn paginate_returns_expected_window() {
         let values = vec![1, 2, 3, 4, 5];
@@ -793,4 +1824,449 @@ mod tests {
         assert_eq!(rows[1]["event_id"], "a");
         assert_eq!(rows[2]["event_id"], "b");
     }
+
+    #[tokio::test]
+    async fn event_source_caches_relay_answers_for_offline_reads() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let relays = vec![relay.url().await.to_string()];
+        let header = make_event(Kind::Custom(9998), "", vec![Tag::hashtag("jazz")]);
+        let client = connect_client(&relays).await.unwrap();
+        client.send_event(&header).await.unwrap();
+        client.disconnect().await;
+
+        let jazz = build_header_filter(None, Some(&"jazz".to_string())).unwrap();
+        let mut online =
+            EventSource::with_cache(&relays, CacheMode::Refresh, EventCache::default());
+        assert_eq!(online.fetch(&jazz).await.unwrap().events.len(), 1);
+        assert_eq!(online.source(), "relays");
+        let cache = std::mem::take(&mut online.cache);
+        online.close().await;
+
+        let mut offline = EventSource::with_cache(&relays, CacheMode::Offline, cache);
+        let merged = offline.fetch(&jazz).await.unwrap();
+        assert_eq!(merged.seen_on[&header.id], relays);
+        let rock = build_header_filter(None, Some(&"rock".to_string())).unwrap();
+        assert!(offline.fetch(&rock).await.unwrap().events.is_empty());
+        assert!(offline.client.is_none());
+        assert_eq!(offline.source(), "cache");
+
+        let cache = std::mem::take(&mut offline.cache);
+        let mut cache_first = EventSource::with_cache(&relays, CacheMode::CacheFirst, cache);
+        assert!(cache_first.fetch(&rock).await.unwrap().events.is_empty());
+        assert!(cache_first.client.is_some());
+        cache_first.close().await;
+    }
+
+    #[tokio::test]
+    async fn relays_that_never_connect_are_reported_unreachable() {
+        let header = make_event(Kind::Custom(9998), "", vec![Tag::hashtag("jazz")]);
+        let (_relay, live) = relay_with(std::slice::from_ref(&header)).await;
+        let dead = "ws://127.0.0.1:1".to_string();
+        let relays = vec![live, dead.clone()];
+        let jazz = build_header_filter(None, Some(&"jazz".to_string())).unwrap();
+
+        let mut source =
+            EventSource::with_cache(&relays, CacheMode::Refresh, EventCache::default());
+        let merged = source.fetch(&jazz).await.unwrap();
+        assert_eq!(merged.events.len(), 1);
+        assert_eq!(merged.unreachable, vec![dead.clone()]);
+        source.close().await;
+
+        let client = connect_client(&relays).await.unwrap();
+        let merged = fetch_merged(&client, &relays, &[&jazz]).await.unwrap();
+        assert_eq!(merged.unreachable, vec![dead.clone()]);
+        client.disconnect().await;
+
+        let err = connect_client(std::slice::from_ref(&dead))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "RELAY_UNREACHABLE");
+    }
+
+    #[tokio::test]
+    async fn unsaved_cache_is_reported_as_a_warning() {
+        let header = make_event(Kind::Custom(9998), "", vec![Tag::hashtag("jazz")]);
+        let (_relay, url) = relay_with(std::slice::from_ref(&header)).await;
+        let relays = vec![url];
+        let jazz = build_header_filter(None, Some(&"jazz".to_string())).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(crate::keys::keys_dir_from(dir.path()), "not a directory").unwrap();
+
+        let cache = EventCache::load_from(dir.path());
+        let mut source = EventSource::with_cache(&relays, CacheMode::Refresh, cache);
+        source.fetch(&jazz).await.unwrap();
+        let warning = source.close().await;
+        assert!(
+            warning
+                .as_deref()
+                .is_some_and(|w| w.contains("cache.jsonl"))
+        );
+
+        let mut result = json!({ "total": 1 });
+        note_cache_warning(&mut result, warning);
+        assert!(result["cache_warning"].is_string());
+    }
+
+    #[tokio::test]
+    async fn event_source_pages_relays_known_to_lack_negentropy() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let relays = vec![relay.url().await.to_string()];
+        let (kept, gone) = (
+            make_event(Kind::Custom(9998), "", vec![Tag::hashtag("jazz")]),
+            make_event(Kind::Custom(9998), "", vec![Tag::hashtag("jazz")]),
+        );
+        let client = connect_client(&relays).await.unwrap();
+        client.send_event(&kept).await.unwrap();
+        client.disconnect().await;
+        let jazz = build_header_filter(None, Some(&"jazz".to_string())).unwrap();
+        let stale = || {
+            let mut cache = EventCache::default();
+            cache.record(&relays[0], &gone);
+            cache
+        };
+
+        let mut paging = EventSource::with_cache(&relays, CacheMode::Refresh, stale());
+        paging.no_negentropy.insert(relays[0].clone());
+        let merged = paging.fetch(&jazz).await.unwrap();
+        assert_eq!(merged.events.keys().collect::<Vec<_>>(), vec![&kept.id]);
+        assert_eq!(paging.cache.matching(&relays, &jazz).len(), 2);
+        paging.close().await;
+
+        let mut reconciling = EventSource::with_cache(&relays, CacheMode::Refresh, stale());
+        reconciling.fetch(&jazz).await.unwrap();
+        assert_eq!(reconciling.cache.matching(&relays, &jazz).len(), 1);
+        assert!(reconciling.no_negentropy.is_empty());
+        reconciling.close().await;
+    }
+
+    /// A local relay preloaded with `events`, without the mock relay's write rate limit.
+    async fn relay_with(events: &[Event]) -> (nostr_relay_builder::LocalRelay, String) {
+        relay_built(events, nostr_relay_builder::RelayBuilder::default()).await
+    }
+
+    async fn relay_built(
+        events: &[Event],
+        builder: nostr_relay_builder::RelayBuilder,
+    ) -> (nostr_relay_builder::LocalRelay, String) {
+        let database = MemoryDatabase::with_opts(MemoryDatabaseOptions {
+            events: true,
+            max_events: None,
+        });
+        for event in events {
+            database.save_event(event).await.unwrap();
+        }
+        let relay = nostr_relay_builder::LocalRelay::new(builder.database(database));
+        relay.run().await.unwrap();
+        let url = relay.url().await.to_string();
+        (relay, url)
+    }
+
+    /// `n` notes by `keys`, all created at `secs`.
+    fn notes_at(keys: &Keys, secs: u64, n: usize) -> Vec<Event> {
+        (0..n)
+            .map(|i| {
+                EventBuilder::text_note(format!("{secs}-{i}"))
+                    .custom_created_at(Timestamp::from_secs(secs))
+                    .sign_with_keys(keys)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn paging_keeps_every_event_sharing_a_boundary_second() {
+        let keys = Keys::generate();
+        let at = |secs: u64, n: usize| notes_at(&keys, secs, n);
+        let events = [at(1_100, 5), at(1_000, 25), at(900, 5)].concat();
+        let (_relay, url) = relay_with(&events).await;
+        let client = connect_client(std::slice::from_ref(&url)).await.unwrap();
+
+        let filter = Filter::new().author(keys.public_key());
+        let all = fetch_pages(&client, &url, &filter, 10, true).await.unwrap();
+        assert_eq!(all.len(), 35);
+        assert_eq!(all.iter().map(|e| e.id).collect::<HashSet<_>>().len(), 35);
+
+        let second = Timestamp::from_secs(1_000);
+        let crowded = second_by_limit(&client, &url, &filter.since(second).until(second), 10)
+            .await
+            .unwrap();
+        assert_eq!(crowded.len(), 25);
+        client.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn crowded_second_past_a_relay_limit_cap_is_an_error() {
+        let keys = Keys::generate();
+        let events = [
+            notes_at(&keys, 1_000, 25),
+            notes_at(&keys, 900, 10),
+            notes_at(&keys, 800, 5),
+        ]
+        .concat();
+        let builder = nostr_relay_builder::RelayBuilder::default().max_filter_limit(10);
+        let (_relay, url) = relay_built(&events, builder).await;
+        let client = connect_client(std::slice::from_ref(&url)).await.unwrap();
+        let filter = Filter::new().author(keys.public_key());
+        let second = |secs: u64| {
+            let at = Timestamp::from_secs(secs);
+            filter.clone().since(at).until(at)
+        };
+
+        let err = second_by_limit(&client, &url, &second(1_000), 10)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, "RELAY_LIMIT_CAPPED");
+        let exactly_capped = second_by_limit(&client, &url, &second(900), 10)
+            .await
+            .unwrap();
+        assert_eq!(exactly_capped.len(), 10);
+        let short = second_by_limit(&client, &url, &second(800), 10)
+            .await
+            .unwrap();
+        assert_eq!(short.len(), 5);
+        client.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn list_items_fail_when_one_relay_caps_a_crowded_second() {
+        let keys = Keys::generate();
+        let parent = make_event(Kind::Custom(9998), "", vec![]).id.to_hex();
+        let items: Vec<Event> = (0..FETCH_PAGE_SIZE + 100)
+            .map(|i| {
+                EventBuilder::new(Kind::Custom(9999), format!("item-{i}"))
+                    .tag(Tag::custom(TagKind::custom("z"), [parent.as_str()]))
+                    .custom_created_at(Timestamp::from_secs(1_000))
+                    .sign_with_keys(&keys)
+                    .unwrap()
+            })
+            .collect();
+        let (_full, full_url) = relay_with(&items).await;
+        let capped = nostr_relay_builder::RelayBuilder::default().max_filter_limit(FETCH_PAGE_SIZE);
+        let (_capped, capped_url) = relay_built(&items, capped).await;
+        let relays = vec![full_url, capped_url.clone()];
+
+        let mut source =
+            EventSource::with_cache(&relays, CacheMode::Refresh, EventCache::default());
+        source.no_negentropy.insert(capped_url);
+        let Err(err) = source.fetch_items(&parent).await else {
+            panic!("a capped relay must not yield a short item list");
+        };
+        assert_eq!(err.code, "RELAY_LIMIT_CAPPED");
+        source.close().await;
+    }
+
+    #[tokio::test]
+    async fn header_items_include_a_only_references() {
+        let header = make_event(Kind::Custom(9998), "", vec![]);
+        let parent = header.id.to_hex();
+        let tag = |name: &str| Tag::custom(TagKind::custom(name), [parent.as_str()]);
+        let by_z = make_event(Kind::Custom(9999), "", vec![tag("z")]);
+        let by_a = make_event(Kind::Custom(9999), "", vec![tag("a")]);
+        let by_both = make_event(Kind::Custom(9999), "", vec![tag("z"), tag("a")]);
+        let (_relay, url) =
+            relay_with(&[header.clone(), by_z.clone(), by_a.clone(), by_both]).await;
+        let client = connect_client(std::slice::from_ref(&url)).await.unwrap();
+        let scope = ExportScope::default();
+
+        let items = fetch_items_for_header_event(&client, &url, &header, &scope)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 3);
+
+        let missing = missing_items(&client, &url, &header, &scope, &[header.clone(), by_z])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(missing.len(), 2);
+        assert!(missing.iter().any(|e| e.id == by_a.id));
+        client.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn export_list_renders_a_only_items_too() {
+        let header = make_event(
+            Kind::Custom(9998),
+            "",
+            vec![Tag::custom(TagKind::custom("names"), ["album", "albums"])],
+        );
+        let parent = header.id.to_hex();
+        let item = |tag: &str, resource: &str| {
+            make_event(
+                Kind::Custom(9999),
+                "",
+                vec![
+                    Tag::custom(TagKind::custom(tag), [parent.as_str()]),
+                    Tag::custom(TagKind::custom("r"), [resource]),
+                ],
+            )
+        };
+        let events = [
+            header.clone(),
+            item("z", "https://z"),
+            item("a", "https://a"),
+        ];
+        let (_relay, url) = relay_with(&events).await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("list.csv").display().to_string();
+
+        let scope = ExportScope {
+            header: Some(parent.clone()),
+            ..ExportScope::default()
+        };
+        export_list(vec![url], scope, TableFormat::Csv, Some(path.clone()))
+            .await
+            .unwrap();
+        let rendered = std::fs::read_to_string(&path).unwrap();
+        assert!(rendered.contains("https://z"));
+        assert!(rendered.contains("https://a"));
+    }
+
+    #[tokio::test]
+    async fn sync_appends_only_new_events() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let url = relay.url().await.to_string();
+        let client = connect_client(std::slice::from_ref(&url)).await.unwrap();
+        let keys = Keys::generate();
+        let header = EventBuilder::new(Kind::Custom(39998), "")
+            .tags([
+                Tag::custom(TagKind::custom("names"), ["a", "as"]),
+                Tag::identifier("list"),
+            ])
+            .custom_created_at(Timestamp::from_secs(1_000))
+            .sign_with_keys(&keys)
+            .unwrap();
+        let z = format!("39998:{}:list", keys.public_key().to_hex());
+        let item = |secs: u64, r: &str| {
+            EventBuilder::new(Kind::Custom(9999), "")
+                .tags([
+                    Tag::custom(TagKind::custom("z"), [z.as_str()]),
+                    Tag::custom(TagKind::custom("r"), [r]),
+                ])
+                .custom_created_at(Timestamp::from_secs(secs))
+                .sign_with_keys(&keys)
+                .unwrap()
+        };
+        client.send_event(&header).await.unwrap();
+        client.send_event(&item(2_000, "https://a")).await.unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = ExportFile {
+            path: dir.path().join("backup.jsonl").display().to_string(),
+            format: crate::export::ExportFormat::Jsonl,
+        };
+        let scope = ExportScope::default();
+        let mut state = SyncState::default();
+        let headers = [header];
+        let first = sync_to_file(&client, &url, &headers, &scope, &file, 0, &mut state)
+            .await
+            .unwrap();
+        assert_eq!(first["counts"], json!({ "headers": 1, "items": 1 }));
+        assert_eq!(first["sync"]["cursor"], 2_000);
+
+        client.send_event(&item(2_000, "https://b")).await.unwrap();
+        client.send_event(&item(3_000, "https://c")).await.unwrap();
+        client
+            .send_event(&item(1_500, "https://late"))
+            .await
+            .unwrap();
+        let second = sync_to_file(&client, &url, &headers, &scope, &file, 0, &mut state)
+            .await
+            .unwrap();
+        assert_eq!(second["sync"]["appended"], true);
+        assert_eq!(second["sync"]["method"], "negentropy");
+        assert_eq!(second["counts"], json!({ "headers": 0, "items": 3 }));
+
+        let third = sync_to_file(&client, &url, &headers, &scope, &file, 0, &mut state)
+            .await
+            .unwrap();
+        assert_eq!(third["counts"], json!({ "headers": 0, "items": 0 }));
+        client.disconnect().await;
+
+        let lines = std::fs::read_to_string(&file.path).unwrap();
+        assert_eq!(lines.lines().count(), 5);
+    }
+
+    #[test]
+    fn list_report_fails_on_violations_and_warns_on_unknown_fields() {
+        let custom = |name: &str, value: &str| Tag::custom(TagKind::custom(name), [value]);
+        let header = make_event(
+            Kind::Custom(9998),
+            "",
+            vec![
+                Tag::custom(TagKind::custom("names"), ["book", "books"]),
+                custom("required", "title"),
+            ],
+        );
+        let z_ref = header.id.to_hex();
+        let item = |resource: &str, field: Tag| {
+            make_event(
+                Kind::Custom(9999),
+                "",
+                vec![custom("z", &z_ref), custom("r", resource), field],
+            )
+        };
+        let titled = item("https://a", custom("title", "A"));
+        let untitled = item("https://b", custom("year", "1965"));
+
+        let err = list_report(&header, &z_ref, &[titled.clone(), untitled.clone()]).unwrap_err();
+        assert_eq!(err.code(), "LIST_INVALID");
+        let message = err.to_string();
+        assert!(message.starts_with("1 of 2 list items"));
+        assert!(message.contains(&untitled.id.to_hex()));
+        assert!(message.contains("MISSING_REQUIRED_FIELDS"));
+
+        let extra = item("https://c", custom("title", "C"));
+        let mut tags = extra.tags.to_vec();
+        tags.push(custom("year", "1965"));
+        let extra = make_event(Kind::Custom(9999), "", tags);
+        let report = list_report(&header, &z_ref, &[titled, extra.clone()]).unwrap();
+        assert_eq!(report["valid"], true);
+        assert_eq!(report["total"], 2);
+        assert_eq!(report["required"], json!(["title"]));
+        assert_eq!(report["warnings"][0]["event_id"], extra.id.to_hex());
+        assert_eq!(report["warnings"][0]["unknown_fields"], json!(["year"]));
+    }
+
+    #[tokio::test]
+    async fn fetch_current_addressable_finds_newest_version() {
+        let keys = Keys::generate();
+        let version = |description: &str, secs: u64| {
+            EventBuilder::new(Kind::Custom(39998), "")
+                .tags([
+                    Tag::identifier("books"),
+                    Tag::custom(TagKind::custom("description"), [description]),
+                ])
+                .custom_created_at(Timestamp::from_secs(secs))
+                .sign_with_keys(&keys)
+                .unwrap()
+        };
+        let (old, new) = (version("old", 1_000), version("new", 2_000));
+        let note = EventBuilder::text_note("x").sign_with_keys(&keys).unwrap();
+        let (_relay, url) = relay_with(&[old.clone(), new.clone(), note.clone()]).await;
+        let client = connect_client(std::slice::from_ref(&url)).await.unwrap();
+        let header = Kind::Custom(39998);
+
+        let coordinate = format!("39998:{}:books", keys.public_key().to_hex());
+        for target in [coordinate, new.id.to_hex()] {
+            let current = fetch_current_addressable(&client, &url, &target, header)
+                .await
+                .unwrap();
+            assert_eq!(current.id, new.id);
+        }
+
+        let err = fetch_current_addressable(&client, &url, &note.id.to_hex(), header)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, "INVALID_ARGS");
+        let missing = format!("39998:{}:other", keys.public_key().to_hex());
+        let err = fetch_current_addressable(&client, &url, &missing, header)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, "HEADER_NOT_FOUND");
+        assert_eq!(
+            replacement_created_at(&new),
+            Timestamp::now().max(new.created_at + 1u64)
+        );
+        client.disconnect().await;
+    }
 }
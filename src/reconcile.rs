@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use agcli::CommandError;
+use nostr_sdk::prelude::*;
+
+use crate::error::AppError;
+use crate::import::LOOKUP_CHUNK;
+use crate::query::QUERY_TIMEOUT;
+
+/// How long to wait for a relay to answer `NEG-OPEN` before assuming it doesn't speak NIP-77.
+const NEGENTROPY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How a relay's events were compared with what we already hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// NIP-77 set reconciliation: only ids are exchanged, then missing events are fetched.
+    Negentropy,
+    /// The relay doesn't support negentropy, so events were paged through or looked up.
+    Paging,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Negentropy => "negentropy",
+            Self::Paging => "paging",
+        }
+    }
+}
+
+/// Ids on either side of a reconciliation.
+#[derive(Debug, Default)]
+pub struct IdDiff {
+    /// On the relay but not in `known`.
+    pub remote: HashSet<EventId>,
+    /// In `known` but not on the relay.
+    pub local: HashSet<EventId>,
+}
+
+/// Compare `known` with what `relay` holds for `filter` without transferring events.
+/// `None` when the relay doesn't speak NIP-77 (or `filter` has a limit, which negentropy
+/// can't express), so the caller falls back to paging.
+pub async fn negentropy_diff(
+    client: &Client,
+    relay: &str,
+    filter: &Filter,
+    known: &[&Event],
+) -> Option<IdDiff> {
+    if filter.limit.is_some() {
+        return None;
+    }
+    let relay = client.relay(relay).await.ok()?;
+    let items = known.iter().map(|e| (e.id, e.created_at)).collect();
+    let opts = SyncOptions::new()
+        .initial_timeout(NEGENTROPY_TIMEOUT)
+        .dry_run();
+    let reconciliation = relay
+        .sync_with_items(filter.clone(), items, &opts)
+        .await
+        .ok()?;
+    Some(IdDiff {
+        remote: reconciliation.remote,
+        local: reconciliation.local,
+    })
+}
+
+/// Download the events with `ids` from `relay`.
+pub async fn fetch_ids(
+    client: &Client,
+    relay: &str,
+    ids: &HashSet<EventId>,
+) -> Result<Vec<Event>, CommandError> {
+    let ids: Vec<EventId> = ids.iter().copied().collect();
+    let mut events = Vec::with_capacity(ids.len());
+    for chunk in ids.chunks(LOOKUP_CHUNK) {
+        let filter = Filter::new().ids(chunk.iter().copied());
+        let batch = client
+            .fetch_events_from([relay], filter, QUERY_TIMEOUT)
+            .await
+            .map_err(|_| {
+                CommandError::from(AppError::RelayUnreachable {
+                    url: relay.to_string(),
+                })
+            })?;
+        events.extend(batch);
+    }
+    Ok(events)
+}
+
+/// Events `relay` holds for `filter` that `known` lacks, downloading only those.
+/// `None` when negentropy isn't available.
+pub async fn missing_events(
+    client: &Client,
+    relay: &str,
+    filter: &Filter,
+    known: &[&Event],
+) -> Result<Option<Vec<Event>>, CommandError> {
+    match negentropy_diff(client, relay, filter, known).await {
+        Some(diff) => Ok(Some(fetch_ids(client, relay, &diff.remote).await?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::connect_client;
+
+    fn note(keys: &Keys, text: &str) -> Event {
+        EventBuilder::text_note(text).sign_with_keys(keys).unwrap()
+    }
+
+    #[tokio::test]
+    async fn negentropy_transfers_only_missing_events() {
+        let relay = nostr_relay_builder::MockRelay::run().await.unwrap();
+        let url = relay.url().await.to_string();
+        let client = connect_client(std::slice::from_ref(&url)).await.unwrap();
+        let keys = Keys::generate();
+        let (shared, remote_only, local_only) =
+            (note(&keys, "a"), note(&keys, "b"), note(&keys, "c"));
+        client.send_event(&shared).await.unwrap();
+        client.send_event(&remote_only).await.unwrap();
+
+        let filter = Filter::new().author(keys.public_key());
+        let known = [&shared, &local_only];
+        let diff = negentropy_diff(&client, &url, &filter, &known)
+            .await
+            .unwrap();
+        assert_eq!(diff.remote, HashSet::from([remote_only.id]));
+        assert_eq!(diff.local, HashSet::from([local_only.id]));
+
+        let missing = missing_events(&client, &url, &filter, &known)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(missing, vec![remote_only]);
+
+        assert!(
+            negentropy_diff(&client, &url, &filter.limit(1), &known)
+                .await
+                .is_none()
+        );
+        client.disconnect().await;
+    }
+}
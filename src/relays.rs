@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use agcli::CommandError;
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::error::AppError;
+
+pub const DEFAULT_RELAY: &str = "ws://localhost:7777";
+
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ---------------------------------------------------------------------------
+// Relay set resolution
+// ---------------------------------------------------------------------------
+
+fn relays_path_from(base: &Path) -> PathBuf {
+    crate::keys::keys_dir_from(base).join("relays")
+}
+
+/// Parse a relays config file: one URL per line, blank lines and `#` comments ignored.
+fn parse_relays_file(contents: &str) -> Vec<String> {
+    split_relays(
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#')),
+    )
+}
+
+fn configured_relays_from(base: &Path) -> Vec<String> {
+    fs::read_to_string(relays_path_from(base))
+        .map(|contents| parse_relays_file(&contents))
+        .unwrap_or_default()
+}
+
+/// Default relay set from `~/.wokhei/relays`. Missing file = empty set.
+pub fn configured_relays() -> Vec<String> {
+    configured_relays_from(&crate::keys::home_base())
+}
+
+/// Split comma-separated relay values, dropping empties and duplicates while keeping order.
+pub fn split_relays<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut relays: Vec<String> = Vec::new();
+    for url in values.into_iter().flat_map(|v| v.split(',')) {
+        let url = url.trim();
+        if !url.is_empty() && !relays.iter().any(|r| r == url) {
+            relays.push(url.to_string());
+        }
+    }
+    relays
+}
+
+/// Render a relay set back into a `--relay=` value for `next_actions`.
+pub fn relays_arg(relays: &[String]) -> String {
+    relays.join(",")
+}
+
+// ---------------------------------------------------------------------------
+// Per-relay write results
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayStatus {
+    Accepted,
+    Rejected,
+    Unreachable,
+}
+
+impl RelayStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+            Self::Unreachable => "unreachable",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RelayReport {
+    pub url: String,
+    pub status: RelayStatus,
+    pub reason: Option<String>,
+}
+
+impl RelayReport {
+    fn new(url: &str, status: RelayStatus, reason: Option<String>) -> Self {
+        Self {
+            url: url.to_string(),
+            status,
+            reason,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut obj = json!({
+            "relay": self.url,
+            "status": self.status.as_str(),
+        });
+        if let Some(ref reason) = self.reason {
+            obj["reason"] = json!(reason);
+        }
+        obj
+    }
+}
+
+pub fn reports_json(reports: &[RelayReport]) -> Vec<serde_json::Value> {
+    reports.iter().map(RelayReport::to_json).collect()
+}
+
+/// One-line summary of every non-accepted relay, used when nothing accepted the event.
+fn failure_summary(reports: &[RelayReport]) -> String {
+    reports
+        .iter()
+        .filter(|r| r.status != RelayStatus::Accepted)
+        .map(|r| {
+            let reason = r.reason.as_deref().unwrap_or(r.status.as_str());
+            format!("{}: {reason}", r.url)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Classify every relay in the set against the pool's send output, keeping the input order.
+fn classify_send(
+    relays: &[String],
+    targets: &[(String, RelayUrl)],
+    unreachable: &[RelayReport],
+    success: &HashSet<RelayUrl>,
+    failed: &HashMap<RelayUrl, String>,
+) -> Vec<RelayReport> {
+    let mut reports = Vec::with_capacity(relays.len());
+    for url in relays {
+        if let Some(report) = unreachable.iter().find(|r| &r.url == url) {
+            reports.push(report.clone());
+            continue;
+        }
+        let Some((_, relay_url)) = targets.iter().find(|(target, _)| target == url) else {
+            continue;
+        };
+        if success.contains(relay_url) {
+            reports.push(RelayReport::new(url, RelayStatus::Accepted, None));
+        } else {
+            let reason = failed
+                .get(relay_url)
+                .cloned()
+                .unwrap_or_else(|| "no response from relay".to_string());
+            reports.push(RelayReport::new(url, RelayStatus::Rejected, Some(reason)));
+        }
+    }
+    reports
+}
+
+// ---------------------------------------------------------------------------
+// Write session: one client fanned out to every relay in the set
+// ---------------------------------------------------------------------------
+
+pub struct WriteSession {
+    client: Client,
+    relays: Vec<String>,
+    targets: Vec<(String, RelayUrl)>,
+    unreachable: Vec<RelayReport>,
+}
+
+impl WriteSession {
+    /// Connect to every relay in the set. Fails only if none of them is reachable.
+    pub async fn connect(relays: &[String]) -> Result<Self, CommandError> {
+        let client = Client::default();
+        let mut targets = Vec::with_capacity(relays.len());
+        let mut unreachable = Vec::new();
+
+        for url in relays {
+            let parsed = match RelayUrl::parse(url) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    unreachable.push(RelayReport::new(
+                        url,
+                        RelayStatus::Unreachable,
+                        Some(e.to_string()),
+                    ));
+                    continue;
+                }
+            };
+            if let Err(e) = client.add_relay(parsed.clone()).await {
+                unreachable.push(RelayReport::new(
+                    url,
+                    RelayStatus::Unreachable,
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+            targets.push((url.clone(), parsed));
+        }
+
+        let connected = client.try_connect(CONNECT_TIMEOUT).await;
+        for (url, relay_url) in &targets {
+            if let Some(reason) = connected.failed.get(relay_url) {
+                unreachable.push(RelayReport::new(
+                    url,
+                    RelayStatus::Unreachable,
+                    Some(reason.clone()),
+                ));
+            }
+        }
+
+        if unreachable.len() >= relays.len() {
+            client.disconnect().await;
+            return Err(CommandError::from(AppError::RelayUnreachable {
+                url: relays_arg(relays),
+            }));
+        }
+
+        Ok(Self {
+            client,
+            relays: relays.to_vec(),
+            targets,
+            unreachable,
+        })
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    fn connected_urls(&self) -> Vec<RelayUrl> {
+        self.targets
+            .iter()
+            .filter(|(url, _)| !self.unreachable.iter().any(|r| &r.url == url))
+            .map(|(_, relay_url)| relay_url.clone())
+            .collect()
+    }
+
+    /// Broadcast a signed event to every reachable relay.
+    ///
+    /// Returns one report per relay in the set. Errors only when no relay accepted it.
+    pub async fn send(&self, event: &Event) -> Result<Vec<RelayReport>, CommandError> {
+        let (success, failed) = match self
+            .client
+            .send_event_to(self.connected_urls(), event)
+            .await
+        {
+            Ok(output) => (output.success, output.failed),
+            Err(e) => {
+                let failed = self
+                    .connected_urls()
+                    .into_iter()
+                    .map(|relay_url| (relay_url, e.to_string()))
+                    .collect();
+                (HashSet::new(), failed)
+            }
+        };
+
+        let reports = classify_send(
+            &self.relays,
+            &self.targets,
+            &self.unreachable,
+            &success,
+            &failed,
+        );
+
+        if reports.iter().any(|r| r.status == RelayStatus::Accepted) {
+            Ok(reports)
+        } else {
+            Err(CommandError::from(AppError::RelayRejected {
+                reason: failure_summary(&reports),
+            }))
+        }
+    }
+
+    pub async fn disconnect(&self) {
+        self.client.disconnect().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_relays_handles_commas_and_duplicates() {
+        let relays = split_relays(["ws://a:1, ws://b:2", "ws://a:1", ""]);
+        assert_eq!(relays, vec!["ws://a:1", "ws://b:2"]);
+    }
+
+    #[test]
+    fn parse_relays_file_skips_comments_and_blank_lines() {
+        let contents = "# prod\nwss://dcosl.brainstorm.world\n\n  ws://localhost:7777  \n";
+        assert_eq!(
+            parse_relays_file(contents),
+            vec!["wss://dcosl.brainstorm.world", "ws://localhost:7777"]
+        );
+    }
+
+    #[test]
+    fn configured_relays_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(configured_relays_from(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn configured_relays_reads_wokhei_relays_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".wokhei")).unwrap();
+        fs::write(relays_path_from(dir.path()), "ws://a:1\nws://b:2\n").unwrap();
+        assert_eq!(
+            configured_relays_from(dir.path()),
+            vec!["ws://a:1", "ws://b:2"]
+        );
+    }
+
+    #[test]
+    fn report_json_omits_reason_when_accepted() {
+        let j = RelayReport::new("ws://a:1", RelayStatus::Accepted, None).to_json();
+        assert_eq!(j["status"], "accepted");
+        assert!(j.get("reason").is_none());
+    }
+
+    #[test]
+    fn classify_send_marks_accepted_rejected_and_unreachable() {
+        let a = RelayUrl::parse("ws://a:1").unwrap();
+        let b = RelayUrl::parse("ws://b:2").unwrap();
+        let c = RelayUrl::parse("ws://c:3").unwrap();
+        let relays = vec![
+            "ws://a:1".to_string(),
+            "ws://b:2".to_string(),
+            "ws://c:3".to_string(),
+        ];
+        let targets = vec![
+            (relays[0].clone(), a.clone()),
+            (relays[1].clone(), b.clone()),
+            (relays[2].clone(), c),
+        ];
+        let unreachable = vec![RelayReport::new(
+            "ws://c:3",
+            RelayStatus::Unreachable,
+            Some("connection refused".into()),
+        )];
+        let success = HashSet::from([a]);
+        let failed = HashMap::from([(b, "blocked: policy".to_string())]);
+
+        let reports = classify_send(&relays, &targets, &unreachable, &success, &failed);
+        let statuses: Vec<_> = reports.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                RelayStatus::Accepted,
+                RelayStatus::Rejected,
+                RelayStatus::Unreachable
+            ]
+        );
+        assert_eq!(reports[1].reason.as_deref(), Some("blocked: policy"));
+    }
+
+    #[test]
+    fn failure_summary_lists_each_failed_relay() {
+        let reports = vec![
+            RelayReport::new("ws://a:1", RelayStatus::Rejected, Some("blocked".into())),
+            RelayReport::new("ws://b:2", RelayStatus::Unreachable, None),
+        ];
+        assert_eq!(
+            failure_summary(&reports),
+            "ws://a:1: blocked; ws://b:2: unreachable"
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use std::fmt::Write;
+
+use nostr_sdk::prelude::*;
+
+use agcli::CommandError;
+
+use crate::error::AppError;
+
+/// Output format for `export-list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    Markdown,
+    Html,
+}
+
+impl TableFormat {
+    pub fn parse(value: &str) -> Result<Self, CommandError> {
+        match value {
+            "csv" => Ok(Self::Csv),
+            "md" | "markdown" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            other => Err(CommandError::new(
+                format!("unknown table format: {other}"),
+                "INVALID_ARGS",
+                "Use --format=csv, --format=md or --format=html",
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+fn tag_values<'a>(event: &'a Event, name: &'a str) -> impl Iterator<Item = &'a [String]> + 'a {
+    event
+        .tags
+        .iter()
+        .map(Tag::as_slice)
+        .filter(move |t| t.len() >= 2 && t[0] == name)
+        .map(|t| &t[1..])
+}
+
+/// One list as a table: `resource` plus the header's required then recommended fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListTable {
+    pub title: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ListTable {
+    pub fn new(header: &Event, items: &[Event]) -> Self {
+        let mut columns = vec!["resource".to_string()];
+        for field in tag_values(header, "required")
+            .chain(tag_values(header, "recommended"))
+            .flatten()
+        {
+            if !columns.contains(field) {
+                columns.push(field.clone());
+            }
+        }
+
+        let rows = items
+            .iter()
+            .map(|item| {
+                columns
+                    .iter()
+                    .map(|column| {
+                        let tag = if column == "resource" { "r" } else { column };
+                        tag_values(item, tag)
+                            .map(|values| values.join(" "))
+                            .collect::<Vec<_>>()
+                            .join("; ")
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            title: list_title(header),
+            columns,
+            rows,
+        }
+    }
+
+    pub fn render(&self, format: TableFormat) -> Result<String, AppError> {
+        match format {
+            TableFormat::Csv => self.to_csv(),
+            TableFormat::Markdown => Ok(self.to_markdown()),
+            TableFormat::Html => Ok(self.to_html()),
+        }
+    }
+
+    fn to_csv(&self) -> Result<String, AppError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let io = |e: csv::Error| AppError::Io {
+            reason: e.to_string(),
+        };
+        writer.write_record(&self.columns).map_err(io)?;
+        for row in &self.rows {
+            writer.write_record(row).map_err(io)?;
+        }
+        let bytes = writer.into_inner().map_err(|e| AppError::Io {
+            reason: e.to_string(),
+        })?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn to_markdown(&self) -> String {
+        let cell = |s: &str| s.replace('|', "\\|").replace(['\r', '\n'], " ");
+        let line = |cells: &[String]| {
+            format!(
+                "| {} |\n",
+                cells
+                    .iter()
+                    .map(|c| cell(c))
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            )
+        };
+        let mut out = format!("# {}\n\n", cell(&self.title));
+        out.push_str(&line(&self.columns));
+        out.push_str(&line(&vec!["---".to_string(); self.columns.len()]));
+        for row in &self.rows {
+            out.push_str(&line(row));
+        }
+        out
+    }
+
+    fn to_html(&self) -> String {
+        let cells = |tag: &str, values: &[String]| {
+            values.iter().fold(String::new(), |mut out, v| {
+                let _ = write!(out, "<{tag}>{}</{tag}>", html_escape(v));
+                out
+            })
+        };
+        let title = html_escape(&self.title);
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n<table>\n<thead><tr>{}</tr></thead>\n<tbody>\n",
+            cells("th", &self.columns)
+        );
+        for row in &self.rows {
+            let _ = writeln!(out, "<tr>{}</tr>", cells("td", row));
+        }
+        out.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+        out
+    }
+}
+
+/// Plural title, else plural name, else singular name.
+fn list_title(header: &Event) -> String {
+    let first = |name| tag_values(header, name).next();
+    first("titles")
+        .and_then(|t| t.get(1).or(t.first()))
+        .or_else(|| first("names").and_then(|n| n.get(1).or(n.first())))
+        .cloned()
+        .unwrap_or_else(|| "List".to_string())
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: u16, tags: &[&[&str]]) -> Event {
+        EventBuilder::new(Kind::Custom(kind), "")
+            .tags(tags.iter().map(|t| Tag::parse(t.iter().copied()).unwrap()))
+            .sign_with_keys(&Keys::generate())
+            .unwrap()
+    }
+
+    fn table() -> ListTable {
+        let header = event(
+            9998,
+            &[
+                &["names", "album", "albums"],
+                &["titles", "Album", "Jazz | Albums"],
+                &["required", "title", "artist"],
+                &["recommended", "year"],
+                &["recommended", "title"],
+            ],
+        );
+        let items = vec![
+            event(
+                9999,
+                &[
+                    &["r", "https://a"],
+                    &["title", "Kind of Blue"],
+                    &["artist", "Miles Davis"],
+                    &["artist", "Coltrane"],
+                ],
+            ),
+            event(
+                9999,
+                &[&["r", "https://b"], &["title", "A, \"Love\" <Supreme>"]],
+            ),
+        ];
+        ListTable::new(&header, &items)
+    }
+
+    #[test]
+    fn columns_come_from_required_then_recommended() {
+        let t = table();
+        assert_eq!(t.columns, vec!["resource", "title", "artist", "year"]);
+        assert_eq!(t.title, "Jazz | Albums");
+        assert_eq!(
+            t.rows[0],
+            vec!["https://a", "Kind of Blue", "Miles Davis; Coltrane", ""]
+        );
+    }
+
+    #[test]
+    fn csv_quotes_commas_and_quotes() {
+        let csv = table().render(TableFormat::Csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("resource,title,artist,year"));
+        assert_eq!(
+            lines.nth(1),
+            Some("https://b,\"A, \"\"Love\"\" <Supreme>\",,")
+        );
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        let md = table().render(TableFormat::Markdown).unwrap();
+        assert!(
+            md.starts_with("# Jazz \\| Albums\n\n| resource | title | artist | year |\n| --- |")
+        );
+        assert_eq!(md.lines().count(), 6);
+    }
+
+    #[test]
+    fn html_escapes_markup() {
+        let html = table().render(TableFormat::Html).unwrap();
+        assert!(html.contains("<th>resource</th><th>title</th>"));
+        assert!(html.contains("<td>A, &quot;Love&quot; &lt;Supreme&gt;</td>"));
+    }
+
+    #[test]
+    fn title_falls_back_to_plural_name() {
+        let header = event(9998, &[&["names", "book", "books"]]);
+        assert_eq!(list_title(&header), "books");
+    }
+
+    #[test]
+    fn format_parse_accepts_markdown_alias() {
+        assert_eq!(
+            TableFormat::parse("markdown").unwrap(),
+            TableFormat::Markdown
+        );
+        assert_eq!(TableFormat::parse("xlsx").unwrap_err().code, "INVALID_ARGS");
+    }
+}
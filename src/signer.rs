@@ -0,0 +1,401 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use agcli::{CommandError, CommandOutput, NextAction};
+use nostr_connect::client::NostrConnect;
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::error::AppError;
+use crate::keys::{
+    bunker_client_keys_from, bunker_pairing_from, home_base, load_keys, profile_flag,
+    save_bunker_pairing_from,
+};
+
+/// How long to wait for a bunker to answer (it may need a human to approve, or to open a
+/// `nostrconnect://` URI in their signer app).
+const BUNKER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where write commands get their signature from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerSpec {
+    /// The active profile's key in `~/.wokhei/`.
+    Local { profile: String },
+    /// A NIP-46 remote signer, by `bunker://` URI or a `nostrconnect://` URI for the
+    /// session key; `profile` owns that app-side key.
+    Bunker { uri: String, profile: String },
+}
+
+impl SignerSpec {
+    /// `--signer` value (or `WOKHEI_SIGNER`); absent means the local profile key.
+    pub fn from_flag(signer: Option<&str>, profile: String) -> Result<Self, AppError> {
+        match signer {
+            None | Some("local") => Ok(Self::Local { profile }),
+            Some(uri) if uri.starts_with("bunker://") || uri.starts_with("nostrconnect://") => {
+                Ok(Self::Bunker {
+                    uri: uri.to_string(),
+                    profile,
+                })
+            }
+            Some(other) => Err(AppError::InvalidSigner {
+                uri: other.to_string(),
+            }),
+        }
+    }
+
+    /// Pubkey of the local profile key if it loads without help; bunkers need the network.
+    pub fn local_public_key(&self) -> Option<PublicKey> {
+        match self {
+            Self::Local { profile } => load_keys(profile).ok().map(|keys| keys.public_key()),
+            Self::Bunker { .. } => None,
+        }
+    }
+}
+
+pub enum Signer {
+    Local(Keys),
+    Bunker(Box<NostrConnect>, Option<Pairing>),
+}
+
+/// A `nostrconnect://` session the signer hasn't answered yet. Its first answer completes
+/// the pairing, and the paired `bunker://` URI is saved to the profile for later runs.
+pub struct Pairing {
+    base: PathBuf,
+    profile: String,
+    uri: String,
+    saved: AtomicBool,
+}
+
+impl Pairing {
+    async fn remember(&self, connect: &NostrConnect) -> Result<(), AppError> {
+        if self.saved.swap(true, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let bunker = connect
+            .bunker_uri()
+            .await
+            .map_err(|e| AppError::SignerUnavailable {
+                reason: e.to_string(),
+            })?;
+        save_bunker_pairing_from(&self.base, &self.profile, &self.uri, &bunker.to_string())
+    }
+}
+
+impl Signer {
+    pub fn load(spec: &SignerSpec) -> Result<Self, CommandError> {
+        match spec {
+            SignerSpec::Local { profile } => {
+                let keys = load_keys(profile).map_err(|e| {
+                    CommandError::from(e).next_actions(vec![NextAction::new(
+                        format!("wokhei init --generate{}", profile_flag(profile)),
+                        "Generate a keypair first",
+                    )])
+                })?;
+                Ok(Self::Local(keys))
+            }
+            SignerSpec::Bunker { uri, profile } => {
+                Self::remote(&home_base(), uri, profile).map_err(CommandError::from)
+            }
+        }
+    }
+
+    /// A `nostrconnect://` URI already paired goes to its saved bunker; otherwise it must
+    /// carry the profile's session key, and the signer has to connect to it first.
+    fn remote(base: &Path, uri: &str, profile: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::InvalidSigner {
+            uri: uri.to_string(),
+        };
+        let client_keys = bunker_client_keys_from(base, profile)?;
+        let paired = bunker_pairing_from(base, profile, uri);
+        let parsed =
+            NostrConnectURI::parse(paired.as_deref().unwrap_or(uri)).map_err(|_| invalid())?;
+        let pairing = (!parsed.is_bunker()).then(|| Pairing {
+            base: base.to_path_buf(),
+            profile: profile.to_string(),
+            uri: uri.to_string(),
+            saved: AtomicBool::new(false),
+        });
+        let connect =
+            NostrConnect::new(parsed, client_keys, BUNKER_TIMEOUT, None).map_err(|_| invalid())?;
+        Ok(Self::Bunker(Box::new(connect), pairing))
+    }
+
+    /// The pubkey events will be signed as (asks the bunker on first use).
+    pub async fn public_key(&self) -> Result<PublicKey, AppError> {
+        match self {
+            Self::Local(keys) => Ok(keys.public_key()),
+            Self::Bunker(connect, pairing) => {
+                let public_key =
+                    connect
+                        .get_public_key()
+                        .await
+                        .map_err(|e| AppError::SignerUnavailable {
+                            reason: e.to_string(),
+                        })?;
+                if let Some(pairing) = pairing {
+                    pairing.remember(connect).await?;
+                }
+                Ok(public_key)
+            }
+        }
+    }
+
+    /// Sign an event so it can be broadcast unchanged to several relays.
+    pub async fn sign(&self, builder: EventBuilder) -> Result<Event, AppError> {
+        match self {
+            Self::Local(keys) => {
+                builder
+                    .sign_with_keys(keys)
+                    .map_err(|e| AppError::SigningFailed {
+                        reason: e.to_string(),
+                    })
+            }
+            Self::Bunker(connect, pairing) => {
+                let event = builder.sign(connect.as_ref()).await.map_err(|e| {
+                    AppError::SignerUnavailable {
+                        reason: e.to_string(),
+                    }
+                })?;
+                if let Some(pairing) = pairing {
+                    pairing.remember(connect).await?;
+                }
+                Ok(event)
+            }
+        }
+    }
+
+    /// Close the bunker's relay connections; no-op for local keys.
+    pub async fn shutdown(self) {
+        if let Self::Bunker(connect, _) = self {
+            connect.shutdown().await;
+        }
+    }
+}
+
+fn session_uri(client_keys: &Keys, relays: &[String]) -> Result<String, CommandError> {
+    let urls = relays
+        .iter()
+        .map(|relay| {
+            RelayUrl::parse(relay).map_err(|e| {
+                CommandError::new(
+                    format!("Invalid relay URL {relay}: {e}"),
+                    "INVALID_ARGS",
+                    "Use ws:// or wss:// relay URLs the signer app can reach",
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(NostrConnectURI::client(client_keys.public_key(), urls, "wokhei").to_string())
+}
+
+/// A `nostrconnect://` URI for `profile`'s session key, to open in a signer app. The
+/// pairing completes on the first write command run with it as `--signer`.
+pub fn nostrconnect_uri(profile: &str, relays: &[String]) -> Result<CommandOutput, CommandError> {
+    nostrconnect_uri_from(&home_base(), profile, relays)
+}
+
+fn nostrconnect_uri_from(
+    base: &Path,
+    profile: &str,
+    relays: &[String],
+) -> Result<CommandOutput, CommandError> {
+    let client_keys = bunker_client_keys_from(base, profile).map_err(CommandError::from)?;
+    let uri = session_uri(&client_keys, relays)?;
+
+    let actions = vec![NextAction::new(
+        format!(
+            "wokhei create-header --signer='{uri}'{} --name=<singular> --plural=<plural>",
+            profile_flag(profile)
+        ),
+        "Open the URI in the signer app while this runs (it waits up to 60s to pair)",
+    )];
+    Ok(CommandOutput::new(json!({
+        "profile": profile,
+        "session_pubkey": client_keys.public_key().to_hex(),
+        "relays": relays,
+        "uri": uri,
+    }))
+    .next_actions(actions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr_connect::prelude::{
+        NostrConnectKeys, NostrConnectMessage, NostrConnectRemoteSigner, NostrConnectRequest,
+        NostrConnectResponse, NostrConnectSignerActions, ResponseResult,
+    };
+    use nostr_relay_builder::MockRelay;
+
+    struct ApproveAll;
+
+    impl NostrConnectSignerActions for ApproveAll {
+        fn approve(&self, _public_key: &PublicKey, _req: &NostrConnectRequest) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn from_flag_defaults_to_local_profile() {
+        let spec = SignerSpec::from_flag(None, "curator".into()).unwrap();
+        assert_eq!(
+            spec,
+            SignerSpec::Local {
+                profile: "curator".into()
+            }
+        );
+    }
+
+    #[test]
+    fn from_flag_accepts_bunker_uri() {
+        let spec = SignerSpec::from_flag(Some("bunker://abc?relay=ws://x"), "default".into());
+        assert!(matches!(spec, Ok(SignerSpec::Bunker { .. })));
+    }
+
+    #[test]
+    fn from_flag_rejects_other_schemes() {
+        let err = SignerSpec::from_flag(Some("nsec1abc"), "default".into()).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNER");
+    }
+
+    #[test]
+    fn from_flag_accepts_nostrconnect_uri() {
+        let uri = format!(
+            "nostrconnect://{}?relay=ws://x",
+            Keys::generate().public_key().to_hex()
+        );
+        let spec = SignerSpec::from_flag(Some(&uri), "default".into());
+        assert!(matches!(spec, Ok(SignerSpec::Bunker { .. })));
+    }
+
+    #[test]
+    fn remote_rejects_malformed_uri() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = Signer::remote(dir.path(), "bunker://not-a-pubkey", "default")
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "INVALID_SIGNER");
+    }
+
+    #[test]
+    fn remote_rejects_nostrconnect_uri_for_another_session_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = session_uri(&Keys::generate(), &["ws://x".into()]).unwrap();
+        let err = Signer::remote(dir.path(), &uri, "default").err().unwrap();
+        assert_eq!(err.code(), "INVALID_SIGNER");
+        assert!(err.fix().contains("wokhei nostrconnect-uri"));
+    }
+
+    #[test]
+    fn session_uri_carries_the_profile_session_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let client_keys = bunker_client_keys_from(dir.path(), "curator").unwrap();
+        let uri = session_uri(&client_keys, &["ws://x".into()]).unwrap();
+        let parsed = NostrConnectURI::parse(&uri).unwrap();
+        assert!(matches!(
+            parsed,
+            NostrConnectURI::Client { public_key, .. } if public_key == client_keys.public_key()
+        ));
+        assert!(Signer::remote(dir.path(), &uri, "curator").is_ok());
+        assert!(session_uri(&client_keys, &["not a url".into()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn local_signer_signs_as_profile_key() {
+        let keys = Keys::generate();
+        let signer = Signer::Local(keys.clone());
+        let event = signer.sign(EventBuilder::text_note("hi")).await.unwrap();
+        assert_eq!(event.pubkey, keys.public_key());
+        assert!(event.verify().is_ok());
+    }
+
+    #[tokio::test]
+    async fn bunker_signer_signs_via_in_process_remote_signer() {
+        let relay = MockRelay::run().await.unwrap();
+        let url = relay.url().await;
+
+        let user = Keys::generate();
+        let remote = NostrConnectRemoteSigner::new(
+            NostrConnectKeys {
+                signer: Keys::generate(),
+                user: user.clone(),
+            },
+            [url],
+            Some("s3cret".into()),
+            None,
+        )
+        .unwrap();
+        let uri = remote.bunker_uri().to_string();
+        tokio::spawn(async move { remote.serve(ApproveAll).await });
+        // The bunker only listens for requests newer than its subscription; let it subscribe first.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let signer = Signer::remote(dir.path(), &uri, "default").unwrap();
+        assert_eq!(signer.public_key().await.unwrap(), user.public_key());
+
+        let event = signer
+            .sign(EventBuilder::new(Kind::Custom(9998), "").tag(Tag::identifier("x")))
+            .await
+            .unwrap();
+        assert_eq!(event.pubkey, user.public_key());
+        assert!(event.verify().is_ok());
+        signer.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn nostrconnect_pairs_once_then_reuses_the_saved_bunker() {
+        let relay = MockRelay::run().await.unwrap();
+        let url = relay.url().await;
+        let dir = tempfile::tempdir().unwrap();
+        let client_keys = bunker_client_keys_from(dir.path(), "default").unwrap();
+        let uri = session_uri(&client_keys, &[url.to_string()]).unwrap();
+
+        let (signer_keys, user) = (Keys::generate(), Keys::generate());
+        let remote = NostrConnectRemoteSigner::new(
+            NostrConnectKeys {
+                signer: signer_keys.clone(),
+                user: user.clone(),
+            },
+            [url.clone()],
+            None,
+            None,
+        )
+        .unwrap();
+        tokio::spawn(async move { remote.serve(ApproveAll).await });
+        // A signer app answers the URI with a `connect` response once; the remote signer
+        // above only sends it as a request, so the stand-in publishes the response itself.
+        let ack = EventBuilder::nostr_connect(
+            &signer_keys,
+            client_keys.public_key(),
+            NostrConnectMessage::response(
+                "pair",
+                NostrConnectResponse::with_result(ResponseResult::Ack),
+            ),
+        )
+        .unwrap()
+        .sign_with_keys(&signer_keys)
+        .unwrap();
+
+        let pairing = Signer::remote(dir.path(), &uri, "default").unwrap();
+        let (public_key, ()) = tokio::join!(pairing.public_key(), async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            let app = Client::default();
+            app.add_relay(url.clone()).await.unwrap();
+            app.connect().await;
+            app.send_event(&ack).await.unwrap();
+            app.disconnect().await;
+        });
+        assert_eq!(public_key.unwrap(), user.public_key());
+        pairing.shutdown().await;
+        let bunker = bunker_pairing_from(dir.path(), "default", &uri).unwrap();
+        assert!(bunker.starts_with("bunker://"));
+
+        let paired = Signer::remote(dir.path(), &uri, "default").unwrap();
+        assert!(matches!(paired, Signer::Bunker(_, None)));
+        let event = paired.sign(EventBuilder::text_note("hi")).await.unwrap();
+        assert_eq!(event.pubkey, user.public_key());
+        paired.shutdown().await;
+    }
+}
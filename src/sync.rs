@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::error::AppError;
+
+fn sync_state_path_from(base: &Path) -> PathBuf {
+    crate::keys::keys_dir_from(base).join("sync.json")
+}
+
+/// Events already in a JSON Lines export; lines that aren't events are skipped.
+pub fn written_events(path: &str) -> Vec<Event> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| Event::from_json(line).ok())
+        .collect()
+}
+
+/// Newest `created_at` already written, plus the ids at exactly that second: relay `since`
+/// is inclusive, so those would otherwise be appended twice.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncCursor {
+    pub since: u64,
+    pub ids: Vec<String>,
+}
+
+impl SyncCursor {
+    pub fn is_new(&self, event: &Event) -> bool {
+        let secs = event.created_at.as_secs();
+        secs > self.since || (secs == self.since && !self.ids.contains(&event.id.to_hex()))
+    }
+
+    /// Advance past an event that was just written.
+    pub fn observe(&mut self, event: &Event) {
+        let secs = event.created_at.as_secs();
+        let id = event.id.to_hex();
+        if secs > self.since {
+            self.since = secs;
+            self.ids = vec![id];
+        } else if secs == self.since && !self.ids.contains(&id) {
+            self.ids.push(id);
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({ "since": self.since, "ids": self.ids })
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            since: value["since"].as_u64()?,
+            ids: value["ids"]
+                .as_array()?
+                .iter()
+                .filter_map(|id| id.as_str().map(String::from))
+                .collect(),
+        })
+    }
+}
+
+fn output_path(output: &Path) -> Option<PathBuf> {
+    let dir = output
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    match (fs::canonicalize(dir), output.file_name()) {
+        (Ok(dir), Some(name)) => Some(dir.join(name)),
+        _ => std::path::absolute(output).ok(),
+    }
+}
+
+/// Cursors from `~/.wokhei/sync.json`, keyed by relay, export scope and output file.
+#[derive(Debug, Default)]
+pub struct SyncState {
+    cursors: BTreeMap<String, SyncCursor>,
+}
+
+impl SyncState {
+    /// The output is keyed by its canonical directory, which exists before the first run
+    /// writes the file, so a relative path keys the same on every run.
+    pub fn key(relay: &str, scope: Option<&serde_json::Value>, output: &str) -> String {
+        let output = output_path(Path::new(output))
+            .map_or_else(|| output.to_string(), |p| p.display().to_string());
+        let scope = scope.map_or_else(|| "all".to_string(), ToString::to_string);
+        format!("{relay} {scope} {output}")
+    }
+
+    /// A missing or unreadable state file starts every sync from scratch.
+    fn load_from(base: &Path) -> Self {
+        let cursors = fs::read_to_string(sync_state_path_from(base))
+            .ok()
+            .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+            .and_then(|v| v.as_object().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), SyncCursor::from_json(v)?)))
+            .collect();
+        Self { cursors }
+    }
+
+    pub fn load() -> Self {
+        Self::load_from(&crate::keys::home_base())
+    }
+
+    fn save_to(&self, base: &Path) -> Result<(), AppError> {
+        let path = sync_state_path_from(base);
+        let io = |e: std::io::Error| AppError::Io {
+            reason: format!("Failed to write {}: {e}", path.display()),
+        };
+        fs::create_dir_all(crate::keys::keys_dir_from(base)).map_err(io)?;
+        let state: serde_json::Map<_, _> = self
+            .cursors
+            .iter()
+            .map(|(k, c)| (k.clone(), c.to_json()))
+            .collect();
+        fs::write(&path, serde_json::Value::Object(state).to_string()).map_err(io)
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        self.save_to(&crate::keys::home_base())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SyncCursor> {
+        self.cursors.get(key)
+    }
+
+    pub fn set(&mut self, key: String, cursor: SyncCursor) {
+        self.cursors.insert(key, cursor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_at(secs: u64) -> Event {
+        EventBuilder::text_note("x")
+            .custom_created_at(Timestamp::from_secs(secs))
+            .sign_with_keys(&Keys::generate())
+            .unwrap()
+    }
+
+    #[test]
+    fn cursor_skips_ids_already_written_at_the_boundary() {
+        let (a, b) = (event_at(100), event_at(100));
+        let mut cursor = SyncCursor::default();
+        cursor.observe(&event_at(50));
+        cursor.observe(&a);
+        assert_eq!(cursor.since, 100);
+        assert!(!cursor.is_new(&a));
+        assert!(cursor.is_new(&b));
+        assert!(!cursor.is_new(&event_at(99)));
+        assert!(cursor.is_new(&event_at(101)));
+    }
+
+    #[test]
+    fn state_round_trips_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = SyncState::load_from(dir.path());
+        assert!(state.get("k").is_none());
+
+        let mut cursor = SyncCursor::default();
+        cursor.observe(&event_at(7));
+        state.set("k".into(), cursor.clone());
+        state.save_to(dir.path()).unwrap();
+
+        assert_eq!(SyncState::load_from(dir.path()).get("k"), Some(&cursor));
+    }
+
+    #[test]
+    fn key_separates_relays_scopes_and_outputs() {
+        let scope = json!({ "author": "abc" });
+        let keys = [
+            SyncState::key("ws://a", None, "out.jsonl"),
+            SyncState::key("ws://b", None, "out.jsonl"),
+            SyncState::key("ws://a", Some(&scope), "out.jsonl"),
+            SyncState::key("ws://a", None, "other.jsonl"),
+        ];
+        let unique: std::collections::HashSet<_> = keys.iter().collect();
+        assert_eq!(unique.len(), 4);
+    }
+
+    #[test]
+    fn key_is_stable_once_the_output_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.jsonl").display().to_string();
+        let before = SyncState::key("ws://a", None, &path);
+        fs::write(&path, "").unwrap();
+        assert_eq!(SyncState::key("ws://a", None, &path), before);
+
+        let cwd = fs::canonicalize(".").unwrap().join("out.jsonl");
+        assert!(SyncState::key("ws://a", None, "out.jsonl").ends_with(&cwd.display().to_string()));
+    }
+}
@@ -0,0 +1,655 @@
+use nostr_sdk::prelude::*;
+use serde_json::json;
+
+use crate::error::AppError;
+
+/// One broken `DCoSL` rule. `rule` is a stable machine-readable code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl Violation {
+    pub fn new(rule: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            rule,
+            message: message.into(),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({ "rule": self.rule, "message": self.message })
+    }
+}
+
+pub fn violations_json(violations: &[Violation]) -> Vec<serde_json::Value> {
+    violations.iter().map(Violation::to_json).collect()
+}
+
+/// `Err` naming the first broken rule, with every violation in the message.
+pub fn reject(violations: &[Violation]) -> Result<(), AppError> {
+    match violations.first() {
+        None => Ok(()),
+        Some(first) => Err(AppError::RuleViolation {
+            rule: first.rule,
+            details: violations
+                .iter()
+                .map(|v| format!("{}: {}", v.rule, v.message))
+                .collect::<Vec<_>>()
+                .join("; "),
+        }),
+    }
+}
+
+/// Tag array as plain JSON (`[["names","a","b"], ...]`).
+pub fn tags_json(tags: &[Tag]) -> serde_json::Value {
+    json!(tags.iter().map(Tag::as_slice).collect::<Vec<_>>())
+}
+
+/// Tags only in `old` (`removed`) and only in `new` (`added`), as plain JSON arrays.
+pub fn tag_diff(old: &[Tag], new: &[Tag]) -> serde_json::Value {
+    let only_in = |a: &[Tag], b: &[Tag]| -> Vec<Tag> {
+        a.iter().filter(|t| !b.contains(t)).cloned().collect()
+    };
+    json!({
+        "added": tags_json(&only_in(new, old)),
+        "removed": tags_json(&only_in(old, new)),
+    })
+}
+
+fn tags_named<'a>(tags: &'a [Tag], name: &'a str) -> impl Iterator<Item = &'a [String]> + 'a {
+    tags.iter()
+        .map(Tag::as_slice)
+        .filter(move |t| t.first().map(String::as_str) == Some(name))
+}
+
+fn tag_value<'a>(tags: &'a [Tag], name: &'a str) -> Option<&'a str> {
+    tags_named(tags, name)
+        .next()
+        .and_then(|t| t.get(1))
+        .map(String::as_str)
+}
+
+/// Check a `DCoSL` event's tags. Kinds other than 9998/39998/9999/39999 have no rules.
+pub fn check_event(kind: u16, tags: &[Tag]) -> Vec<Violation> {
+    match kind {
+        9998 | 39998 => check_header_tags(kind, tags),
+        9999 | 39999 => check_item_tags(kind, tags),
+        _ => Vec::new(),
+    }
+}
+
+fn check_field_name(source: &str, field: &str, out: &mut Vec<Violation>) {
+    if field.is_empty() || field.contains(',') || field.chars().any(char::is_whitespace) {
+        out.push(Violation::new(
+            "INVALID_FIELD_NAME",
+            format!("{source} field {field:?} must be a single non-empty word"),
+        ));
+    }
+}
+
+fn check_header_tags(kind: u16, tags: &[Tag]) -> Vec<Violation> {
+    let mut out = Vec::new();
+
+    match tags_named(tags, "names").next() {
+        None => out.push(Violation::new(
+            "MISSING_NAMES",
+            "header needs a names tag: [\"names\", <singular>, <plural>]",
+        )),
+        Some(names) if names.len() < 3 || names[1..3].iter().any(|n| n.trim().is_empty()) => {
+            out.push(Violation::new(
+                "NAMES_INCOMPLETE",
+                "names tag needs both a singular and a plural name",
+            ));
+        }
+        Some(_) => {}
+    }
+
+    if let Some(titles) = tags_named(tags, "titles").next()
+        && titles.len() != 3
+    {
+        out.push(Violation::new(
+            "TITLES_INCOMPLETE",
+            "titles tag needs exactly a singular and a plural title",
+        ));
+    }
+
+    let required: Vec<&String> = tags_named(tags, "required").flat_map(|t| &t[1..]).collect();
+    for field in &required {
+        check_field_name("required", field, &mut out);
+    }
+
+    for recommended in tags_named(tags, "recommended") {
+        if recommended.len() > 2 {
+            out.push(Violation::new(
+                "RECOMMENDED_COMBINED",
+                format!(
+                    "recommended tag lists {} fields; repeat the tag once per field",
+                    recommended.len() - 1
+                ),
+            ));
+        }
+        for field in &recommended[1..] {
+            check_field_name("recommended", field, &mut out);
+            if required.contains(&field) {
+                out.push(Violation::new(
+                    "FIELD_REQUIRED_AND_RECOMMENDED",
+                    format!("field {field:?} is both required and recommended"),
+                ));
+            }
+        }
+    }
+
+    if kind == 39998 {
+        check_d_tag(tags, &mut out);
+    }
+    out
+}
+
+fn check_d_tag(tags: &[Tag], out: &mut Vec<Violation>) {
+    if tag_value(tags, "d").is_none_or(str::is_empty) {
+        out.push(Violation::new(
+            "MISSING_D_TAG",
+            "addressable events need a non-empty d tag",
+        ));
+    }
+}
+
+/// A `z` value must be a 64-hex header event id or a `39998:<pubkey>:<d-tag>` coordinate.
+pub fn check_z_ref(z: &str) -> Option<Violation> {
+    let well_formed = if let Some(rest) = z.strip_prefix("39998:") {
+        rest.split_once(':')
+            .is_some_and(|(pk, d)| PublicKey::from_hex(pk).is_ok() && !d.is_empty())
+    } else {
+        EventId::from_hex(z).is_ok()
+    };
+    (!well_formed).then(|| {
+        Violation::new(
+            "MALFORMED_Z",
+            format!("z {z:?} is neither a header event id nor a 39998:<pubkey>:<d-tag> coordinate"),
+        )
+    })
+}
+
+fn check_item_tags(kind: u16, tags: &[Tag]) -> Vec<Violation> {
+    let mut out = Vec::new();
+
+    let zs: Vec<&[String]> = tags_named(tags, "z").collect();
+    match zs.as_slice() {
+        [] => out.push(Violation::new(
+            "MISSING_Z",
+            "item needs a z tag pointing at its header",
+        )),
+        [z] => match z.get(1) {
+            Some(value) => out.extend(check_z_ref(value)),
+            None => out.push(Violation::new("MALFORMED_Z", "z tag has no value")),
+        },
+        _ => out.push(Violation::new(
+            "MULTIPLE_Z",
+            format!(
+                "item has {} z tags; it must belong to exactly one header",
+                zs.len()
+            ),
+        )),
+    }
+
+    if kind == 39999 {
+        check_d_tag(tags, &mut out);
+    }
+    out
+}
+
+/// Item tags wokhei manages itself; never reported as unknown fields.
+const STRUCTURAL_ITEM_TAGS: [&str; 4] = ["z", "d", "r", "client"];
+
+/// The item fields a header declares in its `required` and `recommended` tags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderSchema {
+    pub required: Vec<String>,
+    pub recommended: Vec<String>,
+}
+
+impl HeaderSchema {
+    pub fn from_tags(tags: &[Tag]) -> Self {
+        let fields = |name| {
+            let mut out: Vec<String> = Vec::new();
+            for field in tags_named(tags, name).flat_map(|t| &t[1..]) {
+                if !field.is_empty() && !out.contains(field) {
+                    out.push(field.clone());
+                }
+            }
+            out
+        };
+        Self {
+            required: fields("required"),
+            recommended: fields("recommended"),
+        }
+    }
+
+    /// Required fields with no non-empty tag in `item_tags`. `resource` is the `r` tag.
+    pub fn missing(&self, item_tags: &[Tag]) -> Vec<String> {
+        self.required
+            .iter()
+            .filter(|field| {
+                let name = if *field == "resource" { "r" } else { field };
+                !tags_named(item_tags, name).any(|t| t.get(1).is_some_and(|v| !v.is_empty()))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Item fields the header neither requires nor recommends. A header that declares no
+    /// fields accepts any.
+    pub fn unknown(&self, item_tags: &[Tag]) -> Vec<String> {
+        if self.required.is_empty() && self.recommended.is_empty() {
+            return Vec::new();
+        }
+        let mut out: Vec<String> = Vec::new();
+        for name in item_tags.iter().filter_map(|t| t.as_slice().first()) {
+            if !STRUCTURAL_ITEM_TAGS.contains(&name.as_str())
+                && !self.required.contains(name)
+                && !self.recommended.contains(name)
+                && !out.contains(name)
+            {
+                out.push(name.clone());
+            }
+        }
+        out
+    }
+
+    /// `Err` listing the missing required fields, otherwise warnings for unknown ones.
+    pub fn enforce(&self, item_tags: &[Tag]) -> Result<Vec<String>, AppError> {
+        let missing = self.missing(item_tags);
+        if !missing.is_empty() {
+            return Err(AppError::MissingRequiredFields { fields: missing });
+        }
+        Ok(self
+            .unknown(item_tags)
+            .into_iter()
+            .map(|field| format!("field {field:?} is not required or recommended by the header"))
+            .collect())
+    }
+}
+
+/// What is wrong with one item of a list. `violations` fail validation; `unknown` fields
+/// are only reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemReport {
+    pub event_id: EventId,
+    pub resource: Option<String>,
+    pub violations: Vec<Violation>,
+    pub unknown: Vec<String>,
+}
+
+impl ItemReport {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "event_id": self.event_id.to_hex(),
+            "resource": self.resource,
+            "violations": violations_json(&self.violations),
+            "unknown_fields": self.unknown,
+        })
+    }
+}
+
+/// Check every item of the list at `header_ref` against its header. Only items with
+/// something to report are returned, in `items` order. A resource listed more than once
+/// is a duplicate on every item but the oldest.
+pub fn check_list_items(
+    schema: &HeaderSchema,
+    header_ref: &str,
+    items: &[Event],
+) -> Vec<ItemReport> {
+    let mut first_by_resource: std::collections::HashMap<&str, &Event> =
+        std::collections::HashMap::new();
+    for item in items {
+        if let Some(resource) = tag_value(item.tags.as_slice(), "r") {
+            let first = first_by_resource.entry(resource).or_insert(item);
+            if (item.created_at, item.id) < (first.created_at, first.id) {
+                *first = item;
+            }
+        }
+    }
+
+    let mut reports = Vec::new();
+    for item in items {
+        let tags = item.tags.as_slice();
+        let resource = tag_value(tags, "r");
+        let mut violations = check_event(item.kind.as_u16(), tags);
+
+        let zs: Vec<&str> = tags_named(tags, "z")
+            .filter_map(|t| t.get(1))
+            .map(String::as_str)
+            .collect();
+        if let [z] = zs.as_slice()
+            && *z != header_ref
+            && check_z_ref(z).is_none()
+        {
+            violations.push(Violation::new(
+                "Z_MISMATCH",
+                format!("z {z:?} points at another header than {header_ref:?}"),
+            ));
+        }
+
+        let missing = schema.missing(tags);
+        if !missing.is_empty() {
+            violations.push(Violation::new(
+                "MISSING_REQUIRED_FIELDS",
+                format!("missing required fields: {}", missing.join(", ")),
+            ));
+        }
+
+        if let Some(first) = resource.and_then(|r| first_by_resource.get(r))
+            && first.id != item.id
+        {
+            violations.push(Violation::new(
+                "DUPLICATE_RESOURCE",
+                format!(
+                    "resource {:?} is already item {}",
+                    resource.unwrap_or_default(),
+                    first.id.to_hex()
+                ),
+            ));
+        }
+
+        let unknown = schema.unknown(tags);
+        if !violations.is_empty() || !unknown.is_empty() {
+            reports.push(ItemReport {
+                event_id: item.id,
+                resource: resource.map(String::from),
+                violations,
+                unknown,
+            });
+        }
+    }
+    reports
+}
+
+/// Check `--fields=k=v,...` entries that `build_item_tags` would otherwise drop or misuse.
+pub fn check_field_args(fields: &[String]) -> Vec<Violation> {
+    let mut out = Vec::new();
+    for field in fields {
+        match field.split_once('=') {
+            Some((key, _)) if !key.trim().is_empty() => {
+                if matches!(key, "z" | "d") {
+                    out.push(Violation::new(
+                        "RESERVED_FIELD",
+                        format!(
+                            "field {key:?} is managed by wokhei and cannot be set via --fields"
+                        ),
+                    ));
+                }
+            }
+            _ => out.push(Violation::new(
+                "FIELD_WITHOUT_VALUE",
+                format!("field {field:?} is not key=value and would be dropped"),
+            )),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(parts: &[&str]) -> Tag {
+        Tag::parse(parts.iter().copied()).unwrap()
+    }
+
+    fn rules(violations: &[Violation]) -> Vec<&'static str> {
+        violations.iter().map(|v| v.rule).collect()
+    }
+
+    fn pk() -> String {
+        Keys::generate().public_key().to_hex()
+    }
+
+    #[test]
+    fn valid_addressable_header_has_no_violations() {
+        let tags = vec![
+            tag(&["names", "book", "books"]),
+            tag(&["required", "title", "author"]),
+            tag(&["recommended", "isbn"]),
+            tag(&["d", "books--1234abcd"]),
+        ];
+        assert!(check_event(39998, &tags).is_empty());
+    }
+
+    #[test]
+    fn header_missing_names_and_d() {
+        let v = check_event(39998, &[tag(&["t", "x"])]);
+        assert_eq!(rules(&v), vec!["MISSING_NAMES", "MISSING_D_TAG"]);
+    }
+
+    #[test]
+    fn header_names_without_plural() {
+        let v = check_event(9998, &[tag(&["names", "book"])]);
+        assert_eq!(rules(&v), vec!["NAMES_INCOMPLETE"]);
+    }
+
+    #[test]
+    fn header_combined_recommended_and_overlap() {
+        let tags = vec![
+            tag(&["names", "book", "books"]),
+            tag(&["required", "title"]),
+            tag(&["recommended", "title,isbn", "year"]),
+        ];
+        let v = check_event(9998, &tags);
+        assert_eq!(
+            rules(&v),
+            vec!["RECOMMENDED_COMBINED", "INVALID_FIELD_NAME"]
+        );
+    }
+
+    #[test]
+    fn header_field_both_required_and_recommended() {
+        let tags = vec![
+            tag(&["names", "book", "books"]),
+            tag(&["required", "title"]),
+            tag(&["recommended", "title"]),
+        ];
+        let v = check_event(9998, &tags);
+        assert_eq!(rules(&v), vec!["FIELD_REQUIRED_AND_RECOMMENDED"]);
+    }
+
+    #[test]
+    fn header_titles_incomplete() {
+        let tags = vec![tag(&["names", "a", "as"]), tag(&["titles", "A"])];
+        assert_eq!(rules(&check_event(9998, &tags)), vec!["TITLES_INCOMPLETE"]);
+    }
+
+    #[test]
+    fn item_with_coordinate_z_is_valid() {
+        let tags = vec![
+            tag(&["z", &format!("39998:{}:books", pk())]),
+            tag(&["r", "https://x"]),
+            tag(&["d", "x--1"]),
+        ];
+        assert!(check_event(39999, &tags).is_empty());
+    }
+
+    #[test]
+    fn item_with_event_id_z_is_valid() {
+        let id = EventId::all_zeros().to_hex();
+        assert!(check_event(9999, &[tag(&["z", &id])]).is_empty());
+    }
+
+    #[test]
+    fn item_missing_z_and_d() {
+        let v = check_event(39999, &[tag(&["r", "https://x"])]);
+        assert_eq!(rules(&v), vec!["MISSING_Z", "MISSING_D_TAG"]);
+    }
+
+    #[test]
+    fn item_multiple_z() {
+        let id = EventId::all_zeros().to_hex();
+        let v = check_event(9999, &[tag(&["z", &id]), tag(&["z", &id])]);
+        assert_eq!(rules(&v), vec!["MULTIPLE_Z"]);
+    }
+
+    #[test]
+    fn check_z_ref_rejects_malformed_values() {
+        for bad in [
+            "abc",
+            "39998:nothex:d",
+            &format!("39998:{}:", pk()),
+            "9998:x:y",
+        ] {
+            assert!(check_z_ref(bad).is_some(), "{bad:?} should be malformed");
+        }
+    }
+
+    #[test]
+    fn other_kinds_have_no_rules() {
+        assert!(check_event(1, &[]).is_empty());
+    }
+
+    #[test]
+    fn check_field_args_flags_bare_and_reserved() {
+        let fields = vec![
+            "title=Dune".to_string(),
+            "orphan".to_string(),
+            "=x".to_string(),
+            "z=abc".to_string(),
+        ];
+        assert_eq!(
+            rules(&check_field_args(&fields)),
+            vec![
+                "FIELD_WITHOUT_VALUE",
+                "FIELD_WITHOUT_VALUE",
+                "RESERVED_FIELD"
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_names_first_rule_and_lists_all() {
+        assert!(reject(&[]).is_ok());
+        let err = reject(&check_event(39999, &[])).unwrap_err();
+        assert_eq!(err.code(), "MISSING_Z");
+        assert!(err.to_string().contains("MISSING_D_TAG: "));
+    }
+
+    #[test]
+    fn header_schema_reports_missing_and_unknown_fields() {
+        let schema = HeaderSchema::from_tags(&[
+            tag(&["names", "book", "books"]),
+            tag(&["required", "title", "resource"]),
+            tag(&["recommended", "isbn"]),
+        ]);
+        let item = vec![
+            tag(&["z", "x"]),
+            tag(&["r", "https://x"]),
+            tag(&["title", ""]),
+            tag(&["year", "1965"]),
+            tag(&["client", "wokhei"]),
+        ];
+        assert_eq!(schema.missing(&item), vec!["title"]);
+        assert_eq!(schema.unknown(&item), vec!["year"]);
+
+        let err = schema.enforce(&item).unwrap_err();
+        assert_eq!(err.code(), "MISSING_REQUIRED_FIELDS");
+        assert!(err.to_string().contains("title"));
+
+        let mut complete = item.clone();
+        complete.push(tag(&["title", "Dune"]));
+        let warnings = schema.enforce(&complete).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("year"));
+    }
+
+    #[test]
+    fn header_without_fields_accepts_any() {
+        let schema = HeaderSchema::from_tags(&[tag(&["names", "a", "as"])]);
+        assert_eq!(schema.enforce(&[tag(&["year", "1965"])]).unwrap().len(), 0);
+    }
+
+    fn item(keys: &Keys, tags: &[&[&str]], secs: u64) -> Event {
+        EventBuilder::new(Kind::Custom(9999), "")
+            .tags(tags.iter().map(|t| tag(t)))
+            .custom_created_at(Timestamp::from_secs(secs))
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    #[test]
+    fn check_list_items_reports_each_problem() {
+        let keys = Keys::generate();
+        let header_ref = EventId::all_zeros().to_hex();
+        let other_ref = format!("39998:{}:other", pk());
+        let schema = HeaderSchema {
+            required: vec!["title".into()],
+            recommended: vec![],
+        };
+        let good = item(
+            &keys,
+            &[&["z", &header_ref], &["r", "https://a"], &["title", "A"]],
+            1,
+        );
+        let duplicate = item(
+            &keys,
+            &[&["z", &header_ref], &["r", "https://a"], &["title", "A"]],
+            2,
+        );
+        let untitled = item(
+            &keys,
+            &[&["z", &header_ref], &["r", "https://b"], &["year", "1"]],
+            3,
+        );
+        let elsewhere = item(
+            &keys,
+            &[&["z", &other_ref], &["r", "https://c"], &["title", "C"]],
+            4,
+        );
+        let malformed = item(
+            &keys,
+            &[&["z", "nope"], &["r", "https://d"], &["title", "D"]],
+            5,
+        );
+
+        let reports = check_list_items(
+            &schema,
+            &header_ref,
+            &[
+                malformed.clone(),
+                elsewhere.clone(),
+                untitled.clone(),
+                duplicate.clone(),
+                good,
+            ],
+        );
+        let summary: Vec<(EventId, Vec<&str>)> = reports
+            .iter()
+            .map(|r| (r.event_id, rules(&r.violations)))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (malformed.id, vec!["MALFORMED_Z"]),
+                (elsewhere.id, vec!["Z_MISMATCH"]),
+                (untitled.id, vec!["MISSING_REQUIRED_FIELDS"]),
+                (duplicate.id, vec!["DUPLICATE_RESOURCE"]),
+            ]
+        );
+        assert_eq!(reports[2].unknown, vec!["year"]);
+        assert_eq!(reports[3].to_json()["resource"], "https://a");
+    }
+
+    #[test]
+    fn tag_diff_lists_added_and_removed_tags() {
+        let old = vec![tag(&["names", "a", "as"]), tag(&["description", "old"])];
+        let new = vec![tag(&["names", "a", "as"]), tag(&["description", "new"])];
+        assert_eq!(
+            tag_diff(&old, &new),
+            json!({ "added": [["description", "new"]], "removed": [["description", "old"]] })
+        );
+    }
+
+    #[test]
+    fn tags_json_is_plain_arrays() {
+        let j = tags_json(&[tag(&["names", "a", "as"])]);
+        assert_eq!(j, json!([["names", "a", "as"]]));
+    }
+}
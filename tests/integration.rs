@@ -21,6 +21,7 @@ impl TestContext {
     fn run(&self, args: &[&str]) -> Value {
         let output = Command::new(&self.bin)
             .args(args)
+            .current_dir(self.home.path())
             .env("HOME", self.home.path())
             .env("WOKHEI_RELAY", &self.relay)
             .output()
@@ -85,6 +86,26 @@ fn init_and_whoami() {
     assert!(npub.starts_with("npub1"), "npub should start with npub1");
 }
 
+#[test]
+#[ignore = "requires strfry relay"]
+fn profiles_are_independent_identities() {
+    let ctx = TestContext::new();
+    let default_pk = ctx.init()["result"]["pubkey"].as_str().unwrap().to_string();
+
+    let curator = ctx.run_ok(&["init", "--generate", "--profile=curator"]);
+    let curator_pk = curator["result"]["pubkey"].as_str().unwrap();
+    assert_ne!(default_pk, curator_pk, "profiles should have distinct keys");
+
+    let whoami = ctx.run_ok(&["whoami", "--profile=curator"]);
+    assert_eq!(whoami["result"]["pubkey"], curator_pk);
+
+    let all = ctx.run_ok(&["whoami", "--all"]);
+    assert_eq!(all["result"]["count"], 2);
+    assert_eq!(all["result"]["profiles"][0]["profile"], "default");
+    assert_eq!(all["result"]["profiles"][0]["active"], true);
+    assert_eq!(all["result"]["profiles"][1]["profile"], "curator");
+}
+
 #[test]
 #[ignore = "requires strfry relay"]
 fn create_header_and_list() {
@@ -136,6 +157,43 @@ fn create_addressable_header() {
     );
 }
 
+#[test]
+#[ignore = "requires strfry relay"]
+fn update_header_keeps_d_tag_and_untouched_tags() {
+    let ctx = TestContext::new();
+    ctx.init();
+
+    let header = ctx.run_ok(&[
+        "create-header",
+        "--name=genre",
+        "--plural=genres",
+        "--addressable",
+        "--description=Old",
+        "--tags=music",
+    ]);
+    let coordinate = header["result"]["coordinate"].as_str().unwrap();
+
+    let updated = ctx.run_ok(&[
+        "update-header",
+        coordinate,
+        "--description=New",
+        "--add-recommended=era",
+    ]);
+    let result = &updated["result"];
+    assert_eq!(result["coordinate"], coordinate);
+    assert_eq!(result["previous_event_id"], header["result"]["event_id"]);
+    assert_eq!(result["d_tag"], header["result"]["d_tag"]);
+    assert_eq!(
+        result["diff"]["removed"],
+        serde_json::json!([["description", "Old"]])
+    );
+
+    let inspected = ctx.run_ok(&["inspect", result["event_id"].as_str().unwrap()]);
+    let tags = inspected["result"]["tags"].as_array().unwrap();
+    assert!(tags.iter().any(|t| t[0] == "t" && t[1] == "music"));
+    assert!(tags.iter().any(|t| t[0] == "recommended" && t[1] == "era"));
+}
+
 #[test]
 #[ignore = "requires strfry relay"]
 fn add_item_and_list() {
@@ -174,6 +232,116 @@ fn add_item_and_list() {
     );
 }
 
+#[test]
+#[ignore = "requires strfry relay"]
+fn add_item_enforces_header_required_fields() {
+    let ctx = TestContext::new();
+    ctx.init();
+
+    let header = ctx.run_ok(&[
+        "create-header",
+        "--name=book",
+        "--plural=books",
+        "--required=title",
+        "--recommended=isbn",
+    ]);
+    let header_id = header["result"]["event_id"]
+        .as_str()
+        .expect("header event_id");
+    let header_flag = format!("--header={header_id}");
+
+    let missing = ctx.run(&[
+        "add-item",
+        &header_flag,
+        "--resource=https://example.com/b1",
+    ]);
+    assert_eq!(missing["ok"], false);
+    assert_eq!(missing["error"]["code"], "MISSING_REQUIRED_FIELDS");
+
+    let item = ctx.run_ok(&[
+        "add-item",
+        &header_flag,
+        "--resource=https://example.com/b1",
+        "--fields=title=Dune,year=1965",
+    ]);
+    let warnings = item["result"]["warnings"].as_array().expect("warnings");
+    assert!(warnings[0].as_str().is_some_and(|w| w.contains("year")));
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn update_item_keeps_d_and_z() {
+    let ctx = TestContext::new();
+    ctx.init();
+    let header = ctx.run_ok(&[
+        "create-header",
+        "--name=album",
+        "--plural=albums",
+        "--addressable",
+        "--required=title",
+    ]);
+    let coordinate = header["result"]["coordinate"].as_str().unwrap();
+    let item = ctx.run_ok(&[
+        "add-item",
+        &format!("--header-coordinate={coordinate}"),
+        "--resource=https://example.com/kob",
+        "--fields=title=Kind of Blue,label=Columbia",
+        "--addressable",
+    ]);
+    let d_tag = item["result"]["d_tag"].as_str().unwrap();
+    let pubkey = ctx.pubkey();
+    let item_coordinate = format!("39999:{pubkey}:{d_tag}");
+
+    let unset_required = ctx.run(&["update-item", &item_coordinate, "--unset=title"]);
+    assert_eq!(unset_required["error"]["code"], "MISSING_REQUIRED_FIELDS");
+
+    let updated = ctx.run_ok(&[
+        "update-item",
+        &item_coordinate,
+        "--set=title=Kind of Blue (Legacy)",
+        "--unset=label",
+    ]);
+    let result = &updated["result"];
+    assert_eq!(result["previous_event_id"], item["result"]["event_id"]);
+    assert_eq!(result["header_ref"], coordinate);
+    assert_eq!(result["d_tag"], d_tag);
+
+    let items = ctx.run_ok(&["list-items", &format!("--header-coordinate={coordinate}")]);
+    assert_eq!(items["result"]["total"], 1);
+    assert_eq!(items["result"]["items"][0]["event_id"], result["event_id"]);
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn migrate_list_reissues_items_under_a_coordinate() {
+    let ctx = TestContext::new();
+    ctx.init();
+    let header = ctx.run_ok(&["create-header", "--name=tune", "--plural=tunes"]);
+    let header_id = header["result"]["event_id"].as_str().unwrap();
+    for resource in [
+        "https://example.com/so-what",
+        "https://example.com/blue-in-green",
+    ] {
+        ctx.run_ok(&[
+            "add-item",
+            &format!("--header={header_id}"),
+            &format!("--resource={resource}"),
+        ]);
+    }
+
+    let migrated = ctx.run_ok(&["migrate-list", header_id]);
+    let result = &migrated["result"];
+    assert_eq!(result["succeeded"], 2);
+    assert_eq!(result["mapping"].as_object().unwrap().len(), 3);
+    assert_eq!(result["mapping"][header_id], result["header"]["event_id"]);
+    let coordinate = result["header"]["coordinate"].as_str().unwrap();
+
+    let items = ctx.run_ok(&["list-items", &format!("--header-coordinate={coordinate}")]);
+    assert_eq!(items["result"]["total"], 2);
+    let original = ctx.run_ok(&["list-items", header_id]);
+    assert_eq!(original["result"]["total"], 2);
+}
+
 #[test]
 #[ignore = "requires strfry relay"]
 fn inspect_event() {
@@ -233,3 +401,365 @@ fn delete_event() {
         );
     }
 }
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn create_header_fans_out_to_every_relay() {
+    let ctx = TestContext::new();
+    ctx.init();
+
+    let relays = format!("--relay={},ws://127.0.0.1:1", ctx.relay);
+    let header = ctx.run_ok(&[
+        "create-header",
+        "--name=fanout",
+        "--plural=fanouts",
+        &relays,
+    ]);
+    let reports = header["result"]["relays"]
+        .as_array()
+        .expect("relays should be an array");
+
+    assert_eq!(reports.len(), 2, "one report per relay");
+    assert_eq!(reports[0]["status"], "accepted");
+    assert_eq!(reports[1]["status"], "unreachable");
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn list_items_reports_relays_each_item_was_seen_on() {
+    let ctx = TestContext::new();
+    ctx.init();
+
+    let header = ctx.run_ok(&["create-header", "--name=merged", "--plural=mergeds"]);
+    let header_id = header["result"]["event_id"].as_str().expect("event_id");
+    ctx.run_ok(&[
+        "add-item",
+        &format!("--header={header_id}"),
+        "--resource=https://example.com/merged",
+    ]);
+
+    let relays = format!("--relay={},ws://127.0.0.1:1", ctx.relay);
+    let items = ctx.run_ok(&["list-items", header_id, &relays]);
+    let result = &items["result"];
+    assert_eq!(result["unreachable_relays"][0], "ws://127.0.0.1:1");
+    assert_eq!(result["items"][0]["relays"][0], ctx.relay.as_str());
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn sign_only_then_broadcast_unchanged() {
+    let ctx = TestContext::new();
+    ctx.init();
+
+    let signed = ctx.run_ok(&[
+        "create-header",
+        "--name=offline",
+        "--plural=offlines",
+        "--sign-only",
+        "--relay=ws://127.0.0.1:1",
+    ]);
+    assert_eq!(signed["result"]["signed_only"], true);
+    let event_id = signed["result"]["event_id"].as_str().expect("event_id");
+    assert!(signed["result"].get("relays").is_none(), "nothing sent");
+
+    let path = ctx.home.path().join("signed.json");
+    std::fs::write(&path, signed.to_string()).expect("write signed envelope");
+
+    let sent = ctx.run_ok(&["broadcast", path.to_str().unwrap()]);
+    assert_eq!(sent["result"]["event_id"], event_id);
+    assert_eq!(sent["result"]["relays"][0]["status"], "accepted");
+
+    let inspected = ctx.run_ok(&["inspect", event_id]);
+    assert_eq!(inspected["result"]["event_id"], event_id);
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn publish_batch_from_jsonl() {
+    let ctx = TestContext::new();
+    ctx.init();
+
+    let input = ctx.home.path().join("batch.jsonl");
+    std::fs::write(
+        &input,
+        "{\"kind\": 1, \"content\": \"one\"}\n\
+         {\"kind\": 9998, \"tags\": [[\"names\", \"only-singular\"]]}\n\
+         {\"kind\": 1, \"content\": \"two\"}\n",
+    )
+    .unwrap();
+
+    let json = ctx.run_ok(&[
+        "publish",
+        &format!("--relay={}", ctx.relay),
+        input.to_str().unwrap(),
+    ]);
+    let result = &json["result"];
+    assert_eq!(result["count"], 3);
+    assert_eq!(result["succeeded"], 2);
+    assert_eq!(result["results"][1]["error"]["code"], "NAMES_INCOMPLETE");
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn add_items_from_csv() {
+    let ctx = TestContext::new();
+    ctx.init();
+
+    let header = ctx.run_ok(&[
+        "create-header",
+        &format!("--relay={}", ctx.relay),
+        "--name=album",
+        "--plural=albums",
+    ]);
+    let header_id = header["result"]["event_id"].as_str().unwrap();
+
+    let csv = ctx.home.path().join("albums.csv");
+    std::fs::write(
+        &csv,
+        "resource,title\nhttps://example.com/a,A\n,missing resource\nhttps://example.com/c,C\n",
+    )
+    .unwrap();
+
+    let json = ctx.run_ok(&[
+        "add-item",
+        &format!("--relay={}", ctx.relay),
+        &format!("--header={header_id}"),
+        &format!("--from={}", csv.display()),
+    ]);
+    let result = &json["result"];
+    assert_eq!(result["count"], 3);
+    assert_eq!(result["succeeded"], 2);
+    assert_eq!(result["results"][1]["error"]["code"], "INVALID_ROW");
+
+    let listed = ctx.run_ok(&["list-items", &format!("--relay={}", ctx.relay), header_id]);
+    assert_eq!(listed["result"]["count"], 2);
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn list_items_paginates_with_total() {
+    let ctx = TestContext::new();
+    ctx.init();
+
+    let header = ctx.run_ok(&[
+        "create-header",
+        &format!("--relay={}", ctx.relay),
+        "--name=page",
+        "--plural=pages",
+    ]);
+    let header_id = header["result"]["event_id"].as_str().unwrap();
+
+    let csv = ctx.home.path().join("pages.csv");
+    let rows: Vec<String> = (0..5).map(|i| format!("https://example.com/{i}")).collect();
+    std::fs::write(&csv, format!("resource\n{}\n", rows.join("\n"))).unwrap();
+    ctx.run_ok(&[
+        "add-item",
+        &format!("--relay={}", ctx.relay),
+        &format!("--header={header_id}"),
+        &format!("--from={}", csv.display()),
+    ]);
+
+    let json = ctx.run_ok(&[
+        "list-items",
+        &format!("--relay={}", ctx.relay),
+        header_id,
+        "--offset=2",
+        "--limit=2",
+    ]);
+    let result = &json["result"];
+    assert_eq!(result["total"], 5);
+    assert_eq!(result["count"], 2);
+    assert_eq!(result["has_more"], true);
+    let commands: Vec<&str> = json["next_actions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|a| a["command"].as_str())
+        .collect();
+    assert!(commands.iter().any(|c| c.ends_with("--offset=4 --limit=2")));
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn export_then_import_skips_existing_events() {
+    let ctx = TestContext::new();
+    ctx.init();
+    ctx.run_ok(&[
+        "create-header",
+        &format!("--relay={}", ctx.relay),
+        "--name=backup",
+        "--plural=backups",
+    ]);
+
+    let exported = ctx.run_ok(&["export", &format!("--relay={}", ctx.relay)]);
+    let path = ctx.home.path().join("backup.json");
+    std::fs::write(&path, exported.to_string()).unwrap();
+
+    let json = ctx.run_ok(&[
+        "import",
+        &format!("--relay={}", ctx.relay),
+        path.to_str().unwrap(),
+    ]);
+    let counts = &json["result"]["counts"];
+    assert_eq!(counts["imported"], 0);
+    assert_eq!(counts["failed"], 0);
+    assert_eq!(counts["skipped"], counts["events"]);
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn export_streams_jsonl_to_file() {
+    let ctx = TestContext::new();
+    ctx.init();
+    ctx.run_ok(&[
+        "create-header",
+        &format!("--relay={}", ctx.relay),
+        "--name=stream",
+        "--plural=streams",
+    ]);
+
+    let path = ctx.home.path().join("backup.jsonl");
+    let json = ctx.run_ok(&[
+        "export",
+        &format!("--relay={}", ctx.relay),
+        &format!("--output={}", path.display()),
+    ]);
+    let result = &json["result"];
+    assert_eq!(result["format"], "jsonl");
+    assert!(result.get("headers").is_none());
+
+    let lines = std::fs::read_to_string(&path).unwrap();
+    let written = lines.lines().count() as u64;
+    let counts = &result["counts"];
+    assert_eq!(
+        written,
+        counts["headers"].as_u64().unwrap() + counts["items"].as_u64().unwrap()
+    );
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn export_scoped_to_one_header() {
+    let ctx = TestContext::new();
+    ctx.init();
+    let relay = format!("--relay={}", ctx.relay);
+    let wanted = ctx.run_ok(&["create-header", &relay, "--name=wanted", "--plural=wanteds"]);
+    ctx.run_ok(&["create-header", &relay, "--name=other", "--plural=others"]);
+    let wanted_id = wanted["result"]["event_id"].as_str().unwrap();
+
+    let json = ctx.run_ok(&["export", &relay, &format!("--header={wanted_id}")]);
+    let result = &json["result"];
+    assert_eq!(result["counts"]["headers"], 1);
+    assert_eq!(result["headers"][0]["header"]["event_id"], wanted_id);
+    assert_eq!(result["scope"]["header"], wanted_id);
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn export_list_renders_csv_from_header_fields() {
+    let ctx = TestContext::new();
+    ctx.init();
+    let relay = format!("--relay={}", ctx.relay);
+    let header = ctx.run_ok(&[
+        "create-header",
+        &relay,
+        "--name=album",
+        "--plural=albums",
+        "--required=title",
+        "--recommended=year",
+    ]);
+    let header_id = header["result"]["event_id"].as_str().unwrap();
+    ctx.run_ok(&[
+        "add-item",
+        &relay,
+        &format!("--header={header_id}"),
+        "--resource=https://example.com/kob",
+        "--fields=title=Kind of Blue,year=1959",
+    ]);
+
+    let json = ctx.run_ok(&["export-list", &relay, header_id]);
+    let rendered = json["result"]["rendered"].as_str().unwrap();
+    assert_eq!(
+        rendered,
+        "resource,title,year\nhttps://example.com/kob,Kind of Blue,1959\n"
+    );
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn validate_list_fails_on_duplicate_resources() {
+    let ctx = TestContext::new();
+    ctx.init();
+    let header = ctx.run_ok(&[
+        "create-header",
+        "--name=album",
+        "--plural=albums",
+        "--required=title",
+    ]);
+    let header_id = header["result"]["event_id"].as_str().unwrap();
+    let add = |title: &str| {
+        ctx.run_ok(&[
+            "add-item",
+            &format!("--header={header_id}"),
+            "--resource=https://example.com/kob",
+            &format!("--fields=title={title}"),
+        ])
+    };
+    add("Kind of Blue");
+
+    let valid = ctx.run_ok(&["validate-list", header_id]);
+    assert_eq!(valid["result"]["valid"], true);
+    assert_eq!(valid["result"]["total"], 1);
+
+    add("Kind of Blue (Legacy Edition)");
+    let invalid = ctx.run(&["validate-list", header_id]);
+    assert_eq!(invalid["ok"], false);
+    assert_eq!(invalid["error"]["code"], "LIST_INVALID");
+    assert!(
+        invalid["error"]["message"]
+            .as_str()
+            .is_some_and(|m| m.contains("DUPLICATE_RESOURCE"))
+    );
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn export_sync_appends_only_new_events() {
+    let ctx = TestContext::new();
+    ctx.init();
+    let relay = format!("--relay={}", ctx.relay);
+    let path = ctx.home.path().join("sync.jsonl");
+    let output = format!("--output={}", path.display());
+    let author = format!("--author={}", ctx.pubkey());
+
+    ctx.run_ok(&["create-header", &relay, "--name=first", "--plural=firsts"]);
+    ctx.run_ok(&["export", &relay, &author, &output, "--sync"]);
+    let before = std::fs::read_to_string(&path).unwrap().lines().count();
+
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    ctx.run_ok(&["create-header", &relay, "--name=second", "--plural=seconds"]);
+    let json = ctx.run_ok(&["export", &relay, &author, &output, "--sync"]);
+    assert_eq!(json["result"]["sync"]["appended"], true);
+    assert_eq!(json["result"]["counts"]["headers"], 1);
+
+    let after = std::fs::read_to_string(&path).unwrap().lines().count();
+    assert_eq!(after, before + 1);
+}
+
+#[test]
+#[ignore = "requires strfry relay"]
+fn export_sync_to_a_relative_output_is_incremental() {
+    let ctx = TestContext::new();
+    ctx.init();
+    let relay = format!("--relay={}", ctx.relay);
+    let author = format!("--author={}", ctx.pubkey());
+
+    ctx.run_ok(&["create-header", &relay, "--name=first", "--plural=firsts"]);
+    let first = ctx.run_ok(&["export", &relay, &author, "--output=backup.jsonl", "--sync"]);
+    assert_eq!(first["result"]["sync"]["appended"], false);
+
+    let second = ctx.run_ok(&["export", &relay, &author, "--output=backup.jsonl", "--sync"]);
+    assert_eq!(second["result"]["sync"]["appended"], true);
+    assert_eq!(second["result"]["counts"]["headers"], 0);
+    assert!(ctx.home.path().join("backup.jsonl").exists());
+}